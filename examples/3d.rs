@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use bevy::prelude::*;
 use bracket_fast_noise::prelude::*;
 
@@ -7,7 +5,8 @@ use bracket_fast_noise::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use worley_biomes::{
-    bevy::debug_plugin::{DebugColor, DebugPluginSettings, GetWorley, WorleyImage},
+    bevy::debug_plugin::{DebugColor, DebugHeight, DebugPluginSettings, GetWorley, WorleyImage},
+    bevy::terrain_mesh::{TerrainMesh, VertexWeights, build_terrain_mesh},
     biome_picker::{BiomeVariants, SimpleBiomePicker},
     distance_fn::DistanceFn,
     worley::Worley,
@@ -43,8 +42,9 @@ impl DebugColor<BiomeType> for BiomeType {
     }
 }
 
-impl BiomeType {
-    fn height(&self) -> f32 {
+// the terrain mesh blends these per vertex
+impl DebugHeight for BiomeType {
+    fn get_height(&self) -> f32 {
         match self {
             BiomeType::Desert => 0.0,
             BiomeType::Forest => 10.0,
@@ -59,27 +59,30 @@ fn main() {
         .add_plugins(DefaultPlugins)
         .add_plugins(EguiPlugin::default())
         // THE DEBUG PLUGIN for worley preview + tweak ui
-        .add_plugins(worley_biomes::bevy::debug_plugin::DebugPlugin::<
-            WorleyHolder,
-            BiomeType,
-            SimpleBiomePicker<BiomeType>,
-        > {
-            // you can customize some parts of the tweak ui
-            settings: DebugPluginSettings {
-                spawn_preview_image: true,
-                show_preview_image: true,
-                show_inspector_ui: true,
-            },
-            ..default()
-        })
-        .insert_resource(VoxelMaterials(HashMap::new()))
+        .add_plugins(
+            worley_biomes::bevy::debug_plugin::DebugPlugin::<
+                WorleyHolder,
+                BiomeType,
+                SimpleBiomePicker<BiomeType>,
+            > {
+                // you can customize some parts of the tweak ui
+                settings: DebugPluginSettings {
+                    spawn_preview_image: true,
+                    show_preview_image: true,
+                    show_inspector_ui: true,
+                    ..default()
+                },
+                ..default()
+            }
+            // lets the preview show heights (the terrain heights) in the inspector
+            .with_heights(),
+        )
         .insert_resource(Offset { x: 0.0, z: 0.0 })
         .add_systems(Startup, setup)
-        .add_systems(Startup, setup_voxels)
-        .add_systems(PostUpdate, update_voxel_from_worley)
+        .add_systems(Startup, setup_terrain)
+        .add_systems(PostUpdate, update_terrain_from_worley)
         .add_systems(Update, move_input)
         .add_systems(Update, toggle_preview_visibility)
-        .add_systems(Update, animate_height)
         .run();
 }
 
@@ -99,19 +102,12 @@ impl GetWorley<BiomeType, SimpleBiomePicker<BiomeType>> for WorleyHolder {
     }
 }
 
-///! avoid duplication of same color voxel material
-#[derive(Resource)]
-pub struct VoxelMaterials(HashMap<(u8, u8, u8), Handle<StandardMaterial>>);
-
-///! how many voxels to generate
-pub const GRID_SIZE: i32 = 32 * 4;
+///! how many quads along each side of the terrain
+pub const GRID_SIZE: u32 = 32 * 4;
 
-///! store the voxel x,z pos to later find the correct voxel to update
+///! the single terrain mesh, rebuilt when the worley changes
 #[derive(Component)]
-struct VoxelCoord {
-    gx: i32,
-    gz: i32,
-}
+struct Terrain;
 
 // tap space to show/hide the preview image and tweak ui
 fn toggle_preview_visibility(
@@ -126,89 +122,48 @@ fn toggle_preview_visibility(
     debug_plugin_settings.show_inspector_ui = debug_plugin_settings.show_preview_image;
 }
 
-///! initially spawn all "voxels"
-fn setup_voxels(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
-    // one shared cube mesh
-    let cube_mesh = meshes.add(Mesh::from(Cuboid::new(1.0, 1.0, 1.0)));
-
-    for gx in 0..GRID_SIZE {
-        for gz in 0..GRID_SIZE {
-            commands.spawn((
-                VoxelCoord { gx, gz },
-                Mesh3d(cube_mesh.clone()),
-                // assign a default material for now, will be updated later
-                MeshMaterial3d(Handle::<StandardMaterial>::default()),
-                Transform::from_translation(Vec3::new(
-                    gx as f32 - GRID_SIZE as f32 / 2.0,
-                    0.0,
-                    gz as f32 - GRID_SIZE as f32 / 2.0,
-                )),
-                TargetHeight(0.0f32),
-            ));
-        }
-    }
+///! spawn the terrain entity, its mesh is built in update_terrain_from_worley
+fn setup_terrain(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        Terrain,
+        Mesh3d(meshes.add(Mesh::from(Plane3d::default()))),
+        // white, the vertex colors show the biomes
+        MeshMaterial3d(materials.add(Color::WHITE)),
+        Transform::from_translation(Vec3::new(
+            -(GRID_SIZE as f32) / 2.0,
+            0.0,
+            -(GRID_SIZE as f32) / 2.0,
+        )),
+    ));
 }
 
-///! what's the generated height of the "voxel"
-#[derive(Component)]
-pub struct TargetHeight(f32);
-
-///! fetch worley data to UPDATE the voxel height + material
-fn update_voxel_from_worley(
+///! rebuild the blended terrain mesh around the offset
+fn update_terrain_from_worley(
     worley_holder: Res<WorleyHolder>,
-    mut voxels: Query<(
-        &VoxelCoord,
-        &mut MeshMaterial3d<StandardMaterial>,
-        &mut TargetHeight,
-    )>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut voxel_materials: ResMut<VoxelMaterials>,
+    terrain: Query<&Mesh3d, With<Terrain>>,
+    mut meshes: ResMut<Assets<Mesh>>,
     offset: Res<Offset>,
 ) {
     if !worley_holder.is_changed() {
         return;
     }
-
-    let worley = &worley_holder.worley;
-
-    for (coord, mut mat, mut target_height) in voxels.iter_mut() {
-        let gx = coord.gx;
-        let gz = coord.gz;
-        let weights = worley.get(gx as f64 + offset.x, gz as f64 + offset.z);
-        // blend colors
-        let mut r = 0.0;
-        let mut g = 0.0;
-        let mut b = 0.0;
-        let mut height = 0.0;
-        let mut wsum = 0.0;
-        for (w, biome) in &weights {
-            let c = biome.get_color();
-            r += c.red as f64 * w;
-            g += c.green as f64 * w;
-            b += c.blue as f64 * w;
-            height += biome.height() * *w as f32;
-            wsum += w;
-        }
-        let color = Srgba::new(r as f32, g as f32, b as f32, 1.0);
-        let (color, key) = quantize_srgba(color, 32);
-        let color_material = voxel_materials
-            .0
-            .entry(key)
-            .or_insert_with(|| materials.add(Color::Srgba(color)));
-
-        *mat = MeshMaterial3d(color_material.clone());
-        target_height.0 = height;
-    }
-}
-
-///! move voxels scale to their target height
-fn animate_height(mut query: Query<(&mut Transform, &TargetHeight)>, time: Res<Time>) {
-    for (mut transform, target_height) in query.iter_mut() {
-        transform.scale.y = transform
-            .scale
-            .y
-            .lerp(target_height.0, time.delta_secs() * 7.0);
-    }
+    let Ok(mesh3d) = terrain.single() else {
+        return;
+    };
+    let min = Vec2::new(offset.x as f32, offset.z as f32);
+    let terrain_mesh = TerrainMesh::new(
+        Rect::from_corners(min, min + Vec2::splat(GRID_SIZE as f32)),
+        UVec2::splat(GRID_SIZE),
+    )
+    .with_weights(VertexWeights::Color(BiomeType::get_color));
+    let _ = meshes.insert(
+        &mesh3d.0,
+        build_terrain_mesh(&worley_holder.worley, &terrain_mesh),
+    );
 }
 
 ///! offset the worley position, so we can move around
@@ -262,7 +217,6 @@ fn setup(mut commands: Commands) {
     worley.sharpness = 20.0;
     worley.k = 3;
     worley.warp_settings.strength = 0.6;
-    worley.warp_settings.noise.set_seed(0);
     worley.warp_settings.noise.frequency = 0.7;
     worley.warp_settings.noise.fractal_lacunarity = 2.0;
     worley.warp_settings.noise.set_fractal_gain(0.6);
@@ -287,31 +241,3 @@ fn setup(mut commands: Commands) {
         Transform::from_xyz(-2.5, 77.5, -114.0).looking_at(Vec3::ZERO, Vec3::Y),
     ));
 }
-
-/// Quantize an Srgba color so each component is divisible by `step`.
-/// Example: step = 32 → each channel maps into {0, 32, 64, …, 224, 255}
-/// returns the color + rgb values you can use for a hash key
-fn quantize_srgba(color: Srgba, step: u8) -> (Srgba, (u8, u8, u8)) {
-    // convert float [0.0..1.0] into byte [0..255]
-    let r = (color.red * 255.0).round().clamp(0.0, 255.0) as u8;
-    let g = (color.green * 255.0).round().clamp(0.0, 255.0) as u8;
-    let b = (color.blue * 255.0).round().clamp(0.0, 255.0) as u8;
-
-    // quantize each component down to nearest multiple of `step`
-    let rq = (r / step) * step;
-    let gq = (g / step) * step;
-    let bq = (b / step) * step;
-    let aq = 255;
-
-    let key = (rq, gq, bq);
-    // back to normalized floats
-    (
-        Srgba::new(
-            rq as f32 / 255.0,
-            gq as f32 / 255.0,
-            bq as f32 / 255.0,
-            aq as f32 / 255.0,
-        ),
-        key,
-    )
-}
@@ -52,13 +52,12 @@ pub const GRID_SIZE: i32 = 32 * 4;
 fn setup(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
     let mut worley: Worley<BiomeType, SimpleBiomePicker<BiomeType>> = Worley::default();
     worley.zoom = 22.0;
-    worley.seed = 12345;
+    worley.set_seed(12345);
     worley.set_distance_fn(DistanceFn::Chebyshev);
     worley.biome_picker = SimpleBiomePicker::Any;
     worley.sharpness = 20.0;
     worley.k = 3;
     worley.warp_settings.strength = 0.6;
-    worley.warp_settings.noise.set_seed(0);
     worley.warp_settings.noise.frequency = 0.7;
     worley.warp_settings.noise.fractal_lacunarity = 2.0;
     worley.warp_settings.noise.set_fractal_gain(0.6);
@@ -1 +1,22 @@
+#[cfg(feature = "serde")]
+pub mod asset;
+pub mod changed;
+pub mod chunk_cache;
+pub mod chunk_plugin;
+#[cfg(feature = "bevy")]
 pub mod debug_plugin;
+#[cfg(feature = "debug-gizmos")]
+pub mod gizmos;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod noise_reflect;
+pub mod preview;
+pub mod query;
+pub mod resource;
+pub mod sets;
+pub mod splatmap;
+pub mod terrain_mesh;
+#[cfg(feature = "tilemap")]
+pub mod tilemap;
+pub mod traits;
+pub mod worley_plugin;
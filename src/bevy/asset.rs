@@ -0,0 +1,236 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, UntypedAssetId, VisitAssetDependencies, io::Reader},
+    prelude::*,
+    reflect::{TypePath, utility::GenericTypePathCell},
+};
+use serde::Deserialize;
+
+use crate::{
+    bevy::{changed::WorleyChangedPlugin, sets::WorleySet, traits::GetWorley},
+    biome_picker::{BiomePicker, BiomeVariants},
+    worley::Worley,
+};
+
+///! a Worley loaded through the AssetServer from a `.worley.ron` file
+///! (the same format the debug plugin saves)
+pub struct WorleyAsset<BiomeT, Picker>(pub Worley<BiomeT, Picker>)
+where
+    BiomeT: BiomeVariants,
+    Picker: BiomePicker<BiomeT> + Default;
+
+// generic over user types that are not TypePath, so the path is built from std::any::type_name
+impl<BiomeT, Picker> TypePath for WorleyAsset<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + 'static,
+    Picker: BiomePicker<BiomeT> + Default + 'static,
+{
+    fn type_path() -> &'static str {
+        static CELL: GenericTypePathCell = GenericTypePathCell::new();
+        CELL.get_or_insert::<Self, _>(|| {
+            format!(
+                "worley_biomes::bevy::asset::WorleyAsset<{}, {}>",
+                std::any::type_name::<BiomeT>(),
+                std::any::type_name::<Picker>()
+            )
+        })
+    }
+
+    fn short_type_path() -> &'static str {
+        static CELL: GenericTypePathCell = GenericTypePathCell::new();
+        CELL.get_or_insert::<Self, _>(|| {
+            format!(
+                "WorleyAsset<{}, {}>",
+                std::any::type_name::<BiomeT>(),
+                std::any::type_name::<Picker>()
+            )
+        })
+    }
+}
+
+impl<BiomeT, Picker> VisitAssetDependencies for WorleyAsset<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    fn visit_dependencies(&self, _visit: &mut impl FnMut(UntypedAssetId)) {}
+}
+
+impl<BiomeT, Picker> Asset for WorleyAsset<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+}
+
+#[derive(Debug)]
+pub enum WorleyAssetLoaderError {
+    Io(std::io::Error),
+    Ron(ron::de::SpannedError),
+    ///! still invalid after sanitizing, see Worley::sanitize
+    Invalid(String),
+}
+
+impl std::fmt::Display for WorleyAssetLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorleyAssetLoaderError::Io(err) => write!(f, "could not read worley file: {}", err),
+            WorleyAssetLoaderError::Ron(err) => write!(f, "could not parse worley file: {}", err),
+            WorleyAssetLoaderError::Invalid(err) => write!(f, "invalid worley file: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for WorleyAssetLoaderError {}
+
+impl From<std::io::Error> for WorleyAssetLoaderError {
+    fn from(err: std::io::Error) -> Self {
+        WorleyAssetLoaderError::Io(err)
+    }
+}
+
+impl From<ron::de::SpannedError> for WorleyAssetLoaderError {
+    fn from(err: ron::de::SpannedError) -> Self {
+        WorleyAssetLoaderError::Ron(err)
+    }
+}
+
+///! loads `.worley.ron` files into WorleyAsset
+pub struct WorleyAssetLoader<BiomeT, Picker> {
+    pub _phantom: PhantomData<fn() -> (BiomeT, Picker)>,
+}
+
+impl<BiomeT, Picker> Default for WorleyAssetLoader<BiomeT, Picker> {
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<BiomeT: 'static, Picker: 'static> TypePath for WorleyAssetLoader<BiomeT, Picker> {
+    fn type_path() -> &'static str {
+        static CELL: GenericTypePathCell = GenericTypePathCell::new();
+        CELL.get_or_insert::<Self, _>(|| {
+            format!(
+                "worley_biomes::bevy::asset::WorleyAssetLoader<{}, {}>",
+                std::any::type_name::<BiomeT>(),
+                std::any::type_name::<Picker>()
+            )
+        })
+    }
+
+    fn short_type_path() -> &'static str {
+        static CELL: GenericTypePathCell = GenericTypePathCell::new();
+        CELL.get_or_insert::<Self, _>(|| {
+            format!(
+                "WorleyAssetLoader<{}, {}>",
+                std::any::type_name::<BiomeT>(),
+                std::any::type_name::<Picker>()
+            )
+        })
+    }
+}
+
+impl<BiomeT, Picker> AssetLoader for WorleyAssetLoader<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static + for<'de> Deserialize<'de>,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static + for<'de> Deserialize<'de>,
+{
+    type Asset = WorleyAsset<BiomeT, Picker>;
+    type Settings = ();
+    type Error = WorleyAssetLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        // deserializing rebuilds derived state, see Worley's serde(from)
+        let mut worley = ron::de::from_bytes::<Worley<BiomeT, Picker>>(&bytes)?;
+        // hand edited files (hot reload) load clamped, with a warning instead of failing
+        for warning in worley.sanitize() {
+            warn!("{}: {}", load_context.asset_path(), warning);
+        }
+        worley.validate().map_err(WorleyAssetLoaderError::Invalid)?;
+        Ok(WorleyAsset(worley))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["worley.ron"]
+    }
+}
+
+///! the asset WorleyAssetPlugin keeps copied into WorleyResT
+#[derive(Resource)]
+pub struct WorleyAssetHandle<BiomeT, Picker>(pub Handle<WorleyAsset<BiomeT, Picker>>)
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static;
+
+///! registers WorleyAsset and its loader.
+///! insert a WorleyAssetHandle (e.g. `asset_server.load("my_world.worley.ron")`) and the Worley of
+///! WorleyResT is replaced whenever the asset (re)loads. with bevy's "file_watcher" feature,
+///! saving the file on disk hot reloads it.
+///! like any deserialized Worley, custom_warp and warp backends are not part of the file
+pub struct WorleyAssetPlugin<WorleyResT, BiomeT, Picker> {
+    pub _phantom: PhantomData<fn() -> (WorleyResT, BiomeT, Picker)>,
+}
+
+impl<WorleyResT, BiomeT, Picker> Default for WorleyAssetPlugin<WorleyResT, BiomeT, Picker> {
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<WorleyResT, BiomeT, Picker> Plugin for WorleyAssetPlugin<WorleyResT, BiomeT, Picker>
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Send + Sync + 'static + for<'de> Deserialize<'de>,
+    Picker:
+        BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static + for<'de> Deserialize<'de>,
+{
+    fn build(&self, app: &mut App) {
+        app.init_asset::<WorleyAsset<BiomeT, Picker>>();
+        WorleyChangedPlugin::<WorleyResT, BiomeT, Picker>::add_once(app);
+        app.register_asset_loader(WorleyAssetLoader::<BiomeT, Picker>::default());
+        app.add_systems(
+            PreUpdate,
+            apply_worley_asset::<WorleyResT, BiomeT, Picker>.in_set(WorleySet::ApplyAsset),
+        );
+    }
+}
+
+///! copy the asset into WorleyResT when it finished loading or changed on disk
+fn apply_worley_asset<WorleyResT, BiomeT, Picker>(
+    mut events: MessageReader<AssetEvent<WorleyAsset<BiomeT, Picker>>>,
+    handle: Option<Res<WorleyAssetHandle<BiomeT, Picker>>>,
+    assets: Res<Assets<WorleyAsset<BiomeT, Picker>>>,
+    mut map_settings: ResMut<WorleyResT>,
+) where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static,
+{
+    let Some(handle) = handle else {
+        events.clear();
+        return;
+    };
+    let mut reload = handle.is_changed();
+    for event in events.read() {
+        reload |= event.is_loaded_with_dependencies(&handle.0) || event.is_modified(&handle.0);
+    }
+    if !reload {
+        return;
+    }
+    if let Some(asset) = assets.get(&handle.0) {
+        *map_settings.get_worley_mut() = asset.0.clone();
+        info!("applied worley asset {:?}", handle.0.path());
+    }
+}
@@ -0,0 +1,86 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+use crate::{
+    bevy::{sets::WorleySet, traits::GetWorley},
+    biome_picker::{BiomePicker, BiomeVariants},
+};
+
+///! sent when the Worley of the watched resource changes what it generates
+///! (inspector edits, loaded files, ..), with its new Worley::config_hash
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorleyChanged {
+    pub config_hash: u64,
+}
+
+///! sends WorleyChanged for WorleyResT. added by the DebugPlugin and WorleyAssetPlugin,
+///! add it yourself if you use neither
+pub struct WorleyChangedPlugin<WorleyResT, BiomeT, Picker> {
+    pub _phantom: PhantomData<fn() -> (WorleyResT, BiomeT, Picker)>,
+}
+
+impl<WorleyResT, BiomeT, Picker> Default for WorleyChangedPlugin<WorleyResT, BiomeT, Picker> {
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<WorleyResT, BiomeT, Picker> WorleyChangedPlugin<WorleyResT, BiomeT, Picker>
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    ///! add the plugin, unless another plugin already did
+    pub fn add_once(app: &mut App) {
+        if !app.is_plugin_added::<Self>() {
+            app.add_plugins(Self::default());
+        }
+    }
+}
+
+impl<WorleyResT, BiomeT, Picker> Plugin for WorleyChangedPlugin<WorleyResT, BiomeT, Picker>
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        app.add_message::<WorleyChanged>();
+        app.add_systems(
+            Last,
+            send_worley_changed::<WorleyResT, BiomeT, Picker>.in_set(WorleySet::DetectChanges),
+        );
+    }
+}
+
+///! resource change detection also fires for writes that change nothing (e.g. set_changed),
+///! so only send when the config hash actually differs
+fn send_worley_changed<WorleyResT, BiomeT, Picker>(
+    map_settings: Option<Res<WorleyResT>>,
+    mut last_hash: Local<Option<u64>>,
+    mut changed: MessageWriter<WorleyChanged>,
+) where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    let Some(map_settings) = map_settings else {
+        return;
+    };
+    if !map_settings.is_changed() {
+        return;
+    }
+    let config_hash = map_settings.get_worley().config_hash();
+    if *last_hash == Some(config_hash) {
+        return;
+    }
+    // the first hash is the initial state, not a change
+    if last_hash.is_some() {
+        changed.write(WorleyChanged { config_hash });
+    }
+    *last_hash = Some(config_hash);
+}
@@ -0,0 +1,157 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{
+    bevy::{
+        changed::{WorleyChanged, WorleyChangedPlugin},
+        sets::WorleySet,
+        traits::GetWorley,
+    },
+    biome_picker::{BiomePicker, BiomeVariants},
+    chunk::BiomeChunk,
+    worley::Worley,
+};
+
+///! memoized BiomeChunks keyed by (chunk coord, config hash), all of one size, spacing and warp_step.
+///! WorleyChunkCachePlugin clears it on WorleyChanged, so stale chunks don't pile up
+#[derive(Resource)]
+pub struct WorleyChunkCache<BiomeT: Default + Send + Sync + 'static> {
+    ///! samples per chunk side
+    pub size: u32,
+    ///! world units between samples
+    pub spacing: f64,
+    ///! warp_step chunks are generated with (BiomeChunk::generate_interpolated), 1: exact.
+    ///! clear() after changing it
+    pub warp_step: u32,
+    chunks: HashMap<(IVec2, u64), Arc<BiomeChunk<BiomeT>>>,
+}
+
+impl<BiomeT: Default + Send + Sync + 'static> Default for WorleyChunkCache<BiomeT> {
+    fn default() -> Self {
+        Self::new(32, 1.0)
+    }
+}
+
+impl<BiomeT: BiomeVariants + Default + Send + Sync + 'static> WorleyChunkCache<BiomeT> {
+    pub fn new(size: u32, spacing: f64) -> Self {
+        Self {
+            size,
+            spacing,
+            warp_step: 1,
+            chunks: HashMap::default(),
+        }
+    }
+
+    ///! the cached chunk, None if it wasn't generated with this config hash
+    pub fn get(&self, coord: IVec2, config_hash: u64) -> Option<Arc<BiomeChunk<BiomeT>>> {
+        self.chunks.get(&(coord, config_hash)).cloned()
+    }
+
+    ///! the cached chunk, generated (on this thread) if missing
+    pub fn get_or_generate<Picker>(
+        &mut self,
+        worley: &Worley<BiomeT, Picker>,
+        coord: IVec2,
+    ) -> Arc<BiomeChunk<BiomeT>>
+    where
+        Picker: BiomePicker<BiomeT> + Default,
+    {
+        let (size, spacing, warp_step) = (self.size, self.spacing, self.warp_step);
+        self.chunks
+            .entry((coord, worley.config_hash()))
+            .or_insert_with(|| {
+                Arc::new(BiomeChunk::generate_interpolated(
+                    worley,
+                    (coord.x, coord.y),
+                    size,
+                    spacing,
+                    warp_step,
+                ))
+            })
+            .clone()
+    }
+
+    ///! cache a chunk generated elsewhere (e.g. by spawn_chunk_task).
+    ///! ignored if its size, spacing or warp_step differ from the cache's, an interpolated chunk
+    ///! would otherwise stand in for an exact one (or the other way around) under the same key
+    pub fn insert(&mut self, chunk: Arc<BiomeChunk<BiomeT>>) {
+        if chunk.size != self.size
+            || chunk.spacing != self.spacing
+            || chunk.warp_step != self.warp_step.max(1)
+        {
+            return;
+        }
+        let key = (IVec2::new(chunk.coord.0, chunk.coord.1), chunk.config_hash);
+        self.chunks.insert(key, chunk);
+    }
+
+    pub fn remove(&mut self, coord: IVec2, config_hash: u64) -> Option<Arc<BiomeChunk<BiomeT>>> {
+        self.chunks.remove(&(coord, config_hash))
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+
+///! inserts a WorleyChunkCache<BiomeT> and clears it whenever WorleyResT's Worley changes
+pub struct WorleyChunkCachePlugin<WorleyResT, BiomeT, Picker> {
+    ///! samples per chunk side
+    pub size: u32,
+    ///! world units between samples
+    pub spacing: f64,
+    ///! see WorleyChunkCache::warp_step
+    pub warp_step: u32,
+    pub _phantom: PhantomData<fn() -> (WorleyResT, BiomeT, Picker)>,
+}
+
+impl<WorleyResT, BiomeT, Picker> Default for WorleyChunkCachePlugin<WorleyResT, BiomeT, Picker> {
+    fn default() -> Self {
+        Self {
+            size: 32,
+            spacing: 1.0,
+            warp_step: 1,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<WorleyResT, BiomeT, Picker> Plugin for WorleyChunkCachePlugin<WorleyResT, BiomeT, Picker>
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Default + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        WorleyChangedPlugin::<WorleyResT, BiomeT, Picker>::add_once(app);
+        let mut cache = WorleyChunkCache::<BiomeT>::new(self.size, self.spacing);
+        cache.warp_step = self.warp_step;
+        app.insert_resource(cache);
+        app.add_systems(
+            PreUpdate,
+            clear_chunk_cache::<BiomeT>.in_set(WorleySet::ClearCache),
+        );
+    }
+}
+
+// WorleyChanged is sent in Last, so the cache is empty before the next frame's systems run
+fn clear_chunk_cache<BiomeT>(
+    mut changed: MessageReader<WorleyChanged>,
+    mut cache: ResMut<WorleyChunkCache<BiomeT>>,
+) where
+    BiomeT: BiomeVariants + Default + Send + Sync + 'static,
+{
+    if changed.read().count() > 0 {
+        cache.clear();
+    }
+}
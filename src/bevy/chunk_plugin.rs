@@ -0,0 +1,274 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use bevy::{
+    platform::collections::{HashMap, HashSet},
+    prelude::*,
+    tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future},
+};
+
+use crate::{
+    bevy::{
+        changed::{WorleyChanged, WorleyChangedPlugin},
+        sets::WorleySet,
+        traits::GetWorley,
+    },
+    biome_picker::{BiomePicker, BiomeVariants},
+    chunk::BiomeChunk,
+    worley::Worley,
+};
+
+///! chunks are generated around entities with this component (camera, player, ..)
+#[derive(Component, Default)]
+pub struct WorleyChunkAnchor;
+
+#[derive(Resource, Clone)]
+pub struct WorleyChunkSettings {
+    ///! samples per chunk side
+    pub chunk_size: u32,
+    ///! world units between samples
+    pub spacing: f64,
+    ///! chunks within this many chunks (chebyshev) of an anchor are generated
+    pub load_radius: i32,
+    ///! chunks further than this from every anchor are despawned, keep it >= load_radius
+    pub unload_radius: i32,
+    ///! evaluate the warp every warp_step samples and interpolate in between
+    ///! (BiomeChunk::generate_interpolated), 1: exact
+    pub warp_step: u32,
+}
+
+impl Default for WorleyChunkSettings {
+    fn default() -> Self {
+        Self {
+            chunk_size: 32,
+            spacing: 1.0,
+            load_radius: 4,
+            unload_radius: 6,
+            warp_step: 1,
+        }
+    }
+}
+
+///! the generated data of a chunk entity
+#[derive(Component)]
+pub struct WorleyChunk<BiomeT: Default + Send + Sync + 'static>(pub Arc<BiomeChunk<BiomeT>>);
+
+///! chunk coordinate of a chunk entity
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WorleyChunkCoord(pub IVec2);
+
+///! generation in flight, replaced by WorleyChunk when done
+#[derive(Component)]
+pub struct WorleyChunkTask<BiomeT: Default + Send + Sync + 'static>(Task<BiomeChunk<BiomeT>>);
+
+///! chunk coordinate -> chunk entity, for every loaded (or loading) chunk
+#[derive(Resource, Default)]
+pub struct WorleyChunks {
+    pub entities: HashMap<IVec2, Entity>,
+}
+
+// the worley tasks sample, shared until the configuration changes
+#[derive(Resource)]
+struct ChunkWorleySnapshot<BiomeT, Picker>(Arc<Worley<BiomeT, Picker>>)
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static;
+
+///! opt-in streaming: generates BiomeChunks on the async compute pool around every
+///! WorleyChunkAnchor, despawns chunks out of range, and regenerates loaded chunks on WorleyChanged.
+///! chunk entities get a WorleyChunkCoord, a Transform at the chunk origin and,
+///! once generated, a WorleyChunk (query `Added<WorleyChunk<BiomeT>>` to build meshes etc.)
+pub struct WorleyChunkPlugin<WorleyResT, BiomeT, Picker> {
+    pub settings: WorleyChunkSettings,
+    pub _phantom: PhantomData<fn() -> (WorleyResT, BiomeT, Picker)>,
+}
+
+impl<WorleyResT, BiomeT, Picker> Default for WorleyChunkPlugin<WorleyResT, BiomeT, Picker> {
+    fn default() -> Self {
+        Self {
+            settings: WorleyChunkSettings::default(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<WorleyResT, BiomeT, Picker> Plugin for WorleyChunkPlugin<WorleyResT, BiomeT, Picker>
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Default + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        WorleyChangedPlugin::<WorleyResT, BiomeT, Picker>::add_once(app);
+        app.insert_resource(self.settings.clone());
+        app.init_resource::<WorleyChunks>();
+        app.add_systems(
+            Update,
+            (
+                refresh_chunk_snapshot::<WorleyResT, BiomeT, Picker>,
+                stream_chunks::<BiomeT, Picker>,
+                finish_chunk_tasks::<BiomeT>,
+            )
+                .chain()
+                .in_set(WorleySet::ChunkGen),
+        );
+    }
+}
+
+///! take a new snapshot when the configuration changed, and regenerate every loaded chunk
+fn refresh_chunk_snapshot<WorleyResT, BiomeT, Picker>(
+    mut commands: Commands,
+    map_settings: Res<WorleyResT>,
+    snapshot: Option<Res<ChunkWorleySnapshot<BiomeT, Picker>>>,
+    mut changed: MessageReader<WorleyChanged>,
+    settings: Res<WorleyChunkSettings>,
+    chunks: Res<WorleyChunks>,
+) where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Default + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static,
+{
+    let changed = changed.read().count() > 0;
+    if snapshot.is_some() && !changed {
+        return;
+    }
+    let worley = Arc::new(map_settings.get_worley().clone());
+    if changed {
+        for (coord, entity) in chunks.entities.iter() {
+            // the old WorleyChunk stays until the new one is done
+            commands
+                .entity(*entity)
+                .insert(chunk_task(&worley, *coord, &settings));
+        }
+    }
+    commands.insert_resource(ChunkWorleySnapshot(worley));
+}
+
+///! generate a BiomeChunk on the async compute pool, for your own chunk systems
+///! (poll it with `block_on(future::poll_once(&mut task))`). the worley is shared through the
+///! Arc, clone it into one once per configuration rather than per chunk. the chunk's
+///! config_hash tells which configuration a finished chunk was generated with
+///! ```ignore
+///! let worley = Arc::new(map_settings.get_worley().clone());
+///! let task = spawn_chunk_task(worley.clone(), IVec2::new(3, -1), 32, 1.0);
+///! ```
+pub fn spawn_chunk_task<BiomeT, Picker>(
+    worley: Arc<Worley<BiomeT, Picker>>,
+    chunk_coord: IVec2,
+    size: u32,
+    spacing: f64,
+) -> Task<BiomeChunk<BiomeT>>
+where
+    BiomeT: BiomeVariants + Default + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    AsyncComputeTaskPool::get().spawn(async move {
+        BiomeChunk::generate(&worley, (chunk_coord.x, chunk_coord.y), size, spacing)
+    })
+}
+
+fn chunk_task<BiomeT, Picker>(
+    worley: &Arc<Worley<BiomeT, Picker>>,
+    coord: IVec2,
+    settings: &WorleyChunkSettings,
+) -> WorleyChunkTask<BiomeT>
+where
+    BiomeT: BiomeVariants + Default + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    let worley = worley.clone();
+    let (size, spacing, warp_step) = (settings.chunk_size, settings.spacing, settings.warp_step);
+    WorleyChunkTask(AsyncComputeTaskPool::get().spawn(async move {
+        BiomeChunk::generate_interpolated(&worley, (coord.x, coord.y), size, spacing, warp_step)
+    }))
+}
+
+///! spawn chunks entering the load radius of an anchor, despawn chunks outside every unload radius
+fn stream_chunks<BiomeT, Picker>(
+    mut commands: Commands,
+    anchors: Query<&GlobalTransform, With<WorleyChunkAnchor>>,
+    settings: Res<WorleyChunkSettings>,
+    snapshot: Option<Res<ChunkWorleySnapshot<BiomeT, Picker>>>,
+    mut chunks: ResMut<WorleyChunks>,
+) where
+    BiomeT: BiomeVariants + Default + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    let Some(snapshot) = snapshot else {
+        return;
+    };
+    let anchor_coords: Vec<IVec2> = anchors
+        .iter()
+        .map(|transform| {
+            let p = transform.translation();
+            let (cx, cz) = BiomeChunk::<BiomeT>::coord_of(
+                p.x as f64,
+                p.z as f64,
+                settings.chunk_size,
+                settings.spacing,
+            );
+            IVec2::new(cx, cz)
+        })
+        .collect();
+
+    let mut wanted = HashSet::new();
+    let r = settings.load_radius;
+    for anchor in &anchor_coords {
+        for dx in -r..=r {
+            for dz in -r..=r {
+                wanted.insert(*anchor + IVec2::new(dx, dz));
+            }
+        }
+    }
+
+    for coord in wanted {
+        if chunks.entities.contains_key(&coord) {
+            continue;
+        }
+        let (ox, oz) = BiomeChunk::<BiomeT>::origin_of(
+            (coord.x, coord.y),
+            settings.chunk_size,
+            settings.spacing,
+        );
+        let entity = commands
+            .spawn((
+                Name::new(format!("worley_chunk {} {}", coord.x, coord.y)),
+                WorleyChunkCoord(coord),
+                Transform::from_xyz(ox as f32, 0.0, oz as f32),
+                chunk_task(&snapshot.0, coord, &settings),
+            ))
+            .id();
+        chunks.entities.insert(coord, entity);
+    }
+
+    let unload = settings.unload_radius.max(settings.load_radius);
+    chunks.entities.retain(|coord, entity| {
+        let keep = anchor_coords.iter().any(|anchor| {
+            let d = (*coord - *anchor).abs();
+            d.x.max(d.y) <= unload
+        });
+        if !keep {
+            // dropping the task (if still running) cancels it
+            commands.entity(*entity).despawn();
+        }
+        keep
+    });
+}
+
+///! move finished chunk data into WorleyChunk
+fn finish_chunk_tasks<BiomeT>(
+    mut commands: Commands,
+    mut tasks: Query<(Entity, &mut WorleyChunkTask<BiomeT>)>,
+) where
+    BiomeT: BiomeVariants + Default + Send + Sync + 'static,
+{
+    for (entity, mut task) in tasks.iter_mut() {
+        let Some(chunk) = block_on(future::poll_once(&mut task.0)) else {
+            continue;
+        };
+        commands
+            .entity(entity)
+            .remove::<WorleyChunkTask<BiomeT>>()
+            .insert(WorleyChunk(Arc::new(chunk)));
+    }
+}
@@ -1,16 +1,27 @@
 use bevy::prelude::*;
 
-use std::marker::PhantomData;
+use std::{marker::PhantomData, ops::RangeInclusive, sync::Arc};
+
+pub use crate::bevy::preview::{PreviewOverlay, PreviewRender, RenderStats, render_preview};
+pub use crate::bevy::traits::{DebugColor, DebugHeight, GetWorley, debug_color, palette_color};
 
 use crate::{
-    biome_picker::{BiomePicker, BiomeVariants},
+    bevy::changed::WorleyChangedPlugin,
+    bevy::sets::WorleySet,
+    bevy::traits::MaybeSerde,
+    bevy::worley_plugin::register_reflect_types,
+    biome_picker::{BiomePicker, BiomeVariants, SimpleBiomePicker},
     distance_fn::DistanceFn,
-    worley::Worley,
+    warp::{WarpMode, WarpSeed, WarpSettings, WarpSpace},
+    worley::{Worley, WorleyMetadata},
 };
 use bevy::{
     asset::RenderAssetUsages,
     image::ImageSampler,
+    input::mouse::{AccumulatedMouseScroll, MouseScrollUnit},
     render::render_resource::{Extent3d, TextureDimension},
+    tasks::{AsyncComputeTaskPool, Task, block_on, futures_lite::future},
+    ui::RelativeCursorPosition,
 };
 use bevy_inspector_egui::{
     bevy_egui::{self, EguiContext, EguiPrimaryContextPass},
@@ -18,23 +29,161 @@ use bevy_inspector_egui::{
 };
 use bracket_fast_noise::prelude::*;
 
-#[cfg(feature = "serde")]
-use ron::ser::PrettyConfig;
+#[cfg(feature = "gpu")]
+use crate::bevy::gpu::{GpuWorleyJob, GpuWorleyPlugin, gpu_target_image};
 
+mod inspector_window;
+pub use inspector_window::{WorleyInspectorCamera, WorleyInspectorWindowPass};
+mod persistence;
 #[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+pub use persistence::RonPersistence;
+pub use persistence::{DebugPersistence, NoPersistence, WorleyPersistence};
+#[cfg(feature = "persist")]
+mod persist;
+#[cfg(feature = "persist")]
+pub use persist::DebugState;
 
-#[derive(Default)]
 pub struct DebugPlugin<WorleyResT, BiomeT, Picker>
 where
     WorleyResT: Resource + GetWorley<BiomeT, Picker>,
-    BiomeT: BiomeVariants + DebugColor<BiomeT> + std::default::Default,
+    BiomeT: BiomeVariants + DebugColor<BiomeT> + std::fmt::Debug + std::default::Default,
     Picker: BiomePicker<BiomeT> + Default,
 {
     pub settings: DebugPluginSettings,
+    ///! enables PreviewMode::Height, see with_heights
+    pub height_fn: Option<fn(&BiomeT) -> f32>,
+    ///! what the inspector's save/load buttons use, see with_persistence.
+    ///! default: RonPersistence with feature="serde", NoPersistence without.
+    ///! a field of the plugin rather than of DebugPluginSettings, which isn't generic over BiomeT and Picker
+    pub persistence: Arc<dyn WorleyPersistence<BiomeT, Picker>>,
+    ///! pickers listed in the inspector's picker dropdown, see with_picker_preset
+    pub picker_presets: Vec<(String, Picker)>,
+    ///! editor of Picker's parameters, see with_picker_ui
+    pub picker_ui: Option<fn(&mut egui::Ui, &mut Picker) -> bool>,
     pub _phantom: PhantomData<(WorleyResT, BiomeT, Picker)>,
 }
 
+impl<WorleyResT, BiomeT, Picker> Default for DebugPlugin<WorleyResT, BiomeT, Picker>
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants
+        + DebugColor<BiomeT>
+        + std::fmt::Debug
+        + std::default::Default
+        + Send
+        + Sync
+        + 'static
+        + MaybeSerde,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static + MaybeSerde,
+{
+    fn default() -> Self {
+        Self {
+            settings: DebugPluginSettings::default(),
+            height_fn: None,
+            persistence: default_persistence::<BiomeT, Picker>(),
+            picker_presets: Vec::new(),
+            picker_ui: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn default_persistence<BiomeT, Picker>() -> Arc<dyn WorleyPersistence<BiomeT, Picker>>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static + MaybeSerde,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static + MaybeSerde,
+{
+    Arc::new(RonPersistence::default())
+}
+
+#[cfg(not(feature = "serde"))]
+fn default_persistence<BiomeT, Picker>() -> Arc<dyn WorleyPersistence<BiomeT, Picker>>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    Arc::new(NoPersistence)
+}
+
+impl<WorleyResT, BiomeT, Picker> DebugPlugin<WorleyResT, BiomeT, Picker>
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + DebugColor<BiomeT> + std::fmt::Debug + std::default::Default,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    ///! let the preview show DebugHeight as grayscale (PreviewMode::Height)
+    pub fn with_heights(mut self) -> Self
+    where
+        BiomeT: DebugHeight,
+    {
+        self.height_fn = Some(|biome: &BiomeT| biome.get_height());
+        self
+    }
+
+    ///! list picker in the inspector's picker dropdown, to switch to it at runtime
+    pub fn with_picker_preset(mut self, name: impl Into<String>, picker: Picker) -> Self {
+        self.picker_presets.push((name.into(), picker));
+        self
+    }
+
+    ///! edit the parameters of your own Picker in the inspector, return true if it changed.
+    ///! SimpleBiomePicker has a built in editor
+    pub fn with_picker_ui(mut self, picker_ui: fn(&mut egui::Ui, &mut Picker) -> bool) -> Self {
+        self.picker_ui = Some(picker_ui);
+        self
+    }
+
+    ///! save/load through your own WorleyPersistence
+    pub fn with_persistence(
+        mut self,
+        persistence: impl WorleyPersistence<BiomeT, Picker> + 'static,
+    ) -> Self {
+        self.persistence = Arc::new(persistence);
+        self
+    }
+}
+
+///! the DebugPlugin::height_fn
+#[derive(Resource)]
+pub struct DebugHeightFn<BiomeT>(pub fn(&BiomeT) -> f32);
+
+///! the DebugPlugin::picker_presets and picker_ui
+#[derive(Resource)]
+pub struct DebugPickerUi<Picker> {
+    pub presets: Arc<Vec<(String, Picker)>>,
+    pub editor: Option<fn(&mut egui::Ui, &mut Picker) -> bool>,
+}
+
+///! what the preview pixels show
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PreviewMode {
+    ///! blended DebugColor
+    #[default]
+    Color,
+    ///! blended DebugHeight, black: lowest biome, white: highest
+    Height,
+}
+
+///! when inspector edits reach the preview and WorleyResT's change detection
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ApplyMode {
+    ///! every edit, every frame of a slider drag
+    #[default]
+    Immediate,
+    ///! once no edit happened for this many seconds
+    Debounce(f64),
+    ///! on the inspector's "apply" button
+    Manual,
+}
+
+///! inspector edits waiting for ApplyMode, the Worley already holds them
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct PendingApply {
+    ///! Time::elapsed_secs_f64 of the last edit
+    pub last_edit: f64,
+}
+
 #[derive(Resource, Clone)]
 pub struct DebugPluginSettings {
     ///! true: plugin will spawn a ui entity for showcasing worley
@@ -44,6 +193,80 @@ pub struct DebugPluginSettings {
     // preview + ui visibility controll
     pub show_preview_image: bool,
     pub show_inspector_ui: bool,
+
+    ///! width and height of "save preview as png", covering the same area as the preview
+    pub export_resolution: u32,
+
+    ///! draw on top of the preview image
+    pub overlay: PreviewOverlay,
+    ///! Height needs DebugPlugin::with_heights, falls back to Color otherwise
+    pub preview_mode: PreviewMode,
+
+    ///! show the inspector in a window of its own instead of over the game view.
+    ///! closing that window turns this off again
+    pub inspector_window: bool,
+
+    ///! key toggling show_inspector_ui, None: no key binding
+    pub toggle_inspector_key: Option<KeyCode>,
+    ///! key toggling show_preview_image, None: no key binding
+    pub toggle_preview_key: Option<KeyCode>,
+
+    ///! render the preview with the GpuWorleyPlugin compute shader instead of on the cpu.
+    ///! overlays (including the color blind palette), PreviewMode::Height and the legend's
+    ///! coverage are cpu only
+    #[cfg(feature = "gpu")]
+    pub gpu_preview: bool,
+
+    ///! where the preview/ui state is saved on exit and restored from on startup
+    #[cfg(feature = "persist")]
+    pub state_file: String,
+
+    ///! ranges of the inspector sliders, widen them for unusual scales
+    pub slider_ranges: SliderRanges,
+
+    ///! folder the "snapshot" button writes the preview png and config into
+    pub snapshot_dir: String,
+
+    ///! hold back set_changed for expensive rebuilds (large previews, heavy downstream systems)
+    pub apply_mode: ApplyMode,
+}
+
+///! ranges of the inspector sliders. values outside a range are kept,
+///! the slider just can't reach them. the seed is a text field and has no range
+#[derive(Clone, Debug)]
+pub struct SliderRanges {
+    pub sharpness: RangeInclusive<f64>,
+    pub k: RangeInclusive<usize>,
+    pub zoom: RangeInclusive<f64>,
+    pub kill_threshold: RangeInclusive<f64>,
+    pub warp_strength: RangeInclusive<f32>,
+    ///! both strength_axes sliders
+    pub warp_strength_axes: RangeInclusive<f32>,
+    pub warp_frequency: RangeInclusive<f32>,
+    pub warp_iterations: RangeInclusive<u8>,
+    pub fractal_lacunarity: RangeInclusive<f32>,
+    pub fractal_gain: RangeInclusive<f32>,
+    pub fractal_octaves: RangeInclusive<i32>,
+    pub modulation_frequency: RangeInclusive<f32>,
+}
+
+impl Default for SliderRanges {
+    fn default() -> Self {
+        Self {
+            sharpness: 0.5..=20.0,
+            k: 1..=8,
+            zoom: 10.0..=200.0,
+            kill_threshold: 0.0..=0.99,
+            warp_strength: 0.0..=3.0,
+            warp_strength_axes: 0.0..=2.0,
+            warp_frequency: 0.0..=1.0,
+            warp_iterations: 1..=4,
+            fractal_lacunarity: 0.0..=4.0,
+            fractal_gain: 0.0..=3.0,
+            fractal_octaves: 0..=5,
+            modulation_frequency: 0.0..=0.5,
+        }
+    }
 }
 
 impl Default for DebugPluginSettings {
@@ -52,88 +275,127 @@ impl Default for DebugPluginSettings {
             spawn_preview_image: true,
             show_preview_image: true,
             show_inspector_ui: true,
+            export_resolution: 2048,
+            overlay: PreviewOverlay::default(),
+            preview_mode: PreviewMode::default(),
+            inspector_window: false,
+            toggle_inspector_key: Some(KeyCode::F9),
+            toggle_preview_key: Some(KeyCode::F10),
+            #[cfg(feature = "gpu")]
+            gpu_preview: false,
+            #[cfg(feature = "persist")]
+            state_file: "worley_debug_state.ron".to_string(),
+            slider_ranges: SliderRanges::default(),
+            snapshot_dir: "snapshots".to_string(),
+            apply_mode: ApplyMode::default(),
         }
     }
 }
 
-// I HATE THIS lol
-// explanation: i have identical plugin implementations, but when using serde,
-// we require Serialize and Deserialize bounds on BiomeT and Picker.
-#[cfg(feature = "serde")]
 impl<WorleyResT, BiomeT, Picker> Plugin for DebugPlugin<WorleyResT, BiomeT, Picker>
 where
     WorleyResT: Resource + GetWorley<BiomeT, Picker>,
     BiomeT: BiomeVariants
         + DebugColor<BiomeT>
+        + std::fmt::Debug
         + Sync
         + Send
         + std::default::Default
-        + 'static
-        + Serialize
-        + for<'de> Deserialize<'de>,
-    Picker: BiomePicker<BiomeT>
-        + Default
-        + Sync
-        + Send
-        + 'static
-        + Serialize
-        + for<'de> Deserialize<'de>,
+        + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Sync + Send + 'static,
 {
     fn build(&self, app: &mut App) {
         app.insert_resource(self.settings.clone());
+        app.insert_resource(DebugPersistence(self.persistence.clone()));
+        if let Some(height_fn) = self.height_fn {
+            app.insert_resource(DebugHeightFn(height_fn));
+        }
+        app.insert_resource(DebugPickerUi {
+            presets: Arc::new(self.picker_presets.clone()),
+            editor: self.picker_ui,
+        });
+        #[cfg(feature = "persist")]
+        persist::add(app);
+        register_reflect_types(app);
+        WorleyChangedPlugin::<WorleyResT, BiomeT, Picker>::add_once(app);
+        #[cfg(feature = "gpu")]
+        if !app.is_plugin_added::<GpuWorleyPlugin>() {
+            app.add_plugins(GpuWorleyPlugin);
+        }
         app.add_systems(
             EguiPrimaryContextPass,
-            inspector_ui::<WorleyResT, BiomeT, Picker>.run_if(if_show_inspector),
+            inspector_ui::<WorleyResT, BiomeT, Picker>
+                .run_if(inspector_window::if_inspector_in_primary)
+                .in_set(WorleySet::DebugUi),
         );
-        app.add_systems(Update, texture_tap);
-        app.add_systems(Update, update_preview_visibility);
         app.add_systems(
-            PostUpdate,
-            rebuild_preview_image::<WorleyResT, BiomeT, Picker>,
+            WorleyInspectorWindowPass,
+            inspector_ui::<WorleyResT, BiomeT, Picker>
+                .run_if(if_show_inspector)
+                .in_set(WorleySet::DebugUi),
         );
-    }
-}
-
-// "I HATE THIS lol" our duplicate of plugin without serde bounds
-#[cfg(not(feature = "serde"))]
-impl<WorleyResT, BiomeT, Picker> Plugin for DebugPlugin<WorleyResT, BiomeT, Picker>
-where
-    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
-    BiomeT: BiomeVariants + DebugColor<BiomeT> + Sync + Send + std::default::Default + 'static,
-    Picker: BiomePicker<BiomeT> + Default + Sync + Send + 'static,
-{
-    fn build(&self, app: &mut App) {
-        app.insert_resource(self.settings.clone());
         app.add_systems(
             EguiPrimaryContextPass,
-            inspector_ui::<WorleyResT, BiomeT, Picker>.run_if(if_show_inspector),
+            hover_tooltip_ui.in_set(WorleySet::DebugUi),
+        );
+        app.add_systems(
+            Update,
+            (
+                inspector_window::sync_inspector_window,
+                apply_debounced::<WorleyResT, BiomeT, Picker>,
+                (texture_tap, inspect_click, toggle_by_key, scroll_preview),
+                (spawn_minimap_markers, follow_minimap_target),
+                hover_preview::<WorleyResT, BiomeT, Picker>,
+                update_preview_visibility,
+            )
+                .in_set(WorleySet::DebugUi),
         );
-        app.add_systems(Update, texture_tap);
-        app.add_systems(Update, update_preview_visibility);
+        #[cfg(feature = "gpu")]
+        app.add_systems(Update, swap_preview_target.in_set(WorleySet::DebugUi));
         app.add_systems(
             PostUpdate,
-            rebuild_preview_image::<WorleyResT, BiomeT, Picker>,
+            (
+                rebuild_changed_worley::<WorleyResT, BiomeT, Picker>,
+                rebuild_preview_image::<WorleyResT, BiomeT, Picker>,
+                apply_preview_task,
+                rebuild_preview_views::<WorleyResT, BiomeT, Picker>,
+                apply_preview_view_tasks,
+            )
+                .chain()
+                .in_set(WorleySet::RebuildPreview),
         );
     }
 }
 
-pub fn if_show_inspector(settings: Res<DebugPluginSettings>) -> bool {
-    settings.show_inspector_ui
-}
-
-///! color of biome to display in debug worley texture
-pub trait DebugColor<BiomeT> {
-    fn get_color(&self) -> Srgba;
-}
-
-///! required for the debug_plugin to find what worley to visualize
-pub trait GetWorley<BiomeT, Picker>
+///! edits through reflection (e.g. WorldInspectorPlugin) write fields directly,
+///! refresh the state derived from them
+fn rebuild_changed_worley<WorleyResT, BiomeT, Picker>(mut map_settings: ResMut<WorleyResT>)
 where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
     BiomeT: BiomeVariants,
     Picker: BiomePicker<BiomeT> + Default,
 {
-    fn get_worley<'a>(&'a self) -> &'a Worley<BiomeT, Picker>;
-    fn get_worley_mut<'a>(&'a mut self) -> &'a mut Worley<BiomeT, Picker>;
+    if map_settings.is_changed() {
+        map_settings
+            .bypass_change_detection()
+            .get_worley_mut()
+            .rebuild();
+    }
+}
+
+///! DebugPluginSettings::toggle_inspector_key / toggle_preview_key
+fn toggle_by_key(keyboard: Res<ButtonInput<KeyCode>>, mut settings: ResMut<DebugPluginSettings>) {
+    let pressed = |key: Option<KeyCode>| key.is_some_and(|key| keyboard.just_pressed(key));
+    if pressed(settings.toggle_inspector_key) {
+        settings.show_inspector_ui = !settings.show_inspector_ui;
+    }
+    if pressed(settings.toggle_preview_key) {
+        settings.show_preview_image = !settings.show_preview_image;
+    }
+}
+
+pub fn if_show_inspector(settings: Res<DebugPluginSettings>) -> bool {
+    settings.show_inspector_ui
 }
 
 ///! size of the visible worley texture on screen
@@ -165,20 +427,216 @@ impl DisplayTextureSize {
 ///! the size of the preview image
 pub const IMG_SIZE: i32 = 32 * 4;
 
-///! toggle the preview image size
+///! drag state of the preview image
+#[derive(Component, Default)]
+pub struct PreviewDrag {
+    ///! cursor position (RelativeCursorPosition::normalized) last frame while pressed
+    last: Option<Vec2>,
+    ///! the cursor moved since the press, so releasing is not a tap
+    moved: bool,
+}
+
+///! tap: toggle the preview image size, drag: pan the preview, scroll: zoom the preview around the cursor
 fn texture_tap(
     mut interaction_query: Query<
-        (&Interaction, &mut DisplayTextureSize, &mut Node),
-        (Changed<Interaction>, With<Button>),
+        (
+            &Interaction,
+            &RelativeCursorPosition,
+            &mut PreviewDrag,
+            &mut DisplayTextureSize,
+            &mut Node,
+        ),
+        With<Button>,
     >,
+    mouse_scroll: Res<AccumulatedMouseScroll>,
+    mut worley_image: Option<ResMut<WorleyImage>>,
+) {
+    for (interaction, cursor, mut drag, mut display_size, mut node) in interaction_query.iter_mut()
+    {
+        let Some(cursor) = cursor.normalized else {
+            continue;
+        };
+        match interaction {
+            Interaction::Pressed => {
+                if let (Some(last), Some(worley_image)) = (drag.last, &mut worley_image) {
+                    let delta = cursor - last;
+                    if delta != Vec2::ZERO {
+                        // image rows are x, columns are z
+                        let scale = worley_image.preview_scale * IMG_SIZE as f64;
+                        worley_image.preview_offset.0 -= delta.y as f64 * scale;
+                        worley_image.preview_offset.1 -= delta.x as f64 * scale;
+                        drag.moved = true;
+                    }
+                }
+                drag.last = Some(cursor);
+            }
+            _ => {
+                if drag.last.is_some() && !drag.moved {
+                    // toggle
+                    display_size.toggle();
+                    node.width = display_size.node_size();
+                }
+                drag.last = None;
+                drag.moved = false;
+            }
+        }
+
+        if *interaction == Interaction::None || mouse_scroll.delta.y == 0.0 {
+            continue;
+        }
+        let Some(worley_image) = &mut worley_image else {
+            continue;
+        };
+        let lines = match mouse_scroll.unit {
+            MouseScrollUnit::Line => mouse_scroll.delta.y,
+            MouseScrollUnit::Pixel => mouse_scroll.delta.y / 100.0,
+        };
+        let scale = worley_image.preview_scale;
+        let new_scale = (scale * 0.9f64.powf(lines as f64)).clamp(1.0 / 64.0, 1024.0);
+        // keep the world position under the cursor in place
+        let px = (cursor.y as f64 + 0.5) * IMG_SIZE as f64;
+        let pz = (cursor.x as f64 + 0.5) * IMG_SIZE as f64;
+        worley_image.preview_offset.0 += px * (scale - new_scale);
+        worley_image.preview_offset.1 += pz * (scale - new_scale);
+        worley_image.preview_scale = new_scale;
+    }
+}
+
+///! the height_fn for render_preview, if the preview shows heights
+fn preview_height_fn<BiomeT>(
+    settings: &DebugPluginSettings,
+    height_fn: Option<&DebugHeightFn<BiomeT>>,
+) -> Option<fn(&BiomeT) -> f32> {
+    match settings.preview_mode {
+        PreviewMode::Color => None,
+        PreviewMode::Height => height_fn.map(|h| h.0),
+    }
+}
+
+///! world position of the preview pixel last right clicked, shown in the "inspected pixel" window
+#[derive(Resource, Clone, Copy)]
+pub struct InspectedPixel {
+    pub world: (f64, f64),
+}
+
+///! right click the preview image to inspect a pixel
+fn inspect_click(
+    mouse: Res<ButtonInput<MouseButton>>,
+    query: Query<(&Interaction, &RelativeCursorPosition), With<WorleyUiPreviewTag>>,
+    worley_image: Option<Res<WorleyImage>>,
+    mut commands: Commands,
 ) {
-    for (interaction, mut display_size, mut node) in interaction_query.iter_mut() {
-        if let Interaction::Pressed = interaction {
-            // toggle
-            display_size.toggle();
-            node.width = display_size.node_size();
+    if !mouse.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let Some(worley_image) = worley_image else {
+        return;
+    };
+    for (interaction, cursor) in query.iter() {
+        let Some(cursor) = cursor.normalized else {
+            continue;
+        };
+        if *interaction == Interaction::None {
+            continue;
+        }
+        // image rows are x, columns are z
+        let gx = ((cursor.y as f64 + 0.5) * IMG_SIZE as f64).floor();
+        let gz = ((cursor.x as f64 + 0.5) * IMG_SIZE as f64).floor();
+        let scale = worley_image.preview_scale;
+        let (ox, oz) = worley_image.preview_offset;
+        commands.insert_resource(InspectedPixel {
+            world: (gx * scale + ox, gz * scale + oz),
+        });
+    }
+}
+
+///! the preview pixel under the cursor, shown as a tooltip
+#[derive(Resource, Clone)]
+pub struct HoveredPixel {
+    pub world: (f64, f64),
+    ///! highest first, biome formatted with Debug
+    pub weights: Vec<(f64, String)>,
+    ///! Time::elapsed_secs_f64 of the sample
+    sampled_at: f64,
+}
+
+///! resample the hovered pixel at most this often (seconds)
+const HOVER_INTERVAL: f64 = 0.1;
+
+fn hover_preview<WorleyResT, BiomeT, Picker>(
+    mut commands: Commands,
+    time: Res<Time>,
+    query: Query<(&Interaction, &RelativeCursorPosition), With<WorleyUiPreviewTag>>,
+    worley_image: Option<Res<WorleyImage>>,
+    map_settings: Res<WorleyResT>,
+    hovered: Option<Res<HoveredPixel>>,
+) where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + std::fmt::Debug + std::default::Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let cursor = query
+        .iter()
+        .filter(|(interaction, _)| **interaction != Interaction::None)
+        .find_map(|(_, cursor)| cursor.normalized);
+    let (Some(cursor), Some(worley_image)) = (cursor, worley_image) else {
+        if hovered.is_some() {
+            commands.remove_resource::<HoveredPixel>();
         }
+        return;
+    };
+    let now = time.elapsed_secs_f64();
+    if hovered.is_some_and(|h| now - h.sampled_at < HOVER_INTERVAL) {
+        return;
     }
+
+    // image rows are x, columns are z
+    let gx = ((cursor.y as f64 + 0.5) * IMG_SIZE as f64).floor();
+    let gz = ((cursor.x as f64 + 0.5) * IMG_SIZE as f64).floor();
+    let scale = worley_image.preview_scale;
+    let (ox, oz) = worley_image.preview_offset;
+    let world = (gx * scale + ox, gz * scale + oz);
+    let mut weights: Vec<(f64, String)> = map_settings
+        .get_worley()
+        .get(world.0, world.1)
+        .iter()
+        .map(|(w, biome)| (*w, format!("{:?}", biome)))
+        .collect();
+    weights.sort_by(|a, b| b.0.total_cmp(&a.0));
+    commands.insert_resource(HoveredPixel {
+        world,
+        weights,
+        sampled_at: now,
+    });
+}
+
+fn hover_tooltip_ui(world: &mut World) {
+    let Some(hovered) = world.get_resource::<HoveredPixel>().cloned() else {
+        return;
+    };
+    let Ok(mut egui_context) = world
+        .query_filtered::<&mut EguiContext, With<bevy_egui::PrimaryEguiContext>>()
+        .single(world)
+        .map(|c| c.clone())
+    else {
+        return;
+    };
+    let ctx = egui_context.get_mut();
+    let Some(pointer) = ctx.pointer_latest_pos() else {
+        return;
+    };
+    egui::Area::new(egui::Id::new("worley_hover_tooltip"))
+        .order(egui::Order::Tooltip)
+        .fixed_pos(pointer + egui::vec2(16.0, 16.0))
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(format!("({:.1}, {:.1})", hovered.world.0, hovered.world.1));
+                for (w, biome) in &hovered.weights {
+                    ui.label(format!("{}: {:.0}%", biome, w * 100.0));
+                }
+            });
+        });
 }
 
 ///! fetch worley data to UPDATE the preview image
@@ -187,373 +645,2079 @@ fn rebuild_preview_image<WorleyResT, BiomeT, Picker>(
     debug_plugin_settings: Res<DebugPluginSettings>,
     mut commands: Commands,
     mut images: ResMut<Assets<Image>>,
-    mut worley_image: Option<ResMut<WorleyImage>>,
+    worley_image: Option<ResMut<WorleyImage>>,
+    running_task: Option<Res<PreviewTask>>,
+    height_fn: Option<Res<DebugHeightFn<BiomeT>>>,
 ) where
     WorleyResT: Resource + GetWorley<BiomeT, Picker>,
-    BiomeT: BiomeVariants + 'static + DebugColor<BiomeT> + std::default::Default,
-    Picker: BiomePicker<BiomeT> + Default + 'static,
+    BiomeT: BiomeVariants + DebugColor<BiomeT> + std::default::Default + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static,
 {
-    if !map_settings.is_changed() {
+    let view_changed =
+        worley_image.as_ref().is_some_and(|w| w.is_changed()) || debug_plugin_settings.is_changed();
+    if !map_settings.is_changed() && !view_changed {
+        return;
+    }
+    // scrolling changes the view every frame, restarting the render each time would never finish one.
+    // let it finish, the next frame's scroll step triggers the next render
+    let scrolling = worley_image.as_ref().is_some_and(|w| w.scroll.enabled);
+    if scrolling && running_task.is_some() && !map_settings.is_changed() {
         return;
     }
 
-    let mut img_data = Vec::new();
-    let worley = WorleyResT::get_worley(&map_settings);
+    let worley = WorleyResT::get_worley(&map_settings).clone();
+    let (worley_offset, scale) = worley_image
+        .as_deref()
+        .map_or(((0.0, 0.0), 1.0), |w| (w.preview_offset, w.preview_scale));
+    let overlay = debug_plugin_settings.overlay;
+    let height_fn = preview_height_fn(&debug_plugin_settings, height_fn.as_deref());
 
-    let worley_offset = worley_image
-        .as_mut()
-        .map_or((0.0, 0.0), |w| w.preview_offset);
-    for gx in 0..IMG_SIZE {
-        for gz in 0..IMG_SIZE {
-            let weights = worley.get(gx as f64 + worley_offset.0, gz as f64 + worley_offset.1);
+    #[cfg(feature = "gpu")]
+    if let Some(worley_image) = &worley_image
+        && debug_plugin_settings.gpu_preview
+        && let Some(job) = GpuWorleyJob::new(
+            &worley,
+            worley_image.gpu_handle.clone(),
+            worley_offset,
+            scale,
+            IMG_SIZE as u32,
+            4,
+        )
+    {
+        commands.remove_resource::<PreviewTask>();
+        commands.insert_resource(job);
+        return;
+    }
 
-            // blend colors
-            let mut r = 0.0;
-            let mut g = 0.0;
-            let mut b = 0.0;
-            let mut wsum = 0.0;
-            for (w, biome) in &weights {
-                let c = DebugColor::get_color(biome);
-                r += c.red as f64 * w;
-                g += c.green as f64 * w;
-                b += c.blue as f64 * w;
-                wsum += w;
-            }
+    // sampled off the main thread, apply_preview_task uploads the result when it's done
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        render_preview(
+            &worley,
+            worley_offset,
+            scale,
+            IMG_SIZE as u32,
+            overlay,
+            height_fn,
+        )
+    });
+    // replacing a task that is still running drops (cancels) it
+    commands.insert_resource(PreviewTask(task));
+
+    if worley_image.is_none() {
+        let image_handle = images.add(blank_preview_image(IMG_SIZE as u32));
+
+        // spawn visual representation
+        if debug_plugin_settings.spawn_preview_image {
+            commands.spawn((
+                Name::new("worley_ui_preview"),
+                Node {
+                    align_self: AlignSelf::Start,
+                    ..default()
+                },
+                ImageNode::new(image_handle.clone()),
+                DisplayTextureSize::default(),
+                WorleyUiPreviewTag,
+                Button,
+                RelativeCursorPosition::default(),
+                PreviewDrag::default(),
+            ));
+        }
+
+        commands.insert_resource(WorleyImage {
+            handle: image_handle,
+            #[cfg(feature = "gpu")]
+            gpu_handle: images.add(gpu_target_image(IMG_SIZE as u32)),
+            preview_offset: (0.0, 0.0),
+            preview_scale: 1.0,
+            coverage: Vec::new(),
+            scroll: PreviewScroll::default(),
+            last_render: None,
+        });
+    }
+}
+
+///! blank until the first render task finishes
+fn blank_preview_image(size: u32) -> Image {
+    let img_data = vec![0; (size * size * 4) as usize];
+    let mut img = Image::new(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        img_data,
+        bevy::render::render_resource::TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    );
+    img.sampler = ImageSampler::nearest();
+    img
+}
+
+///! the preview render in flight, see rebuild_preview_image
+#[derive(Resource)]
+pub struct PreviewTask(Task<PreviewRender>);
+
+///! upload a finished preview render
+fn apply_preview_task(
+    mut commands: Commands,
+    task: Option<ResMut<PreviewTask>>,
+    worley_image: Option<ResMut<WorleyImage>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let (Some(mut task), Some(mut worley_image)) = (task, worley_image) else {
+        return;
+    };
+    let Some(render) = block_on(future::poll_once(&mut task.0)) else {
+        return;
+    };
+    commands.remove_resource::<PreviewTask>();
+    let image = images.get_mut(&worley_image.handle).expect("image");
+    image.data = Some(render.rgba);
+    // not a view change, don't trigger another rebuild
+    let worley_image = worley_image.bypass_change_detection();
+    worley_image.coverage = render.coverage;
+    worley_image.last_render = Some(render.stats);
+}
+
+#[derive(Component)]
+pub struct WorleyUiPreviewTag;
+
+///! an extra preview image with its own view, next to the plugin's own preview (the WorleyImage resource).
+///! add it to a ui Node, the plugin adds the ImageNode and keeps it updated.
+///! change offset every frame to e.g. follow the player
+///! ```ignore
+///! commands.spawn((Node::default(), WorleyPreviewView { scale: 8.0, size: 64, ..default() }));
+///! ```
+#[derive(Component, Clone, Debug)]
+pub struct WorleyPreviewView {
+    ///! world position of the top left pixel
+    pub offset: (f64, f64),
+    ///! world units per pixel
+    pub scale: f64,
+    ///! width and height in pixels
+    pub size: u32,
+}
 
-            let color = Srgba::new(r as f32, g as f32, b as f32, 1.0);
-            img_data.push((color.red * 255.0) as u8);
-            img_data.push((color.green * 255.0) as u8);
-            img_data.push((color.blue * 255.0) as u8);
-            img_data.push(255 as u8);
+impl Default for WorleyPreviewView {
+    fn default() -> Self {
+        Self {
+            offset: (0.0, 0.0),
+            scale: 1.0,
+            size: IMG_SIZE as u32,
         }
     }
+}
 
-    match worley_image {
-        Some(worley_image) => {
-            let image = images.get_mut(&worley_image.handle).expect("image");
-            image.data = Some(img_data);
+///! turns a preview into a minimap: keeps it centered on target's translation (x, z).
+///! add it to the plugin's preview (WorleyUiPreviewTag) or to a WorleyPreviewView
+#[derive(Component, Clone, Debug)]
+pub struct WorleyMinimap {
+    pub target: Entity,
+    ///! draw a dot at the center of the preview, where target is
+    pub marker: bool,
+}
+
+impl WorleyMinimap {
+    pub fn new(target: Entity) -> Self {
+        Self {
+            target,
+            marker: true,
         }
-        None => {
-            // make image
-            let mut img = Image::new(
-                Extent3d {
-                    width: IMG_SIZE as u32,
-                    height: IMG_SIZE as u32,
-                    depth_or_array_layers: 1,
-                },
-                TextureDimension::D2,
-                img_data,
-                bevy::render::render_resource::TextureFormat::Rgba8UnormSrgb,
-                RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
-            );
-            img.sampler = ImageSampler::nearest();
-            let image_handle = images.add(img);
+    }
+}
+
+///! the dot at the center of a WorleyMinimap
+#[derive(Component)]
+pub struct WorleyMinimapMarker;
 
-            // spawn visual representation
-            if debug_plugin_settings.spawn_preview_image {
-                commands.spawn((
-                    Name::new("worley_ui_preview"),
+fn spawn_minimap_markers(
+    mut commands: Commands,
+    minimaps: Query<(Entity, Ref<WorleyMinimap>, Option<&Children>)>,
+    mut markers: Query<&mut Node, With<WorleyMinimapMarker>>,
+) {
+    for (entity, minimap, children) in minimaps.iter() {
+        if !minimap.is_changed() {
+            continue;
+        }
+        let display = match minimap.marker {
+            true => Display::Flex,
+            false => Display::None,
+        };
+        let existing = children
+            .into_iter()
+            .flatten()
+            .find(|child| markers.contains(**child));
+        match existing {
+            Some(marker) => {
+                if let Ok(mut node) = markers.get_mut(*marker) {
+                    node.display = display;
+                }
+            }
+            None => {
+                commands.entity(entity).with_child((
+                    Name::new("worley_minimap_marker"),
+                    WorleyMinimapMarker,
                     Node {
-                        align_self: AlignSelf::Start,
+                        display,
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(50.0),
+                        top: Val::Percent(50.0),
+                        width: Val::Px(6.0),
+                        height: Val::Px(6.0),
+                        margin: UiRect::all(Val::Px(-3.0)),
                         ..default()
                     },
-                    ImageNode::new(image_handle.clone()),
-                    DisplayTextureSize::default(),
-                    WorleyUiPreviewTag,
-                    Button,
+                    BackgroundColor(Color::WHITE),
                 ));
             }
+        }
+    }
+}
 
-            commands.insert_resource(WorleyImage {
-                handle: image_handle,
-                preview_offset: (0.0, 0.0),
-            });
+fn follow_minimap_target(
+    targets: Query<&GlobalTransform>,
+    mut minimaps: Query<(
+        &WorleyMinimap,
+        Option<&mut WorleyPreviewView>,
+        Has<WorleyUiPreviewTag>,
+    )>,
+    mut worley_image: Option<ResMut<WorleyImage>>,
+) {
+    for (minimap, view, is_plugin_preview) in minimaps.iter_mut() {
+        let Ok(target) = targets.get(minimap.target) else {
+            continue;
+        };
+        let center = target.translation();
+        let center = (center.x as f64, center.z as f64);
+        // offset is the top left pixel, shift by half the image
+        let centered = |scale: f64, size: u32| {
+            let half = size as f64 / 2.0 * scale;
+            (center.0 - half, center.1 - half)
+        };
+        if let Some(mut view) = view {
+            let offset = centered(view.scale, view.size);
+            if view.offset != offset {
+                view.offset = offset;
+            }
+        } else if is_plugin_preview && let Some(worley_image) = &mut worley_image {
+            let offset = centered(worley_image.preview_scale, IMG_SIZE as u32);
+            if worley_image.preview_offset != offset {
+                worley_image.preview_offset = offset;
+            }
         }
     }
 }
 
+///! the render in flight of a WorleyPreviewView
 #[derive(Component)]
-pub struct WorleyUiPreviewTag;
+pub struct WorleyPreviewViewTask(Task<PreviewRender>);
 
-fn update_preview_visibility(
-    settings: Res<DebugPluginSettings>,
-    mut query: Query<&mut Node, With<WorleyUiPreviewTag>>,
-) {
-    if !settings.is_changed() {
-        return;
-    }
-    for mut node in query.iter_mut() {
-        match settings.show_preview_image {
-            true => {
-                node.display = Display::Flex;
+fn rebuild_preview_views<WorleyResT, BiomeT, Picker>(
+    map_settings: Res<WorleyResT>,
+    debug_plugin_settings: Res<DebugPluginSettings>,
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut views: Query<(Entity, Ref<WorleyPreviewView>, Option<&mut ImageNode>)>,
+    height_fn: Option<Res<DebugHeightFn<BiomeT>>>,
+) where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + DebugColor<BiomeT> + std::default::Default + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static,
+{
+    for (entity, view, image_node) in views.iter_mut() {
+        let sized = image_node
+            .as_ref()
+            .and_then(|node| images.get(&node.image))
+            .is_some_and(|image| image.width() == view.size);
+        if sized {
+            if !map_settings.is_changed()
+                && !view.is_changed()
+                && !debug_plugin_settings.is_changed()
+            {
+                continue;
             }
-            false => {
-                node.display = Display::None;
+        } else {
+            // new view or resized
+            let handle = images.add(blank_preview_image(view.size));
+            match image_node {
+                Some(mut node) => node.image = handle,
+                None => {
+                    commands.entity(entity).insert(ImageNode::new(handle));
+                }
             }
         }
+
+        let worley = WorleyResT::get_worley(&map_settings).clone();
+        let (offset, scale, size) = (view.offset, view.scale, view.size);
+        let overlay = debug_plugin_settings.overlay;
+        let height_fn = preview_height_fn(&debug_plugin_settings, height_fn.as_deref());
+        let task = AsyncComputeTaskPool::get()
+            .spawn(async move { render_preview(&worley, offset, scale, size, overlay, height_fn) });
+        // replaces (cancels) a task still running
+        commands.entity(entity).insert(WorleyPreviewViewTask(task));
     }
 }
 
-///! reference the preview image of the worley world
-#[derive(Resource)]
-pub struct WorleyImage {
+fn apply_preview_view_tasks(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut views: Query<(Entity, &mut WorleyPreviewViewTask, &ImageNode)>,
+) {
+    for (entity, mut task, image_node) in views.iter_mut() {
+        let Some(render) = block_on(future::poll_once(&mut task.0)) else {
+            continue;
+        };
+        commands.entity(entity).remove::<WorleyPreviewViewTask>();
+        if let Some(image) = images.get_mut(&image_node.image)
+            && image
+                .data
+                .as_ref()
+                .is_some_and(|d| d.len() == render.rgba.len())
+        {
+            image.data = Some(render.rgba);
+        }
+    }
+}
+
+///! show the gpu or cpu preview image, depending on DebugPluginSettings::gpu_preview
+#[cfg(feature = "gpu")]
+fn swap_preview_target(
+    settings: Res<DebugPluginSettings>,
+    worley_image: Option<Res<WorleyImage>>,
+    mut query: Query<&mut ImageNode, With<WorleyUiPreviewTag>>,
+) {
+    let Some(worley_image) = worley_image else {
+        return;
+    };
+    let handle = match settings.gpu_preview {
+        true => &worley_image.gpu_handle,
+        false => &worley_image.handle,
+    };
+    for mut image_node in query.iter_mut() {
+        if image_node.image != *handle {
+            image_node.image = handle.clone();
+        }
+    }
+}
+
+fn update_preview_visibility(
+    settings: Res<DebugPluginSettings>,
+    mut query: Query<&mut Node, With<WorleyUiPreviewTag>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for mut node in query.iter_mut() {
+        match settings.show_preview_image {
+            true => {
+                node.display = Display::Flex;
+            }
+            false => {
+                node.display = Display::None;
+            }
+        }
+    }
+}
+
+///! reference the preview image of the worley world
+#[derive(Resource)]
+pub struct WorleyImage {
     handle: Handle<Image>,
-    ///! preview image sampling is offset by this
+    ///! target of the gpu preview, see DebugPluginSettings::gpu_preview
+    #[cfg(feature = "gpu")]
+    gpu_handle: Handle<Image>,
+    ///! preview image sampling is offset by this (drag the preview to pan)
     pub preview_offset: (f64, f64),
+    ///! world units per preview pixel (scroll over the preview to zoom)
+    pub preview_scale: f64,
+    ///! fraction of the preview per BiomeT::variants() index, updated on rebuild
+    pub coverage: Vec<f64>,
+    ///! keep moving preview_offset, to watch the patterns across a large area
+    pub scroll: PreviewScroll,
+    ///! cost of the last finished rebuild
+    pub last_render: Option<RenderStats>,
+}
+
+///! see WorleyImage::scroll
+#[derive(Clone, Copy, Debug)]
+pub struct PreviewScroll {
+    pub enabled: bool,
+    ///! direction in degrees, 0: +x (down the preview), 90: +z (right)
+    pub angle: f64,
+    ///! preview pixels per second, so the speed on screen doesn't depend on the zoom
+    pub speed: f64,
+}
+
+impl Default for PreviewScroll {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            angle: 0.0,
+            speed: 16.0,
+        }
+    }
+}
+
+fn scroll_preview(time: Res<Time>, worley_image: Option<ResMut<WorleyImage>>) {
+    let Some(mut worley_image) = worley_image else {
+        return;
+    };
+    let scroll = worley_image.scroll;
+    if !scroll.enabled {
+        return;
+    }
+    let step = scroll.speed * worley_image.preview_scale * time.delta_secs_f64();
+    let (sin, cos) = scroll.angle.to_radians().sin_cos();
+    worley_image.preview_offset.0 += cos * step;
+    worley_image.preview_offset.1 += sin * step;
+}
+
+#[derive(Resource)]
+pub struct SaveWorleyFilename(pub String);
+
+impl FromWorld for SaveWorleyFilename {
+    fn from_world(_world: &mut World) -> Self {
+        Self(String::default())
+    }
+}
+
+// save/load through the DebugPersistence
+fn persistence_ui<WorleyResT, BiomeT, Picker>(ui: &mut egui::Ui, world: &mut World)
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static,
+{
+    let persistence = world
+        .resource::<DebugPersistence<BiomeT, Picker>>()
+        .0
+        .clone();
+    if let Some(reason) = persistence.unavailable() {
+        ui.add_enabled(false, egui::Button::new("save worley to file"));
+        ui.add_enabled(false, egui::Button::new("load worley file"));
+        ui.colored_label(egui::Color32::RED, reason);
+        return;
+    }
+
+    let mut worley_file_name = world.get_resource_or_init::<SaveWorleyFilename>();
+    ui.add(egui::Label::new("worley file name: (save or load)"));
+    ui.add(egui::TextEdit::singleline(&mut worley_file_name.0));
+    let file_name = worley_file_name.0.clone();
+    metadata_ui::<WorleyResT, BiomeT, Picker>(ui, world);
+
+    if ui.add(egui::Button::new("save worley to file")).clicked() {
+        let worley = world.resource::<WorleyResT>().get_worley();
+        match persistence.save(&file_name, worley) {
+            Ok(()) => info!("saved worley {:?}", file_name),
+            Err(err) => error!("failed to save worley {:?}: {}", file_name, err),
+        }
+    }
+
+    if ui.add(egui::Button::new("load worley file")).clicked() {
+        // the state before the load stays undoable
+        init_history::<WorleyResT, BiomeT, Picker>(world);
+        match persistence.load(world, &file_name) {
+            Ok(Some(new_worley)) => {
+                world
+                    .resource_mut::<WorleyHistory<BiomeT, Picker>>()
+                    .record_step(&new_worley);
+                // REPLACE
+                *world.resource_mut::<WorleyResT>().get_worley_mut() = new_worley;
+                info!("replaced current worley");
+            }
+            Ok(None) => {
+                world
+                    .resource_mut::<WorleyHistory<BiomeT, Picker>>()
+                    .awaiting_load = true;
+                info!("loading worley {:?}", file_name);
+            }
+            Err(err) => error!("failed to load worley {:?}: {}", file_name, err),
+        }
+    }
+}
+
+// author and description saved with the file. not part of the generation,
+// so edits bypass change detection and don't regenerate anything
+fn metadata_ui<WorleyResT, BiomeT, Picker>(ui: &mut egui::Ui, world: &mut World)
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    let mut map_settings = world.resource_mut::<WorleyResT>();
+    let worley = map_settings.bypass_change_detection().get_worley_mut();
+    ui.collapsing("metadata", |ui| {
+        let Some(metadata) = &mut worley.metadata else {
+            if ui.button("add metadata").clicked() {
+                worley.metadata = Some(WorleyMetadata::new("", ""));
+            }
+            return;
+        };
+        ui.label(format!(
+            "created {} (unix), crate {}, format {}",
+            metadata.created, metadata.crate_version, metadata.format_version
+        ));
+        ui.horizontal(|ui| {
+            ui.label("author");
+            ui.text_edit_singleline(&mut metadata.author);
+        });
+        ui.label("description");
+        ui.text_edit_multiline(&mut metadata.description);
+        if ui.button("remove metadata").clicked() {
+            worley.metadata = None;
+        }
+    });
+}
+
+// the inspector window's context if there is one, the primary one otherwise
+fn inspector_context(world: &mut World) -> EguiContext {
+    if let Ok(context) = world
+        .query_filtered::<&EguiContext, With<WorleyInspectorCamera>>()
+        .single(world)
+    {
+        return context.clone();
+    }
+    world
+        .query_filtered::<&EguiContext, With<bevy_egui::PrimaryEguiContext>>()
+        .single(world)
+        .expect("EguiContext not found")
+        .clone()
+}
+
+fn inspector_ui<WorleyResT, BiomeT, Picker>(mut world: &mut World)
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants
+        + DebugColor<BiomeT>
+        + std::fmt::Debug
+        + std::default::Default
+        + Send
+        + Sync
+        + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static,
+{
+    let mut egui_context = inspector_context(world);
+
+    egui::Window::new("worley UI").show(egui_context.get_mut(), |ui| {
+        egui::ScrollArea::both().show(ui, |ui| {
+            persistence_ui::<WorleyResT, BiomeT, Picker>(ui, &mut world);
+
+            history_ui::<WorleyResT, BiomeT, Picker>(ui, &mut world);
+            if tweak_ui::<WorleyResT, BiomeT, Picker>(ui, &mut world) {
+                record_history::<WorleyResT, BiomeT, Picker>(ui, &mut world);
+            }
+            if seed_thumbnails_ui::<WorleyResT, BiomeT, Picker>(ui, &mut world) {
+                record_history::<WorleyResT, BiomeT, Picker>(ui, &mut world);
+            }
+            if legend_ui::<WorleyResT, BiomeT, Picker>(ui, &mut world) {
+                record_history::<WorleyResT, BiomeT, Picker>(ui, &mut world);
+            }
+            coverage_histogram_ui::<WorleyResT, BiomeT, Picker>(ui, &mut world);
+            falloff_plot_ui::<WorleyResT, BiomeT, Picker>(ui, &mut world);
+            overlay_ui(ui, &mut world);
+            preview_view_ui(ui, &mut world);
+            preview_export_ui::<WorleyResT, BiomeT, Picker>(ui, &mut world);
+        });
+    });
+
+    inspected_pixel_ui::<WorleyResT, BiomeT, Picker>(egui_context.get_mut(), world);
+}
+
+///! bounded undo/redo history of the Worley edited in the inspector
+#[derive(Resource)]
+pub struct WorleyHistory<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    states: std::collections::VecDeque<Worley<BiomeT, Picker>>,
+    ///! index of the current state in states, states after it can be redone
+    index: usize,
+    ///! egui time of the last recorded edit
+    last_record: f64,
+    ///! a load completing later was started, recorded once the worley changes
+    awaiting_load: bool,
+    ///! max number of states kept
+    pub limit: usize,
+}
+
+impl<BiomeT, Picker> Default for WorleyHistory<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self {
+            states: std::collections::VecDeque::new(),
+            index: 0,
+            last_record: f64::NEG_INFINITY,
+            awaiting_load: false,
+            limit: 64,
+        }
+    }
+}
+
+///! edits closer together than this (e.g. dragging a slider) become one undo step
+const HISTORY_MERGE_SECONDS: f64 = 0.5;
+
+impl<BiomeT, Picker> WorleyHistory<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static,
+{
+    ///! record the state after an edit
+    pub fn record(&mut self, worley: &Worley<BiomeT, Picker>, time: f64) {
+        // a new edit drops everything that could be redone
+        self.states.truncate(self.index + 1);
+        match time - self.last_record < HISTORY_MERGE_SECONDS && self.index > 0 {
+            true => self.states[self.index] = worley.clone(),
+            false => {
+                self.states.push_back(worley.clone());
+                if self.states.len() > self.limit.max(2) {
+                    self.states.pop_front();
+                }
+                self.index = self.states.len() - 1;
+            }
+        }
+        self.last_record = time;
+    }
+
+    ///! record a state as its own undo step, never merged with the edits around it
+    ///! (e.g. loading a file)
+    pub fn record_step(&mut self, worley: &Worley<BiomeT, Picker>) {
+        self.last_record = f64::NEG_INFINITY;
+        self.record(worley, f64::NEG_INFINITY);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.index > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.index + 1 < self.states.len()
+    }
+
+    ///! step back, returns the state to restore
+    pub fn undo(&mut self) -> Option<&Worley<BiomeT, Picker>> {
+        if !self.can_undo() {
+            return None;
+        }
+        self.index -= 1;
+        self.last_record = f64::NEG_INFINITY;
+        self.states.get(self.index)
+    }
+
+    ///! step forward, returns the state to restore
+    pub fn redo(&mut self) -> Option<&Worley<BiomeT, Picker>> {
+        if !self.can_redo() {
+            return None;
+        }
+        self.index += 1;
+        self.last_record = f64::NEG_INFINITY;
+        self.states.get(self.index)
+    }
+}
+
+// undo/redo buttons, ctrl+z / ctrl+shift+z / ctrl+y
+fn history_ui<WorleyResT, BiomeT, Picker>(ui: &mut egui::Ui, world: &mut World)
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static,
+{
+    init_history::<WorleyResT, BiomeT, Picker>(world);
+    let history = world.resource::<WorleyHistory<BiomeT, Picker>>();
+    if history.awaiting_load {
+        // edits are recorded as they happen, so only the load can make these differ
+        let recorded = history.states[history.index].config_hash();
+        let current = world.resource::<WorleyResT>().get_worley();
+        if current.config_hash() != recorded {
+            let current = current.clone();
+            let mut history = world.resource_mut::<WorleyHistory<BiomeT, Picker>>();
+            history.record_step(&current);
+            history.awaiting_load = false;
+        }
+    }
+    let mut history = world.resource_mut::<WorleyHistory<BiomeT, Picker>>();
+
+    // leave ctrl+z to a focused text field's own undo
+    let typing = ui.ctx().wants_keyboard_input();
+    let (undo_key, redo_key) = ui.input(|i| {
+        if typing {
+            return (false, false);
+        }
+        let z = i.modifiers.command && i.key_pressed(egui::Key::Z);
+        let y = i.modifiers.command && i.key_pressed(egui::Key::Y);
+        (z && !i.modifiers.shift, (z && i.modifiers.shift) || y)
+    });
+    let mut restore = None;
+    ui.horizontal(|ui| {
+        let can_undo = history.can_undo();
+        let can_redo = history.can_redo();
+        if ui
+            .add_enabled(can_undo, egui::Button::new("undo"))
+            .clicked()
+            || undo_key
+        {
+            restore = history.undo().cloned();
+        }
+        if ui
+            .add_enabled(can_redo, egui::Button::new("redo"))
+            .clicked()
+            || redo_key
+        {
+            restore = history.redo().cloned();
+        }
+    });
+    if let Some(worley) = restore {
+        *world.resource_mut::<WorleyResT>().get_worley_mut() = worley;
+    }
+}
+
+// the history, holding the current state if nothing was recorded yet
+fn init_history<WorleyResT, BiomeT, Picker>(world: &mut World)
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static,
+{
+    world.init_resource::<WorleyHistory<BiomeT, Picker>>();
+    if world
+        .resource::<WorleyHistory<BiomeT, Picker>>()
+        .states
+        .is_empty()
+    {
+        // the state before the first edit
+        let current = world.resource::<WorleyResT>().get_worley().clone();
+        world
+            .resource_mut::<WorleyHistory<BiomeT, Picker>>()
+            .record(&current, f64::NEG_INFINITY);
+    }
+}
+
+fn record_history<WorleyResT, BiomeT, Picker>(ui: &mut egui::Ui, world: &mut World)
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static,
+{
+    let time = ui.input(|i| i.time);
+    let current = world.resource::<WorleyResT>().get_worley().clone();
+    world
+        .resource_mut::<WorleyHistory<BiomeT, Picker>>()
+        .record(&current, time);
+}
+
+///! seed text field state and recently used seeds
+#[derive(Resource, Default)]
+pub struct SeedInput {
+    text: String,
+    ///! seed the text was last synced with
+    synced_seed: Option<u64>,
+    ///! most recent first
+    pub history: std::collections::VecDeque<u64>,
+}
+
+const SEED_HISTORY_LEN: usize = 10;
+
+impl SeedInput {
+    fn remember(&mut self, seed: u64) {
+        self.history.retain(|s| *s != seed);
+        self.history.push_front(seed);
+        self.history.truncate(SEED_HISTORY_LEN);
+    }
+}
+
+///! pixels per side of a seed thumbnail
+const THUMBNAIL_SIZE: u32 = 48;
+
+///! the 3x3 seed comparison grid
+#[derive(Resource, Default)]
+pub struct SeedThumbnails {
+    ///! random seeds instead of the ones around the current seed
+    pub random: bool,
+    seeds: Vec<u64>,
+    textures: Vec<egui::TextureHandle>,
+}
+
+// 3x3 thumbnails of other seeds with the current settings, click one to use its seed
+fn seed_thumbnails_ui<WorleyResT, BiomeT, Picker>(ui: &mut egui::Ui, world: &mut World) -> bool
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + DebugColor<BiomeT> + std::default::Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + 'static,
+{
+    world.init_resource::<SeedThumbnails>();
+    let mut chosen = None;
+    egui::CollapsingHeader::new("seed thumbnails").show(ui, |ui| {
+        let (offset, preview_scale) = world
+            .get_resource::<WorleyImage>()
+            .map_or(((0.0, 0.0), 1.0), |w| (w.preview_offset, w.preview_scale));
+        let seed = world.resource::<WorleyResT>().get_worley().seed;
+
+        let mut random = world.resource::<SeedThumbnails>().random;
+        let mut generate = false;
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut random, false, "neighbour seeds");
+            ui.selectable_value(&mut random, true, "random seeds");
+            generate = ui.button("generate").clicked();
+        });
+        world.resource_mut::<SeedThumbnails>().random = random;
+        if generate {
+            let seeds: Vec<u64> = match random {
+                true => (0..9).map(|_| rand::random::<u64>()).collect(),
+                // the current seed in the middle
+                false => (0..9u64)
+                    .map(|i| seed.wrapping_add(i).wrapping_sub(4))
+                    .collect(),
+            };
+            // same area as the preview
+            let scale = preview_scale * IMG_SIZE as f64 / THUMBNAIL_SIZE as f64;
+            let mut worley = world.resource::<WorleyResT>().get_worley().clone();
+            let textures = seeds
+                .iter()
+                .map(|seed| {
+                    worley.set_seed(*seed);
+                    let render = render_preview(
+                        &worley,
+                        offset,
+                        scale,
+                        THUMBNAIL_SIZE,
+                        PreviewOverlay::default(),
+                        None,
+                    );
+                    let image = egui::ColorImage::from_rgba_unmultiplied(
+                        [THUMBNAIL_SIZE as usize, THUMBNAIL_SIZE as usize],
+                        &render.rgba,
+                    );
+                    ui.ctx().load_texture(
+                        format!("worley_seed_{seed}"),
+                        image,
+                        egui::TextureOptions::NEAREST,
+                    )
+                })
+                .collect();
+            let mut thumbnails = world.resource_mut::<SeedThumbnails>();
+            thumbnails.seeds = seeds;
+            thumbnails.textures = textures;
+        }
+
+        let thumbnails = world.resource::<SeedThumbnails>();
+        if thumbnails.textures.is_empty() {
+            ui.label("generate to compare seeds");
+            return;
+        }
+        egui::Grid::new("worley_seed_thumbnails").show(ui, |ui| {
+            for (i, (texture, thumbnail_seed)) in thumbnails
+                .textures
+                .iter()
+                .zip(&thumbnails.seeds)
+                .enumerate()
+            {
+                let image = egui::Image::new((texture.id(), egui::vec2(64.0, 64.0)));
+                if ui
+                    .add(egui::Button::image(image).selected(*thumbnail_seed == seed))
+                    .on_hover_text(format!("seed {thumbnail_seed}"))
+                    .clicked()
+                {
+                    chosen = Some(*thumbnail_seed);
+                }
+                if i % 3 == 2 {
+                    ui.end_row();
+                }
+            }
+        });
+    });
+
+    let Some(seed) = chosen else {
+        return false;
+    };
+    world.init_resource::<SeedInput>();
+    world.resource_mut::<SeedInput>().remember(seed);
+    world
+        .resource_mut::<WorleyResT>()
+        .get_worley_mut()
+        .set_seed(seed);
+    true
+}
+
+// any u64 seed as text, randomize, and a list of recent seeds. returns a newly chosen seed
+fn seed_ui(ui: &mut egui::Ui, input: &mut SeedInput, seed: u64) -> Option<u64> {
+    if input.synced_seed != Some(seed) {
+        // changed elsewhere (undo, file load, ..)
+        input.text = seed.to_string();
+        input.synced_seed = Some(seed);
+        input.remember(seed);
+    }
+
+    let mut new_seed = None;
+    ui.horizontal(|ui| {
+        ui.label("seed");
+        let response = ui.add(egui::TextEdit::singleline(&mut input.text).desired_width(160.0));
+        match input.text.trim().parse::<u64>() {
+            Ok(parsed) if response.lost_focus() && parsed != seed => new_seed = Some(parsed),
+            Ok(_) => {}
+            Err(_) => {
+                ui.colored_label(egui::Color32::RED, "not a u64");
+            }
+        }
+        if ui.button("randomize").clicked() {
+            new_seed = Some(rand::random::<u64>());
+        }
+    });
+    egui::CollapsingHeader::new("recent seeds").show(ui, |ui| {
+        for recent in input.history.iter() {
+            if ui
+                .add(egui::widgets::Button::selectable(
+                    *recent == seed,
+                    recent.to_string(),
+                ))
+                .clicked()
+            {
+                new_seed = Some(*recent);
+            }
+        }
+    });
+
+    if let Some(new_seed) = new_seed {
+        input.text = new_seed.to_string();
+        input.synced_seed = Some(new_seed);
+        input.remember(new_seed);
+    }
+    new_seed
+}
+
+// tweaking ui for Worley
+fn tweak_ui<WorleyResT, BiomeT, Picker>(ui: &mut egui::Ui, world: &mut World) -> bool
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + std::fmt::Debug + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static,
+{
+    world.init_resource::<SeedInput>();
+    let (presets, editor) = world
+        .get_resource::<DebugPickerUi<Picker>>()
+        .map_or((Arc::default(), None), |p| (p.presets.clone(), p.editor));
+    let ranges = world
+        .resource::<DebugPluginSettings>()
+        .slider_ranges
+        .clone();
+    let immediate = apply_mode_ui::<WorleyResT, BiomeT, Picker>(ui, world);
+    let current_seed = world.resource::<WorleyResT>().get_worley().seed;
+    let new_seed = seed_ui(ui, &mut world.resource_mut::<SeedInput>(), current_seed);
+
+    let mut map_settings = world.resource_mut::<WorleyResT>();
+    let ms = map_settings.bypass_change_detection();
+    let mut worley = ms.get_worley_mut();
+
+    let mut any_changed = false;
+    if let Some(seed) = new_seed {
+        // derived warp seeds follow in rebuild() below
+        worley.seed = seed;
+        any_changed = true;
+    }
+    any_changed |= ui
+        .add(egui::Slider::new(&mut worley.sharpness, ranges.sharpness.clone()).text("Sharpness"))
+        .changed();
+
+    any_changed |= ui
+        .add(egui::Slider::new(&mut worley.k, ranges.k.clone()).text("k (nearest)"))
+        .changed();
+    any_changed |= ui
+        .add(egui::Slider::new(&mut worley.zoom, ranges.zoom.clone()).text("Zoom"))
+        .changed();
+
+    any_changed |= optional_ui(
+        ui,
+        "kill threshold",
+        &mut worley.kill_percent_threshold,
+        0.1,
+        |ui, kill_per| ui.add(egui::Slider::new(kill_per, ranges.kill_threshold.clone())),
+    );
+
+    egui::CollapsingHeader::new("distance fn").show(ui, |ui| {
+        let mut s = |worley: &mut Worley<BiomeT, Picker>,
+                     any_changed: &mut bool,
+                     target_metric: DistanceFn| {
+            if ui
+                .add(egui::widgets::Button::selectable(
+                    worley.distance_fn_config == target_metric,
+                    format!("{:?}", target_metric),
+                ))
+                .clicked()
+            {
+                worley.distance_fn_config = target_metric;
+                worley.distance_fn = target_metric.to_func();
+                *any_changed |= true;
+            }
+        };
+        s(&mut worley, &mut any_changed, DistanceFn::Euclidean);
+        s(&mut worley, &mut any_changed, DistanceFn::EuclideanSquared);
+        s(&mut worley, &mut any_changed, DistanceFn::Manhattan);
+        s(&mut worley, &mut any_changed, DistanceFn::Chebyshev);
+        s(&mut worley, &mut any_changed, DistanceFn::Hybrid);
+    });
+
+    egui::CollapsingHeader::new("biome picker").show(ui, |ui| {
+        if !presets.is_empty() {
+            egui::ComboBox::from_label("preset")
+                .selected_text("switch to…")
+                .show_ui(ui, |ui| {
+                    for (name, picker) in presets.iter() {
+                        if ui.selectable_label(false, name).clicked() {
+                            worley.biome_picker = picker.clone();
+                            any_changed = true;
+                        }
+                    }
+                });
+        }
+        // a registered editor, the built in one for SimpleBiomePicker, or none
+        if let Some(editor) = editor {
+            any_changed |= editor(ui, &mut worley.biome_picker);
+        } else if let Some(picker) = (&mut worley.biome_picker as &mut dyn std::any::Any)
+            .downcast_mut::<SimpleBiomePicker<BiomeT>>()
+        {
+            any_changed |= simple_picker_ui(ui, picker);
+        } else if presets.is_empty() {
+            ui.label("no editor for this picker, see DebugPlugin::with_picker_ui");
+        }
+    });
+
+    ui.group(|ui| {
+        ui.horizontal(|ui| {
+            ui.label("warp space");
+            for space in [WarpSpace::Cell, WarpSpace::World] {
+                if ui
+                    .add(egui::widgets::Button::selectable(
+                        worley.warp_space == space,
+                        format!("{:?}", space),
+                    ))
+                    .clicked()
+                {
+                    worley.warp_space = space;
+                    any_changed = true;
+                }
+            }
+        });
+        any_changed |= warp_ui(ui, &mut worley.warp_settings, &ranges);
+
+        egui::CollapsingHeader::new("warp layers").show(ui, |ui| {
+            any_changed |= warp_layers_ui(ui, &mut worley.warp_settings.layers, &ranges);
+        });
+    });
+
+    if any_changed {
+        // seed/distance fn edits above write fields directly, refresh what's derived from them
+        worley.rebuild();
+        if immediate {
+            // trigger change to MapSettings, causing an update to voxels
+            map_settings.set_changed();
+        } else {
+            let last_edit = world.resource::<Time>().elapsed_secs_f64();
+            world.insert_resource(PendingApply { last_edit });
+        }
+    }
+    any_changed
+}
+
+// pick the ApplyMode, apply pending edits. returns true if edits apply immediately
+fn apply_mode_ui<WorleyResT, BiomeT, Picker>(ui: &mut egui::Ui, world: &mut World) -> bool
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let mut settings = world.resource_mut::<DebugPluginSettings>();
+    // not a view change, don't rerender the preview for it
+    let settings = settings.bypass_change_detection();
+    let mode = settings.apply_mode;
+    ui.horizontal(|ui| {
+        ui.label("apply");
+        ui.selectable_value(
+            &mut settings.apply_mode,
+            ApplyMode::Immediate,
+            "immediately",
+        );
+        let debounce = match mode {
+            ApplyMode::Debounce(seconds) => seconds,
+            _ => 0.5,
+        };
+        ui.selectable_value(
+            &mut settings.apply_mode,
+            ApplyMode::Debounce(debounce),
+            "after a pause",
+        );
+        ui.selectable_value(&mut settings.apply_mode, ApplyMode::Manual, "on click");
+        if let ApplyMode::Debounce(seconds) = &mut settings.apply_mode {
+            ui.add(
+                egui::DragValue::new(seconds)
+                    .range(0.0..=10.0)
+                    .speed(0.05)
+                    .suffix("s"),
+            );
+        }
+    });
+    let immediate = settings.apply_mode == ApplyMode::Immediate;
+    let pending = world.contains_resource::<PendingApply>();
+    if pending && (immediate || ui.button("apply").clicked()) {
+        apply_pending::<WorleyResT, BiomeT, Picker>(world);
+    } else if pending {
+        ui.label("edits not applied yet");
+    }
+    immediate
+}
+
+fn apply_pending<WorleyResT, BiomeT, Picker>(world: &mut World)
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    world.remove_resource::<PendingApply>();
+    world.resource_mut::<WorleyResT>().set_changed();
+}
+
+///! ApplyMode::Debounce: apply once the edits paused long enough
+fn apply_debounced<WorleyResT, BiomeT, Picker>(world: &mut World)
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let ApplyMode::Debounce(seconds) = world.resource::<DebugPluginSettings>().apply_mode else {
+        return;
+    };
+    let Some(pending) = world.get_resource::<PendingApply>() else {
+        return;
+    };
+    if world.resource::<Time>().elapsed_secs_f64() - pending.last_edit >= seconds {
+        apply_pending::<WorleyResT, BiomeT, Picker>(world);
+    }
+}
+
+// write the preview area at DebugPluginSettings::export_resolution to assets/<name>.preview.png
+#[cfg(feature = "image")]
+fn preview_export_ui<WorleyResT, BiomeT, Picker>(ui: &mut egui::Ui, world: &mut World)
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + 'static + DebugColor<BiomeT> + std::default::Default + Send + Sync,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    let mut settings = world.resource_mut::<DebugPluginSettings>();
+    ui.add(
+        egui::DragValue::new(&mut settings.bypass_change_detection().export_resolution)
+            .range(16..=8192)
+            .prefix("export resolution: "),
+    );
+    let resolution = settings.export_resolution;
+    let overlay = settings.overlay;
+    let (export, snapshot) = ui
+        .horizontal(|ui| {
+            let export = ui.button("save preview as png").clicked();
+            let snapshot = ui
+                .button("snapshot")
+                .on_hover_text("preview png + config, named by time, into snapshot_dir")
+                .clicked();
+            (export, snapshot)
+        })
+        .inner;
+    if !export && !snapshot {
+        return;
+    }
+    let height_fn = preview_height_fn(
+        world.resource::<DebugPluginSettings>(),
+        world.get_resource::<DebugHeightFn<BiomeT>>(),
+    );
+
+    let (offset, preview_scale) = world
+        .get_resource::<WorleyImage>()
+        .map_or(((0.0, 0.0), 1.0), |w| (w.preview_offset, w.preview_scale));
+    let scale = preview_scale * IMG_SIZE as f64 / resolution as f64;
+    let worley = world.resource::<WorleyResT>().get_worley();
+    let img = render_preview(worley, offset, scale, resolution, overlay, height_fn).to_image();
+
+    if snapshot {
+        write_snapshot::<WorleyResT, BiomeT, Picker>(world, &img);
+    }
+    if !export {
+        return;
+    }
+    let name = world
+        .get_resource::<SaveWorleyFilename>()
+        .map(|f| f.0.clone())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "worley".to_string());
+    let path = format!("assets/{}.preview.png", name);
+    let result = img.save(&path);
+    info!("saving preview {:?} result: {:?}", path, result);
 }
 
-#[derive(Resource)]
-pub struct SaveWorleyFilename(pub String);
+// <snapshot_dir>/worley_<unix seconds>.png, and the config through the DebugPersistence's to_text
+#[cfg(feature = "image")]
+fn write_snapshot<WorleyResT, BiomeT, Picker>(world: &World, img: &image::RgbaImage)
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    let dir = &world.resource::<DebugPluginSettings>().snapshot_dir;
+    if let Err(err) = std::fs::create_dir_all(dir) {
+        error!("could not create snapshot folder {:?}: {:?}", dir, err);
+        return;
+    }
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |t| t.as_secs());
+    let base = format!("{}/worley_{}", dir, timestamp);
 
-impl FromWorld for SaveWorleyFilename {
-    fn from_world(_world: &mut World) -> Self {
-        Self(String::default())
+    let png = format!("{}.png", base);
+    let result = img.save(&png);
+    info!("saving snapshot {:?} result: {:?}", png, result);
+
+    let worley = world.resource::<WorleyResT>().get_worley();
+    let text = world
+        .get_resource::<DebugPersistence<BiomeT, Picker>>()
+        .and_then(|persistence| persistence.0.to_text(worley));
+    match text {
+        Some((text, extension)) => {
+            let config = format!("{}.{}", base, extension);
+            let result = std::fs::write(&config, text);
+            info!("saving snapshot {:?} result: {:?}", config, result);
+        }
+        None => warn!(
+            "snapshot {:?} has no config, the persistence can't write text",
+            png
+        ),
     }
 }
 
-#[cfg(not(feature = "serde"))]
-fn inspector_ui<WorleyResT, BiomeT, Picker>(mut world: &mut World)
+#[cfg(not(feature = "image"))]
+fn preview_export_ui<WorleyResT, BiomeT, Picker>(ui: &mut egui::Ui, _world: &mut World)
 where
     WorleyResT: Resource + GetWorley<BiomeT, Picker>,
-    BiomeT: BiomeVariants + 'static,
+    BiomeT: BiomeVariants + 'static + DebugColor<BiomeT> + std::default::Default,
     Picker: BiomePicker<BiomeT> + Default + 'static,
 {
-    let mut egui_context = world
-        .query_filtered::<&mut EguiContext, With<bevy_egui::PrimaryEguiContext>>()
-        .single(world)
-        .expect("EguiContext not found")
-        .clone();
-
-    egui::Window::new("worley UI").show(egui_context.get_mut(), |ui| {
-        egui::ScrollArea::both().show(ui, |ui| {
-            // SERIALIZE FEATURES disabled
-            ui.add_enabled(false, egui::Button::new("save worley to file"));
-            ui.colored_label(egui::Color32::RED, "saving requires feature=\"serde\"");
-            ui.add_enabled(false, egui::Button::new("Load worley file"));
-            ui.colored_label(egui::Color32::RED, "loading requires feature=\"serde\"");
-
-            tweak_ui::<WorleyResT, BiomeT, Picker>(ui, &mut world);
-        });
+    ui.horizontal(|ui| {
+        ui.add_enabled(false, egui::Button::new("save preview as png"));
+        ui.add_enabled(false, egui::Button::new("snapshot"));
     });
+    ui.colored_label(egui::Color32::RED, "png export requires feature=\"image\"");
 }
 
-#[cfg(feature = "serde")]
-fn inspector_ui<WorleyResT, BiomeT, Picker>(mut world: &mut World)
+// every variant with its color and coverage of the current preview.
+// the color pickers write Worley::debug_palette, returns true if it changed
+fn legend_ui<WorleyResT, BiomeT, Picker>(ui: &mut egui::Ui, world: &mut World) -> bool
 where
     WorleyResT: Resource + GetWorley<BiomeT, Picker>,
-    BiomeT: BiomeVariants + 'static + Serialize + for<'de> Deserialize<'de>,
-    Picker: BiomePicker<BiomeT> + Default + 'static + Serialize + for<'de> Deserialize<'de>,
+    BiomeT: BiomeVariants + DebugColor<BiomeT> + std::fmt::Debug + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
 {
-    let mut egui_context = world
-        .query_filtered::<&mut EguiContext, With<bevy_egui::PrimaryEguiContext>>()
-        .single(world)
-        .expect("EguiContext not found")
+    let coverage = world
+        .get_resource::<WorleyImage>()
+        .map(|w| w.coverage.clone())
+        .unwrap_or_default();
+    let mut palette = world
+        .resource::<WorleyResT>()
+        .get_worley()
+        .debug_palette
         .clone();
-
-    egui::Window::new("worley UI").show(egui_context.get_mut(), |ui| {
-        egui::ScrollArea::both().show(ui, |ui| {
-            // SERIALIZE FEATURES to save/load our worley to file
-            let mut worley_file_name = world.get_resource_or_init::<SaveWorleyFilename>();
-            ui.add(egui::Label::new("worley file name: (save or load)"));
-            ui.add(egui::TextEdit::singleline(&mut worley_file_name.0));
-            let file_name = worley_file_name.0.clone();
-
-            if ui.add(egui::Button::new("save worley to file")).clicked() {
-                let map_settings = world.get_resource::<WorleyResT>().expect("WorleyResT");
-
-                let deserialized =
-                    ron::ser::to_string_pretty(map_settings.get_worley(), PrettyConfig::default())
-                        .expect("deserialize");
-
-                let path = format!("assets/{}.worley.ron", &file_name);
-                let result = std::fs::write(&path, deserialized);
-                info!("saving {:?} result: {:?}", path, result);
-            }
-
-            if ui.add(egui::Button::new("load worley file")).clicked() {
-                let path = format!("assets/{}.worley.ron", &file_name);
-                let file = std::fs::read_to_string(&path);
-                match file {
-                    Ok(f) => {
-                        let result = ron::from_str::<Worley<BiomeT, Picker>>(&f);
-                        match result {
-                            Ok(new_worley) => {
-                                // REPLACE
-                                let mut map_settings = world.resource_mut::<WorleyResT>();
-                                let worley = map_settings.get_worley_mut();
-                                *worley = new_worley;
-                                info!("replaced current worley");
-                            }
-                            Err(err) => {
-                                error!("failed to deserialize worley: {:?}", err);
-                            }
+    let overlay = world.resource::<DebugPluginSettings>().overlay;
+    let variants = BiomeT::variants();
+    palette.resize(variants.len(), None);
+    let mut changed = false;
+    egui::CollapsingHeader::new("legend")
+        .default_open(true)
+        .show(ui, |ui| {
+            for (i, biome) in variants.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let c = overlay.biome_color(&palette, Some(i), biome);
+                    if overlay.color_blind_palette {
+                        // debug_palette edits wouldn't show under the color blind palette
+                        egui::color_picker::show_color(
+                            ui,
+                            egui_color(c),
+                            ui.spacing().interact_size,
+                        );
+                    } else {
+                        let mut rgb = [c.red, c.green, c.blue];
+                        if egui::color_picker::color_edit_button_rgb(ui, &mut rgb).changed() {
+                            palette[i] = Some(rgb);
+                            changed = true;
+                        }
+                        if palette[i].is_some()
+                            && ui
+                                .small_button("↺")
+                                .on_hover_text("back to DebugColor")
+                                .clicked()
+                        {
+                            palette[i] = None;
+                            changed = true;
                         }
                     }
-                    Err(err) => {
-                        error!("err loading worley file: {:?}, {:?}", path, err);
-                    }
-                }
+                    let percent = coverage.get(i).copied().unwrap_or(0.0) * 100.0;
+                    ui.label(format!("{:?}: {:.1}%", biome, percent));
+                });
             }
+        });
+    if changed {
+        // all None: nothing worth saving
+        if palette.iter().all(Option::is_none) {
+            palette.clear();
+        }
+        world
+            .resource_mut::<WorleyResT>()
+            .get_worley_mut()
+            .debug_palette = palette;
+    }
+    changed
+}
+
+// what Worley::get and Worley::cell_sample return for the InspectedPixel
+fn inspected_pixel_ui<WorleyResT, BiomeT, Picker>(ctx: &mut egui::Context, world: &mut World)
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + std::fmt::Debug + std::default::Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default + 'static,
+{
+    let Some(inspected) = world.get_resource::<InspectedPixel>().copied() else {
+        return;
+    };
+    let worley = world.resource::<WorleyResT>().get_worley();
+    let (x, z) = inspected.world;
+    let weights = worley.get(x, z);
+    let cell = worley.cell_sample(x, z);
 
-            tweak_ui::<WorleyResT, BiomeT, Picker>(ui, &mut world);
+    let mut open = true;
+    egui::Window::new("inspected pixel")
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.label(format!("world: ({:.2}, {:.2})", x, z));
+            ui.label(format!(
+                "warped (cell space): ({:.3}, {:.3})",
+                cell.warped.0, cell.warped.1
+            ));
+            ui.label(format!("nearest cell: ({}, {})", cell.cell.0, cell.cell.1));
+            ui.label(format!("F1: {:.4}  F2: {:.4}", cell.f1, cell.f2));
+            ui.separator();
+            for (w, biome) in &weights {
+                ui.label(format!("{:?}: {:.3}", biome, w));
+            }
         });
+    if !open {
+        world.remove_resource::<InspectedPixel>();
+    }
+}
+
+fn egui_color(c: Srgba) -> egui::Color32 {
+    egui::Color32::from_rgb(
+        (c.red * 255.0) as u8,
+        (c.green * 255.0) as u8,
+        (c.blue * 255.0) as u8,
+    )
+}
+
+// bar chart of WorleyImage::coverage, scaled so the most common biome fills the width
+fn coverage_histogram_ui<WorleyResT, BiomeT, Picker>(ui: &mut egui::Ui, world: &mut World)
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + DebugColor<BiomeT> + std::fmt::Debug + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let Some(worley_image) = world.get_resource::<WorleyImage>() else {
+        return;
+    };
+    let palette = &world.resource::<WorleyResT>().get_worley().debug_palette;
+    let overlay = world.resource::<DebugPluginSettings>().overlay;
+    let coverage = &worley_image.coverage;
+    egui::CollapsingHeader::new("coverage histogram").show(ui, |ui| {
+        let max = coverage.iter().copied().fold(0.0, f64::max).max(1e-9);
+        let bar_height = 16.0;
+        let label_width = 90.0;
+        for (i, biome) in BiomeT::variants().iter().enumerate() {
+            let fraction = coverage.get(i).copied().unwrap_or(0.0);
+            let width = ui.available_width().max(label_width + 40.0);
+            let (rect, _) =
+                ui.allocate_exact_size(egui::vec2(width, bar_height), egui::Sense::hover());
+            let painter = ui.painter();
+            painter.text(
+                rect.left_center(),
+                egui::Align2::LEFT_CENTER,
+                format!("{:?}", biome),
+                egui::FontId::proportional(12.0),
+                ui.visuals().text_color(),
+            );
+            let bar_max = rect.width() - label_width - 40.0;
+            let bar = egui::Rect::from_min_size(
+                rect.left_top() + egui::vec2(label_width, 2.0),
+                egui::vec2((fraction / max) as f32 * bar_max, bar_height - 4.0),
+            );
+            painter.rect_filled(
+                bar,
+                0.0,
+                egui_color(overlay.biome_color(palette, Some(i), biome)),
+            );
+            painter.text(
+                bar.right_center() + egui::vec2(4.0, 0.0),
+                egui::Align2::LEFT_CENTER,
+                format!("{:.1}%", fraction * 100.0),
+                egui::FontId::proportional(12.0),
+                ui.visuals().text_color(),
+            );
+        }
     });
 }
 
-// tweaking ui for Worley
-fn tweak_ui<WorleyResT, BiomeT, Picker>(ui: &mut egui::Ui, world: &mut World)
+///! samples along each axis for the typical F1/F2 of the falloff plot
+const FALLOFF_SAMPLES: i32 = 16;
+
+// the weight of a cell at distance d against one at the typical F2:
+// 1 / (1 + (d / F2)^sharpness), the blend of the nearest two cells of the weighting kernel.
+// dashed: mean F1 and F2 over a patch of the world, red: the kill threshold
+fn falloff_plot_ui<WorleyResT, BiomeT, Picker>(ui: &mut egui::Ui, world: &mut World)
 where
     WorleyResT: Resource + GetWorley<BiomeT, Picker>,
     BiomeT: BiomeVariants + 'static,
-    Picker: BiomePicker<BiomeT> + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
 {
-    let mut map_settings = world.resource_mut::<WorleyResT>();
-    let ms = map_settings.bypass_change_detection();
-    let mut worley = ms.get_worley_mut();
+    let worley = world.resource::<WorleyResT>().get_worley();
+    egui::CollapsingHeader::new("weight falloff").show(ui, |ui| {
+        let (mut f1_sum, mut f2_sum, mut count) = (0.0, 0.0, 0);
+        for ix in 0..FALLOFF_SAMPLES {
+            for iz in 0..FALLOFF_SAMPLES {
+                // ~4 cells across, off the cell lattice
+                let step = worley.zoom * 4.0 / FALLOFF_SAMPLES as f64;
+                let sample =
+                    worley.cell_sample((ix as f64 + 0.37) * step, (iz as f64 + 0.61) * step);
+                if sample.f2 < f64::MAX {
+                    f1_sum += sample.f1;
+                    f2_sum += sample.f2;
+                    count += 1;
+                }
+            }
+        }
+        if count == 0 {
+            ui.label("no cells to measure F1/F2 on");
+            return;
+        }
+        let f1 = f1_sum / count as f64;
+        let f2 = f2_sum / count as f64;
+        let weight = |d: f64| 1.0 / (1.0 + (d / f2).powf(worley.sharpness));
+        let x_max = (f2 * 2.0).max(1e-6);
+
+        let width = ui.available_width().max(120.0);
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(width, 120.0), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        let plot = rect.shrink(6.0);
+        let to_screen = |d: f64, w: f64| {
+            egui::pos2(
+                plot.left() + (d / x_max) as f32 * plot.width(),
+                plot.bottom() - w as f32 * plot.height(),
+            )
+        };
+        let text_color = ui.visuals().text_color();
+        painter.rect_stroke(
+            plot,
+            0.0,
+            egui::Stroke::new(1.0, ui.visuals().weak_text_color()),
+            egui::StrokeKind::Middle,
+        );
+        for (d, label) in [(f1, "F1"), (f2, "F2")] {
+            painter.add(egui::Shape::dashed_line(
+                &[to_screen(d, 0.0), to_screen(d, 1.0)],
+                egui::Stroke::new(1.0, text_color),
+                4.0,
+                3.0,
+            ));
+            painter.text(
+                to_screen(d, 1.0) + egui::vec2(3.0, 2.0),
+                egui::Align2::LEFT_TOP,
+                format!("{} {:.2}", label, d),
+                egui::FontId::proportional(11.0),
+                text_color,
+            );
+        }
+        if let Some(kill) = worley.kill_percent_threshold {
+            painter.line_segment(
+                [to_screen(0.0, kill), to_screen(x_max, kill)],
+                egui::Stroke::new(1.0, egui::Color32::RED),
+            );
+        }
+        let points = (0..=64)
+            .map(|i| {
+                let d = x_max * i as f64 / 64.0;
+                to_screen(d, weight(d))
+            })
+            .collect();
+        painter.add(egui::Shape::line(
+            points,
+            egui::Stroke::new(2.0, egui::Color32::LIGHT_BLUE),
+        ));
+        ui.label(format!(
+            "nearest cell's weight at F1: {:.0}% (sharpness {:.1})",
+            weight(f1) * 100.0,
+            worley.sharpness
+        ));
+    });
+}
+
+// toggles for PreviewOverlay
+fn overlay_ui(ui: &mut egui::Ui, world: &mut World) {
+    let mut settings = world.resource_mut::<DebugPluginSettings>();
+    let mut overlay = settings.overlay;
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        changed |= ui
+            .checkbox(&mut overlay.cell_points, "cell points")
+            .changed();
+        changed |= ui
+            .checkbox(&mut overlay.cell_borders, "cell borders")
+            .changed();
+        changed |= ui
+            .checkbox(&mut overlay.cell_grid, "cell grid (pre warp)")
+            .changed();
+        changed |= ui
+            .checkbox(&mut overlay.killed_biomes, "killed biomes")
+            .on_hover_text("magenta: the kill threshold removed a biome here")
+            .changed();
+    });
+    ui.horizontal(|ui| {
+        changed |= ui
+            .checkbox(&mut overlay.color_blind_palette, "color blind palette")
+            .on_hover_text(
+                "Okabe-Ito colors, distinguishable with red-green and blue-yellow deficiencies",
+            )
+            .changed();
+        changed |= ui
+            .checkbox(&mut overlay.biome_patterns, "biome patterns")
+            .on_hover_text("a hatching per biome, to tell biomes apart without color")
+            .changed();
+    });
+    let mut preview_mode = settings.preview_mode;
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut preview_mode, PreviewMode::Color, "colors");
+        ui.selectable_value(&mut preview_mode, PreviewMode::Height, "heights")
+            .on_hover_text("needs DebugPlugin::with_heights");
+    });
+    if preview_mode != settings.preview_mode {
+        settings.preview_mode = preview_mode;
+    }
+    if changed {
+        settings.overlay = overlay;
+    }
+    #[cfg(feature = "gpu")]
+    {
+        let mut gpu_preview = settings.gpu_preview;
+        if ui.checkbox(&mut gpu_preview, "gpu preview").changed() {
+            settings.gpu_preview = gpu_preview;
+        }
+    }
+    let mut inspector_window = settings.inspector_window;
+    if ui
+        .checkbox(&mut inspector_window, "inspector in its own window")
+        .changed()
+    {
+        settings.inspector_window = inspector_window;
+    }
+}
+
+// pan/zoom of the preview image, also controlled by dragging/scrolling the image
+fn preview_view_ui(ui: &mut egui::Ui, world: &mut World) {
+    if world.contains_resource::<PreviewTask>() {
+        ui.label("regenerating…");
+    }
+    let Some(mut worley_image) = world.get_resource_mut::<WorleyImage>() else {
+        return;
+    };
+    if let Some(stats) = worley_image.last_render {
+        ui.label(format!(
+            "last rebuild: {:.1} ms, {} samples, {:.2} M samples/s",
+            stats.elapsed.as_secs_f64() * 1000.0,
+            stats.samples,
+            stats.samples_per_second() / 1e6
+        ));
+    }
+    egui::CollapsingHeader::new("preview view").show(ui, |ui| {
+        let (mut x, mut z) = worley_image.preview_offset;
+        let mut scale = worley_image.preview_scale;
+        let mut changed = false;
+        changed |= ui.add(egui::DragValue::new(&mut x).prefix("x: ")).changed();
+        changed |= ui.add(egui::DragValue::new(&mut z).prefix("z: ")).changed();
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut scale, 1.0 / 64.0..=1024.0)
+                    .logarithmic(true)
+                    .text("units per pixel"),
+            )
+            .changed();
+        if ui.button("reset view").clicked() {
+            (x, z, scale) = (0.0, 0.0, 1.0);
+            changed = true;
+        }
+        if changed {
+            worley_image.preview_offset = (x, z);
+            worley_image.preview_scale = scale;
+        }
+
+        let mut scroll = worley_image.scroll;
+        let mut scroll_changed = ui.checkbox(&mut scroll.enabled, "animate scroll").changed();
+        ui.add_enabled_ui(scroll.enabled, |ui| {
+            scroll_changed |= ui
+                .add(egui::Slider::new(&mut scroll.angle, 0.0..=360.0).text("direction °"))
+                .changed();
+            scroll_changed |= ui
+                .add(
+                    egui::Slider::new(&mut scroll.speed, 1.0..=256.0)
+                        .logarithmic(true)
+                        .text("pixels per second"),
+                )
+                .changed();
+        });
+        if scroll_changed {
+            worley_image.scroll = scroll;
+        }
+    });
+}
+
+///! checkbox toggling an Option between None and Some, with the value's widget next to it.
+///! unchecking remembers the value (egui memory), checking again restores it instead of default
+fn optional_ui<T: Clone + Send + Sync + 'static>(
+    ui: &mut egui::Ui,
+    label: &str,
+    value: &mut Option<T>,
+    default: T,
+    add_value: impl FnOnce(&mut egui::Ui, &mut T) -> egui::Response,
+) -> bool {
+    let id = ui.id().with(label);
+    let mut enabled = value.is_some();
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        if ui.checkbox(&mut enabled, label).changed() {
+            *value = match enabled {
+                true => Some(ui.data(|d| d.get_temp::<T>(id)).unwrap_or(default)),
+                false => {
+                    if let Some(last) = value.take() {
+                        ui.data_mut(|d| d.insert_temp(id, last));
+                    }
+                    None
+                }
+            };
+            changed = true;
+        }
+        if let Some(value) = value {
+            changed |= add_value(ui, value).changed();
+        }
+    });
+    changed
+}
+
+// switch between the SimpleBiomePicker variants, and edit their biomes/weights
+fn simple_picker_ui<BiomeT>(ui: &mut egui::Ui, picker: &mut SimpleBiomePicker<BiomeT>) -> bool
+where
+    BiomeT: BiomeVariants + std::fmt::Debug + 'static,
+{
+    let mut any_changed = false;
+    let variants = BiomeT::variants();
+    let same = |a: &BiomeT, b: &BiomeT| std::mem::discriminant(a) == std::mem::discriminant(b);
+    let name = match picker {
+        SimpleBiomePicker::Any => "Any",
+        SimpleBiomePicker::AnyOf(_) => "AnyOf",
+        SimpleBiomePicker::Weighted(_) => "Weighted",
+        SimpleBiomePicker::Single(_) => "Single",
+    };
+    egui::ComboBox::from_label("picker")
+        .selected_text(name)
+        .show_ui(ui, |ui| {
+            if ui.selectable_label(name == "Any", "Any").clicked() && name != "Any" {
+                *picker = SimpleBiomePicker::Any;
+                any_changed = true;
+            }
+            if ui.selectable_label(name == "AnyOf", "AnyOf").clicked() && name != "AnyOf" {
+                *picker = SimpleBiomePicker::AnyOf(variants.to_vec());
+                any_changed = true;
+            }
+            if ui
+                .selectable_label(name == "Weighted", "Weighted")
+                .clicked()
+                && name != "Weighted"
+            {
+                // start out equal, the weights are odds that should sum to 1.0
+                let weight = 1.0 / variants.len().max(1) as f32;
+                let weights = variants.iter().map(|b| (*b, weight)).collect();
+                *picker = SimpleBiomePicker::Weighted(weights);
+                any_changed = true;
+            }
+            if ui.selectable_label(name == "Single", "Single").clicked()
+                && name != "Single"
+                && let Some(first) = variants.first()
+            {
+                *picker = SimpleBiomePicker::Single(*first);
+                any_changed = true;
+            }
+        });
+
+    if let SimpleBiomePicker::AnyOf(biomes) = picker {
+        for biome in variants {
+            let position = biomes.iter().position(|b| same(b, biome));
+            let mut included = position.is_some();
+            // an empty AnyOf has nothing to pick, keep the last biome
+            let last = included && biomes.len() == 1;
+            if ui
+                .add_enabled(
+                    !last,
+                    egui::Checkbox::new(&mut included, format!("{:?}", biome)),
+                )
+                .changed()
+            {
+                match position {
+                    Some(idx) => {
+                        biomes.remove(idx);
+                    }
+                    None => biomes.push(*biome),
+                }
+                any_changed = true;
+            }
+        }
+    }
+
+    if let SimpleBiomePicker::Single(single) = picker {
+        egui::ComboBox::from_label("biome")
+            .selected_text(format!("{:?}", single))
+            .show_ui(ui, |ui| {
+                for biome in variants {
+                    if ui
+                        .selectable_label(same(single, biome), format!("{:?}", biome))
+                        .clicked()
+                        && !same(single, biome)
+                    {
+                        *single = *biome;
+                        any_changed = true;
+                    }
+                }
+            });
+    }
+
+    if let SimpleBiomePicker::Weighted(weights) = picker {
+        for (i, (biome, weight)) in weights.iter_mut().enumerate() {
+            ui.push_id(i, |ui| {
+                any_changed |= ui
+                    .add(egui::Slider::new(weight, 0.0..=1.0).text(format!("{:?}", biome)))
+                    .changed();
+            });
+        }
+        let sum: f32 = weights.iter().map(|(_, w)| *w).sum();
+        ui.label(format!("sum: {:.3} (normalize to 1.0)", sum));
+        if ui.button("normalize").clicked() {
+            if sum > 0.0 {
+                for (_, w) in weights.iter_mut() {
+                    *w /= sum;
+                }
+                any_changed = true;
+            }
+        }
+    }
+    any_changed
+}
 
+// tweaking ui for a single warp layer
+fn warp_ui(ui: &mut egui::Ui, warp: &mut WarpSettings, ranges: &SliderRanges) -> bool {
     let mut any_changed = false;
     any_changed |= ui
-        .add(egui::Slider::new(&mut worley.seed, 0..=100).text("seed"))
+        .add(
+            egui::Slider::new(&mut warp.strength, ranges.warp_strength.clone())
+                .text("Warp strength"),
+        )
         .changed();
     any_changed |= ui
-        .add(egui::Slider::new(&mut worley.sharpness, 0.5..=20.0).text("Sharpness"))
+        .add(
+            egui::Slider::new(&mut warp.strength_axes.0, ranges.warp_strength_axes.clone())
+                .text("strength x"),
+        )
         .changed();
-
     any_changed |= ui
-        .add(egui::Slider::new(&mut worley.k, 1..=8).text("k (nearest)"))
+        .add(
+            egui::Slider::new(&mut warp.strength_axes.1, ranges.warp_strength_axes.clone())
+                .text("strength z"),
+        )
         .changed();
     any_changed |= ui
-        .add(egui::Slider::new(&mut worley.zoom, 10.0..=200.0).text("Zoom"))
+        .add(
+            egui::Slider::new(&mut warp.noise.frequency, ranges.warp_frequency.clone())
+                .text("Warp frequency"),
+        )
         .changed();
-
-    let mut kill_per = worley.kill_percent_threshold.unwrap_or(0.0);
-    let kill_per_changed = ui
-        .add(egui::Slider::new(&mut kill_per, 0.0..=0.99).text("kill threshold"))
+    any_changed |= ui
+        .add(
+            egui::Slider::new(&mut warp.iterations, ranges.warp_iterations.clone())
+                .text("Warp iterations"),
+        )
+        .changed();
+    any_changed |= ui
+        .add(
+            egui::Slider::new(
+                &mut warp.noise.fractal_lacunarity,
+                ranges.fractal_lacunarity.clone(),
+            )
+            .text("fractal lacunarity"),
+        )
         .changed();
-    any_changed |= kill_per_changed;
-    if kill_per_changed {
-        worley.kill_percent_threshold = Some(kill_per);
+
+    let mut fractal_gain = warp.noise.get_fractal_gain();
+    if ui
+        .add(egui::Slider::new(&mut fractal_gain, ranges.fractal_gain.clone()).text("fractal gain"))
+        .changed()
+    {
+        warp.noise.set_fractal_gain(fractal_gain);
+        any_changed = true;
     }
+    any_changed |= ui
+        .add(
+            egui::Slider::new(
+                &mut warp.noise.fractal_octaves,
+                ranges.fractal_octaves.clone(),
+            )
+            .text("fractal octaves"),
+        )
+        .changed();
 
-    egui::CollapsingHeader::new("distance fn").show(ui, |ui| {
-        let mut s = |worley: &mut Worley<BiomeT, Picker>,
-                     any_changed: &mut bool,
-                     target_metric: DistanceFn| {
+    ui.horizontal(|ui| {
+        ui.label("warp mode");
+        for mode in [WarpMode::Offset, WarpMode::Curl] {
             if ui
                 .add(egui::widgets::Button::selectable(
-                    worley.distance_fn_config == target_metric,
-                    format!("{:?}", target_metric),
+                    warp.mode == mode,
+                    format!("{:?}", mode),
                 ))
                 .clicked()
             {
-                worley.distance_fn_config = target_metric;
-                worley.distance_fn = target_metric.to_func();
-                *any_changed |= true;
+                warp.mode = mode;
+                any_changed = true;
             }
-        };
-        s(&mut worley, &mut any_changed, DistanceFn::Euclidean);
-        s(&mut worley, &mut any_changed, DistanceFn::EuclideanSquared);
-        s(&mut worley, &mut any_changed, DistanceFn::Manhattan);
-        s(&mut worley, &mut any_changed, DistanceFn::Chebyshev);
-        s(&mut worley, &mut any_changed, DistanceFn::Hybrid);
+        }
     });
 
-    ui.group(|ui| {
-        if ui
-            .add(
-                egui::Slider::new(&mut worley.warp_settings.strength, 0.0..=3.0)
-                    .text("Warp strength"),
-            )
-            .changed()
-        {
-            any_changed = true;
-        }
+    any_changed |= ui.checkbox(&mut warp.turbulence, "turbulence").changed();
+
+    let mut derive_seed = warp.seed_mode == WarpSeed::Derived;
+    if ui
+        .checkbox(&mut derive_seed, "derive warp seed from world seed")
+        .changed()
+    {
+        warp.seed_mode = match derive_seed {
+            true => WarpSeed::Derived,
+            false => WarpSeed::Explicit,
+        };
+        any_changed = true;
+    }
+    if warp.seed_mode == WarpSeed::Explicit {
+        let mut warp_seed = warp.noise.get_seed();
         if ui
-            .add(
-                egui::Slider::new(&mut worley.warp_settings.noise.frequency, 0.0..=1.0)
-                    .text("Warp frequency"),
-            )
+            .add(egui::DragValue::new(&mut warp_seed).prefix("warp seed: "))
             .changed()
         {
+            warp.noise.set_seed(warp_seed);
             any_changed = true;
         }
-        if ui
-            .add(
+    }
+
+    let mut default_modulation = FastNoise::default();
+    default_modulation.frequency = 0.05;
+    any_changed |= optional_ui(
+        ui,
+        "modulate strength",
+        &mut warp.strength_modulation,
+        default_modulation,
+        |ui, modulation| {
+            ui.add(
                 egui::Slider::new(
-                    &mut worley.warp_settings.noise.fractal_lacunarity,
-                    0.0..=4.0,
+                    &mut modulation.frequency,
+                    ranges.modulation_frequency.clone(),
                 )
-                .text("fractal lacunarity"),
+                .text("frequency"),
             )
-            .changed()
-        {
-            any_changed = true;
-        }
+        },
+    );
 
-        let mut fractal_gain = worley.warp_settings.noise.get_fractal_gain();
-        if ui
-            .add(egui::Slider::new(&mut fractal_gain, 0.0..=3.0).text("fractal gain"))
-            .changed()
-        {
-            worley.warp_settings.noise.set_fractal_gain(fractal_gain);
+    egui::CollapsingHeader::new("octave weights").show(ui, |ui| {
+        ui.label("empty: use the fractal settings");
+        let mut remove = None;
+        for (i, weight) in warp.octave_weights.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                any_changed |= ui
+                    .add(egui::Slider::new(weight, 0.0..=1.0).text(format!("octave {}", i)))
+                    .changed();
+                if ui.button("remove").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            warp.octave_weights.remove(i);
             any_changed = true;
         }
-        if ui
-            .add(
-                egui::Slider::new(&mut worley.warp_settings.noise.fractal_octaves, 0..=5)
-                    .text("fractal octaves"),
-            )
-            .changed()
-        {
+        if ui.button("add octave").clicked() {
+            let weight = warp.octave_weights.last().map_or(1.0, |w| w * 0.5);
+            warp.octave_weights.push(weight);
             any_changed = true;
         }
+    });
+
+    ui.label("warp noise");
+    egui::CollapsingHeader::new("noise type").show(ui, |ui| {
+        let mut noise = |warp: &mut WarpSettings, noise_type: NoiseType| {
+            if ui
+                .add(egui::widgets::Button::selectable(
+                    warp.noise.noise_type == noise_type,
+                    format!("{:?}", noise_type),
+                ))
+                .clicked()
+            {
+                warp.noise.noise_type = noise_type;
+                any_changed = true;
+            }
+        };
+        noise(warp, NoiseType::Value);
+        noise(warp, NoiseType::ValueFractal);
+        noise(warp, NoiseType::Perlin);
+        noise(warp, NoiseType::PerlinFractal);
+        noise(warp, NoiseType::Simplex);
+        noise(warp, NoiseType::SimplexFractal);
+        noise(warp, NoiseType::Cellular);
+        noise(warp, NoiseType::WhiteNoise);
+        noise(warp, NoiseType::Cubic);
+        noise(warp, NoiseType::CubicFractal);
+    });
+    egui::CollapsingHeader::new("fractal type").show(ui, |ui| {
+        let mut frac = |warp: &mut WarpSettings, fractal_type: FractalType| {
+            if ui
+                .add(egui::widgets::Button::selectable(
+                    warp.noise.fractal_type == fractal_type,
+                    format!("{:?}", fractal_type),
+                ))
+                .clicked()
+            {
+                warp.noise.fractal_type = fractal_type;
+                any_changed = true;
+            }
+        };
+        frac(warp, FractalType::FBM);
+        frac(warp, FractalType::Billow);
+        frac(warp, FractalType::RigidMulti);
+    });
+    egui::CollapsingHeader::new("interpolation").show(ui, |ui| {
+        let mut interp = |warp: &mut WarpSettings, interp: Interp| {
+            if ui
+                .add(egui::widgets::Button::selectable(
+                    warp.noise.get_interp() == interp,
+                    format!("{:?}", interp),
+                ))
+                .clicked()
+            {
+                warp.noise.set_interp(interp);
+                any_changed = true;
+            }
+        };
+        interp(warp, Interp::Linear);
+        interp(warp, Interp::Hermite);
+        interp(warp, Interp::Quintic);
+    });
 
-        ui.label("warp noise");
-        egui::CollapsingHeader::new("noise type").show(ui, |ui| {
-            let mut noise = |worley: &mut Worley<BiomeT, Picker>, noise_type: NoiseType| {
+    // cellular sub-settings only do something for NoiseType::Cellular
+    if warp.noise.noise_type == NoiseType::Cellular {
+        egui::CollapsingHeader::new("cellular distance fn").show(ui, |ui| {
+            let mut dist = |warp: &mut WarpSettings, f: CellularDistanceFunction| {
                 if ui
                     .add(egui::widgets::Button::selectable(
-                        worley.warp_settings.noise.noise_type == noise_type,
-                        format!("{:?}", noise_type),
+                        warp.noise.get_cellular_distance_function() == f,
+                        format!("{:?}", f),
                     ))
                     .clicked()
                 {
-                    worley.warp_settings.noise.noise_type = noise_type;
+                    warp.noise.set_cellular_distance_function(f);
                     any_changed = true;
                 }
             };
-            noise(&mut worley, NoiseType::Value);
-            noise(&mut worley, NoiseType::ValueFractal);
-            noise(&mut worley, NoiseType::Perlin);
-            noise(&mut worley, NoiseType::PerlinFractal);
-            noise(&mut worley, NoiseType::Simplex);
-            noise(&mut worley, NoiseType::SimplexFractal);
-            noise(&mut worley, NoiseType::Cellular);
-            noise(&mut worley, NoiseType::WhiteNoise);
-            noise(&mut worley, NoiseType::Cubic);
-            noise(&mut worley, NoiseType::CubicFractal);
+            dist(warp, CellularDistanceFunction::Euclidean);
+            dist(warp, CellularDistanceFunction::Manhattan);
+            dist(warp, CellularDistanceFunction::Natural);
         });
-        egui::CollapsingHeader::new("fractal type").show(ui, |ui| {
-            let mut frac = |worley: &mut Worley<BiomeT, Picker>, fractal_type: FractalType| {
+        egui::CollapsingHeader::new("cellular return type").show(ui, |ui| {
+            let mut ret = |warp: &mut WarpSettings, r: CellularReturnType| {
                 if ui
                     .add(egui::widgets::Button::selectable(
-                        worley.warp_settings.noise.fractal_type == fractal_type,
-                        format!("{:?}", fractal_type),
+                        warp.noise.get_cellular_return_type() == r,
+                        format!("{:?}", r),
                     ))
                     .clicked()
                 {
-                    worley.warp_settings.noise.fractal_type = fractal_type;
+                    warp.noise.set_cellular_return_type(r);
                     any_changed = true;
                 }
             };
-            frac(&mut worley, FractalType::FBM);
-            frac(&mut worley, FractalType::Billow);
-            frac(&mut worley, FractalType::RigidMulti);
+            ret(warp, CellularReturnType::CellValue);
+            ret(warp, CellularReturnType::Distance);
+            ret(warp, CellularReturnType::Distance2);
+            ret(warp, CellularReturnType::Distance2Add);
+            ret(warp, CellularReturnType::Distance2Sub);
+            ret(warp, CellularReturnType::Distance2Mul);
+            ret(warp, CellularReturnType::Distance2Div);
         });
-    });
+        let mut jitter = warp.noise.get_cellular_jitter();
+        if ui
+            .add(egui::Slider::new(&mut jitter, 0.0..=1.0).text("cellular jitter"))
+            .changed()
+        {
+            warp.noise.set_cellular_jitter(jitter);
+            any_changed = true;
+        }
+    }
+    any_changed
+}
 
-    if any_changed {
-        // trigger change to MapSettings, causing an update to voxels
-        map_settings.set_changed();
+// add/remove/reorder the extra warp layers
+fn warp_layers_ui(
+    ui: &mut egui::Ui,
+    layers: &mut Vec<WarpSettings>,
+    ranges: &SliderRanges,
+) -> bool {
+    let mut any_changed = false;
+    let mut remove = None;
+    let mut swap = None;
+    let layer_count = layers.len();
+    for (i, layer) in layers.iter_mut().enumerate() {
+        ui.push_id(i, |ui| {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("layer {}", i + 1));
+                    if ui.add_enabled(i > 0, egui::Button::new("up")).clicked() {
+                        swap = Some((i, i - 1));
+                    }
+                    if ui
+                        .add_enabled(i + 1 < layer_count, egui::Button::new("down"))
+                        .clicked()
+                    {
+                        swap = Some((i, i + 1));
+                    }
+                    if ui.button("remove").clicked() {
+                        remove = Some(i);
+                    }
+                });
+                any_changed |= warp_ui(ui, layer, ranges);
+            });
+        });
+    }
+    if let Some((a, b)) = swap {
+        layers.swap(a, b);
+        any_changed = true;
+    }
+    if let Some(i) = remove {
+        layers.remove(i);
+        any_changed = true;
+    }
+    if ui.button("add warp layer").clicked() {
+        layers.push(WarpSettings::default());
+        any_changed = true;
     }
+    any_changed
 }
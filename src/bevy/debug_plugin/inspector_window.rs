@@ -0,0 +1,67 @@
+use bevy::{
+    camera::RenderTarget,
+    ecs::schedule::ScheduleLabel,
+    prelude::*,
+    window::{WindowRef, WindowResolution},
+};
+use bevy_inspector_egui::bevy_egui::{EguiContext, EguiMultipassSchedule};
+
+use super::DebugPluginSettings;
+
+///! egui pass of the inspector window's context, the inspector runs here instead of in
+///! EguiPrimaryContextPass while DebugPluginSettings::inspector_window is on
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WorleyInspectorWindowPass;
+
+///! the camera rendering the inspector window's egui context
+#[derive(Component)]
+pub struct WorleyInspectorCamera {
+    pub window: Entity,
+}
+
+///! open/close the inspector window to follow DebugPluginSettings::inspector_window.
+///! closing the window by hand moves the inspector back into the primary window
+pub(super) fn sync_inspector_window(
+    mut commands: Commands,
+    mut settings: ResMut<DebugPluginSettings>,
+    cameras: Query<(Entity, &WorleyInspectorCamera)>,
+    windows: Query<(), With<Window>>,
+) {
+    let open = cameras.iter().next();
+    match (settings.inspector_window, open) {
+        (true, Some((camera, inspector))) => {
+            if windows.get(inspector.window).is_err() {
+                commands.entity(camera).despawn();
+                settings.inspector_window = false;
+            }
+        }
+        (true, None) => {
+            let window = commands
+                .spawn(Window {
+                    title: "worley inspector".to_string(),
+                    resolution: WindowResolution::new(480, 720),
+                    ..default()
+                })
+                .id();
+            commands.spawn((
+                WorleyInspectorCamera { window },
+                Camera2d,
+                RenderTarget::Window(WindowRef::Entity(window)),
+                EguiContext::default(),
+                EguiMultipassSchedule::new(WorleyInspectorWindowPass),
+            ));
+        }
+        (false, Some((camera, inspector))) => {
+            commands.entity(camera).despawn();
+            if let Ok(mut window) = commands.get_entity(inspector.window) {
+                window.despawn();
+            }
+        }
+        (false, None) => {}
+    }
+}
+
+///! the inspector shows in the primary window, unless it has a window of its own
+pub(super) fn if_inspector_in_primary(settings: Res<DebugPluginSettings>) -> bool {
+    settings.show_inspector_ui && !settings.inspector_window
+}
@@ -0,0 +1,142 @@
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::bevy::sets::WorleySet;
+
+use super::{
+    DebugPluginSettings, DisplayTextureSize, SaveWorleyFilename, WorleyImage, WorleyUiPreviewTag,
+};
+
+///! what the debug plugin remembers between runs, see DebugPluginSettings::state_file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DebugState {
+    pub show_preview_image: bool,
+    pub show_inspector_ui: bool,
+    pub export_resolution: u32,
+    pub cell_points: bool,
+    pub cell_borders: bool,
+    pub color_blind_palette: bool,
+    pub biome_patterns: bool,
+    pub preview_offset: (f64, f64),
+    pub preview_scale: f64,
+    ///! 0: auto, 1: medium, 2: big (DisplayTextureSize)
+    pub preview_size: u8,
+    ///! last used "save/load" file name
+    pub filename: String,
+}
+
+impl Default for DebugState {
+    fn default() -> Self {
+        let settings = DebugPluginSettings::default();
+        Self {
+            show_preview_image: settings.show_preview_image,
+            show_inspector_ui: settings.show_inspector_ui,
+            export_resolution: settings.export_resolution,
+            cell_points: false,
+            cell_borders: false,
+            color_blind_palette: false,
+            biome_patterns: false,
+            preview_offset: (0.0, 0.0),
+            preview_scale: 1.0,
+            preview_size: 0,
+            filename: String::new(),
+        }
+    }
+}
+
+///! restored state waiting for the preview to exist
+#[derive(Resource)]
+struct PendingDebugState(DebugState);
+
+///! restore the state file into the settings, and save it again on AppExit
+pub(super) fn add(app: &mut App) {
+    let path = app
+        .world()
+        .resource::<DebugPluginSettings>()
+        .state_file
+        .clone();
+    // missing or outdated file: start with the plugin's settings
+    if let Some(state) = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| ron::from_str::<DebugState>(&text).ok())
+    {
+        let mut settings = app.world_mut().resource_mut::<DebugPluginSettings>();
+        settings.show_preview_image = state.show_preview_image;
+        settings.show_inspector_ui = state.show_inspector_ui;
+        settings.export_resolution = state.export_resolution;
+        settings.overlay.cell_points = state.cell_points;
+        settings.overlay.cell_borders = state.cell_borders;
+        settings.overlay.color_blind_palette = state.color_blind_palette;
+        settings.overlay.biome_patterns = state.biome_patterns;
+        app.insert_resource(SaveWorleyFilename(state.filename.clone()));
+        app.insert_resource(PendingDebugState(state));
+    }
+    app.add_systems(Update, apply_pending_state.in_set(WorleySet::DebugUi));
+    app.add_systems(Last, save_on_exit.in_set(WorleySet::DebugUi));
+}
+
+fn apply_pending_state(
+    mut commands: Commands,
+    pending: Option<Res<PendingDebugState>>,
+    worley_image: Option<ResMut<WorleyImage>>,
+    mut preview: Query<(&mut DisplayTextureSize, &mut Node), With<WorleyUiPreviewTag>>,
+) {
+    let (Some(pending), Some(mut worley_image)) = (pending, worley_image) else {
+        return;
+    };
+    let state = &pending.0;
+    worley_image.preview_offset = state.preview_offset;
+    worley_image.preview_scale = state.preview_scale;
+    for (mut size, mut node) in preview.iter_mut() {
+        *size = match state.preview_size {
+            1 => DisplayTextureSize::Medium,
+            2 => DisplayTextureSize::Big,
+            _ => DisplayTextureSize::Auto,
+        };
+        node.width = size.node_size();
+    }
+    commands.remove_resource::<PendingDebugState>();
+}
+
+fn save_on_exit(
+    mut exit: MessageReader<AppExit>,
+    settings: Res<DebugPluginSettings>,
+    worley_image: Option<Res<WorleyImage>>,
+    filename: Option<Res<SaveWorleyFilename>>,
+    preview: Query<&DisplayTextureSize, With<WorleyUiPreviewTag>>,
+) {
+    if exit.read().next().is_none() {
+        return;
+    }
+    let mut state = DebugState {
+        show_preview_image: settings.show_preview_image,
+        show_inspector_ui: settings.show_inspector_ui,
+        export_resolution: settings.export_resolution,
+        cell_points: settings.overlay.cell_points,
+        cell_borders: settings.overlay.cell_borders,
+        color_blind_palette: settings.overlay.color_blind_palette,
+        biome_patterns: settings.overlay.biome_patterns,
+        filename: filename.map(|f| f.0.clone()).unwrap_or_default(),
+        ..default()
+    };
+    if let Some(worley_image) = worley_image {
+        state.preview_offset = worley_image.preview_offset;
+        state.preview_scale = worley_image.preview_scale;
+    }
+    if let Some(size) = preview.iter().next() {
+        state.preview_size = match size {
+            DisplayTextureSize::Auto => 0,
+            DisplayTextureSize::Medium => 1,
+            DisplayTextureSize::Big => 2,
+        };
+    }
+    match ron::ser::to_string_pretty(&state, ron::ser::PrettyConfig::default()) {
+        Ok(text) => {
+            if let Err(e) = std::fs::write(&settings.state_file, text) {
+                warn!("failed to save {}: {e}", settings.state_file);
+            }
+        }
+        Err(e) => warn!("failed to serialize the debug state: {e}"),
+    }
+}
@@ -0,0 +1,176 @@
+use std::error::Error;
+
+use bevy::prelude::*;
+
+#[cfg(feature = "serde")]
+use crate::bevy::asset::{WorleyAsset, WorleyAssetHandle};
+#[cfg(feature = "serde")]
+use crate::worley::WORLEY_EXTENSION;
+use crate::{
+    biome_picker::{BiomePicker, BiomeVariants},
+    worley::Worley,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+///! where the inspector's "save"/"load" buttons put a Worley, see DebugPlugin::persistence.
+///! implement it to save into your own save system instead of `assets/*.worley.ron`
+pub trait WorleyPersistence<BiomeT, Picker>: Send + Sync
+where
+    BiomeT: BiomeVariants,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    ///! Some: saving and loading are unavailable, the buttons are disabled with this reason
+    fn unavailable(&self) -> Option<&str> {
+        None
+    }
+
+    ///! worley as text and the file extension for it, written next to the png of a snapshot
+    ///! (DebugPluginSettings::snapshot_dir). None: snapshots are png only
+    fn to_text(&self, _worley: &Worley<BiomeT, Picker>) -> Option<(String, &'static str)> {
+        None
+    }
+
+    ///! store worley under name (the inspector's file name field)
+    fn save(
+        &self,
+        name: &str,
+        worley: &Worley<BiomeT, Picker>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    ///! the worley stored under name. Ok(None): the load completes later on its own
+    ///! (e.g. through the AssetServer)
+    fn load(
+        &self,
+        world: &mut World,
+        name: &str,
+    ) -> Result<Option<Worley<BiomeT, Picker>>, Box<dyn Error + Send + Sync>>;
+}
+
+///! the DebugPlugin::persistence the inspector uses
+#[derive(Resource)]
+pub struct DebugPersistence<BiomeT, Picker>(
+    pub std::sync::Arc<dyn WorleyPersistence<BiomeT, Picker>>,
+)
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static;
+
+///! no saving or loading
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoPersistence;
+
+impl<BiomeT, Picker> WorleyPersistence<BiomeT, Picker> for NoPersistence
+where
+    BiomeT: BiomeVariants,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    fn unavailable(&self) -> Option<&str> {
+        Some("saving and loading requires feature=\"serde\" (or a WorleyPersistence)")
+    }
+
+    fn save(
+        &self,
+        _name: &str,
+        _worley: &Worley<BiomeT, Picker>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Err("no WorleyPersistence".into())
+    }
+
+    fn load(
+        &self,
+        _world: &mut World,
+        _name: &str,
+    ) -> Result<Option<Worley<BiomeT, Picker>>, Box<dyn Error + Send + Sync>> {
+        Err("no WorleyPersistence".into())
+    }
+}
+
+///! `<directory>/<name>.worley.ron` files, the format WorleyAssetPlugin loads.
+///! with WorleyAssetPlugin added, loading goes through the AssetServer (so the file hot reloads),
+///! which only reads below the asset folder: directory has to be `assets` or inside it then
+#[cfg(feature = "serde")]
+#[derive(Clone, Debug)]
+pub struct RonPersistence {
+    pub directory: String,
+}
+
+#[cfg(feature = "serde")]
+impl Default for RonPersistence {
+    fn default() -> Self {
+        Self {
+            directory: "assets".to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl RonPersistence {
+    fn path(&self, name: &str) -> String {
+        format!("{}/{}.{}", self.directory, name, WORLEY_EXTENSION)
+    }
+
+    // path() relative to the asset folder, None if directory is outside of it
+    fn asset_path(&self, name: &str) -> Option<String> {
+        let relative = std::path::Path::new(&self.directory)
+            .strip_prefix(ASSET_FOLDER)
+            .ok()?;
+        Some(
+            relative
+                .join(format!("{}.{}", name, WORLEY_EXTENSION))
+                .to_string_lossy()
+                .replace('\\', "/"),
+        )
+    }
+}
+
+// bevy's default AssetPlugin::file_path
+#[cfg(feature = "serde")]
+const ASSET_FOLDER: &str = "assets";
+
+#[cfg(feature = "serde")]
+impl<BiomeT, Picker> WorleyPersistence<BiomeT, Picker> for RonPersistence
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static + Serialize + for<'de> Deserialize<'de>,
+    Picker: BiomePicker<BiomeT>
+        + Default
+        + Send
+        + Sync
+        + 'static
+        + Serialize
+        + for<'de> Deserialize<'de>,
+{
+    fn to_text(&self, worley: &Worley<BiomeT, Picker>) -> Option<(String, &'static str)> {
+        Some((worley.to_ron_string().ok()?, WORLEY_EXTENSION))
+    }
+
+    fn save(
+        &self,
+        name: &str,
+        worley: &Worley<BiomeT, Picker>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        worley.save_to_path(self.path(name))?;
+        Ok(())
+    }
+
+    fn load(
+        &self,
+        world: &mut World,
+        name: &str,
+    ) -> Result<Option<Worley<BiomeT, Picker>>, Box<dyn Error + Send + Sync>> {
+        if world.contains_resource::<Assets<WorleyAsset<BiomeT, Picker>>>() {
+            // WorleyAssetPlugin is added: load through the AssetServer, so the file hot reloads
+            let Some(asset_path) = self.asset_path(name) else {
+                return Err(format!(
+                    "{:?} is outside the asset folder {:?}, which WorleyAssetPlugin loads from",
+                    self.directory, ASSET_FOLDER
+                )
+                .into());
+            };
+            let handle = world.resource::<AssetServer>().load(asset_path);
+            world.insert_resource(WorleyAssetHandle(handle));
+            return Ok(None);
+        }
+        Ok(Some(Worley::load_from_path(self.path(name))?))
+    }
+}
@@ -0,0 +1,169 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+use crate::{
+    bevy::{
+        sets::WorleySet,
+        traits::{DebugColor, GetWorley, debug_color},
+    },
+    biome_picker::{BiomePicker, BiomeVariants},
+    utils::cell_point,
+};
+
+///! a lightweight debug view drawn with gizmos in the 3d scene, no egui involved.
+///! draws the dominant biome per sample as a colored square, cell borders and cell points,
+///! around the WorleyGizmoAnchor entity (or the origin)
+pub struct WorleyGizmosPlugin<WorleyResT, BiomeT, Picker> {
+    pub settings: WorleyGizmoSettings,
+    pub _phantom: PhantomData<fn() -> (WorleyResT, BiomeT, Picker)>,
+}
+
+impl<WorleyResT, BiomeT, Picker> Default for WorleyGizmosPlugin<WorleyResT, BiomeT, Picker> {
+    fn default() -> Self {
+        Self {
+            settings: WorleyGizmoSettings::default(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[derive(Resource, Clone)]
+pub struct WorleyGizmoSettings {
+    pub enabled: bool,
+    ///! key toggling enabled, None: no key binding
+    pub toggle_key: Option<KeyCode>,
+    ///! world units drawn around the anchor, in each direction
+    pub half_extent: f32,
+    ///! world units between samples
+    pub spacing: f32,
+    ///! y the gizmos are drawn at
+    pub height: f32,
+    ///! a square of the dominant biome's DebugColor per sample
+    pub dominant_color: bool,
+    ///! mark samples where F2 - F1 is small, the approximate cell borders
+    pub cell_borders: bool,
+    ///! a sphere per cell feature point. drawn at point * zoom, which is in warped space:
+    ///! exact without warp, shifted by the warp otherwise
+    pub cell_points: bool,
+}
+
+impl Default for WorleyGizmoSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            toggle_key: Some(KeyCode::F11),
+            half_extent: 64.0,
+            spacing: 2.0,
+            height: 0.0,
+            dominant_color: true,
+            cell_borders: true,
+            cell_points: true,
+        }
+    }
+}
+
+///! the gizmos are drawn around this entity's translation (the first one found)
+#[derive(Component, Default)]
+pub struct WorleyGizmoAnchor;
+
+impl<WorleyResT, BiomeT, Picker> Plugin for WorleyGizmosPlugin<WorleyResT, BiomeT, Picker>
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + DebugColor<BiomeT> + Default + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.settings.clone());
+        app.add_systems(
+            Update,
+            (
+                toggle_gizmos,
+                draw_worley_gizmos::<WorleyResT, BiomeT, Picker>,
+            )
+                .chain()
+                .in_set(WorleySet::Gizmos),
+        );
+    }
+}
+
+fn toggle_gizmos(keyboard: Res<ButtonInput<KeyCode>>, mut settings: ResMut<WorleyGizmoSettings>) {
+    if let Some(key) = settings.toggle_key
+        && keyboard.just_pressed(key)
+    {
+        settings.enabled = !settings.enabled;
+    }
+}
+
+fn draw_worley_gizmos<WorleyResT, BiomeT, Picker>(
+    mut gizmos: Gizmos,
+    settings: Res<WorleyGizmoSettings>,
+    map_settings: Res<WorleyResT>,
+    anchor: Query<&GlobalTransform, With<WorleyGizmoAnchor>>,
+) where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + DebugColor<BiomeT> + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    if !settings.enabled {
+        return;
+    }
+    let worley = map_settings.get_worley();
+    let center = anchor
+        .iter()
+        .next()
+        .map_or(Vec2::ZERO, |t| t.translation().xz());
+    let spacing = settings.spacing.max(0.01);
+    let steps = (settings.half_extent / spacing).ceil() as i32;
+    // snap to the sample grid, so samples don't swim while the anchor moves
+    let snapped = (center / spacing).round() * spacing;
+    // a sample, in cell space
+    let sample_cells = spacing as f64 / worley.zoom;
+    let flat = Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2);
+
+    for ix in -steps..=steps {
+        for iz in -steps..=steps {
+            let pos = snapped + Vec2::new(ix as f32, iz as f32) * spacing;
+            let (x, z) = (pos.x as f64, pos.y as f64);
+            let world = Vec3::new(pos.x, settings.height, pos.y);
+            if settings.dominant_color
+                && let Some(biome) = worley.dominant(x, z)
+            {
+                gizmos.rect(
+                    Isometry3d::new(world, flat),
+                    Vec2::splat(spacing * 0.9),
+                    Color::Srgba(debug_color(worley, &biome)),
+                );
+            }
+            if settings.cell_borders {
+                let cell = worley.cell_sample(x, z);
+                if cell.f2 - cell.f1 < sample_cells {
+                    gizmos.cross(Isometry3d::new(world, flat), spacing * 0.4, Color::BLACK);
+                }
+            }
+        }
+    }
+
+    if settings.cell_points {
+        let zoom = worley.zoom;
+        let min = (snapped - Vec2::splat(settings.half_extent)).as_dvec2() / zoom;
+        let max = (snapped + Vec2::splat(settings.half_extent)).as_dvec2() / zoom;
+        for cx in min.x.floor() as i32..=max.x.floor() as i32 {
+            for cz in min.y.floor() as i32..=max.y.floor() as i32 {
+                let Some(biome) = worley.biome_picker.try_pick_biome(worley.seed, cx, cz) else {
+                    continue;
+                };
+                let (px, pz) = cell_point(worley.seed, cx, cz);
+                gizmos.sphere(
+                    Vec3::new(
+                        (px * zoom) as f32,
+                        settings.height + spacing,
+                        (pz * zoom) as f32,
+                    ),
+                    spacing * 0.5,
+                    Color::Srgba(debug_color(worley, &biome)),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bevy::{
+    asset::{RenderAssetUsages, embedded_asset},
+    image::ImageSampler,
+    prelude::*,
+    render::{
+        Render, RenderApp, RenderStartup, RenderSystems,
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        render_asset::RenderAssets,
+        render_graph::{self, RenderGraph, RenderLabel},
+        render_resource::{
+            binding_types::{storage_buffer_read_only, texture_storage_2d, uniform_buffer},
+            *,
+        },
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        texture::GpuImage,
+    },
+};
+
+use crate::{
+    bevy::traits::{DebugColor, debug_color},
+    biome_picker::{BiomePicker, BiomeVariants, variant_index},
+    distance_fn::DistanceFn,
+    utils::{derive_seed, seed_domain},
+    worley::Worley,
+};
+
+///! gpu evaluation of a Worley into a texture.
+///! the shader does the per pixel work: cell search, distances, k nearest, sharpness and blending.
+///! the cpu still picks the biome of every visible cell and warps a coarse grid (every grid_step pixels),
+///! so any BiomePicker, warp and custom_warp work unchanged.
+///! differences to Worley::get: the warp is bilinear between grid points (grid_step 1 is exact),
+///! math is f32 and colors blend in linear space. feature points match exactly (the fxhash is ported)
+const SHADER_PATH: &str = "embedded://worley_biomes/bevy/gpu_worley.wgsl";
+
+///! cell table marker for cells without a biome
+pub const EMPTY_CELL: u32 = u32::MAX;
+///! refuse to build jobs whose cell table gets larger than this (zoomed far out)
+pub const MAX_GPU_CELLS: usize = 1 << 22;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+pub struct GpuWorleyPlugin;
+
+impl Plugin for GpuWorleyPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "gpu_worley.wgsl");
+        app.add_plugins(ExtractResourcePlugin::<GpuWorleyJob>::default());
+        // no renderer (headless), nothing to run the shader on
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<GpuWorleyBindGroup>()
+            .add_systems(RenderStartup, init_gpu_worley_pipeline)
+            .add_systems(
+                Render,
+                prepare_gpu_worley.in_set(RenderSystems::PrepareBindGroups),
+            );
+        let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
+        render_graph.add_node(GpuWorleyLabel, GpuWorleyNode);
+        render_graph.add_node_edge(GpuWorleyLabel, bevy::render::graph::CameraDriverLabel);
+    }
+}
+
+///! uniforms of gpu_worley.wgsl, field order has to match the shader's Params
+#[derive(ShaderType, Clone, Copy, Default, Debug)]
+pub struct GpuWorleyParams {
+    pub size: u32,
+    pub grid_step: u32,
+    pub grid_w: u32,
+    pub cells_w: u32,
+    pub cells_h: u32,
+    pub k: u32,
+    pub distance_fn: u32,
+    pub variant_count: u32,
+    pub cell_base: IVec2,
+    pub seed_x: UVec2,
+    pub seed_z: UVec2,
+    pub sharpness: f32,
+    ///! negative: no kill threshold
+    pub kill_threshold: f32,
+}
+
+///! a render request: insert it as a resource and the texture is written on the next frames.
+///! inserting a new job renders again, the image has to come from gpu_target_image
+#[derive(Resource, Clone, ExtractResource)]
+pub struct GpuWorleyJob {
+    pub image: Handle<Image>,
+    pub params: GpuWorleyParams,
+    ///! warped cell space positions relative to params.cell_base, grid_w x grid_w
+    pub warped: Vec<Vec2>,
+    ///! index into palette per cell, cells_w x cells_h, EMPTY_CELL without biome
+    pub cells: Vec<u32>,
+    ///! linear rgba of every biome in cells
+    pub palette: Vec<Vec4>,
+    generation: u64,
+}
+
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+impl GpuWorleyJob {
+    ///! size x size pixels, pixel (row gx, column gz) samples the world at offset + (gx, gz) * scale
+    ///! like preview::render_preview. warp is sampled every grid_step pixels.
+    ///! None if the visible area spans more than MAX_GPU_CELLS cells, or if a sparse picker leaves
+    ///! fewer than k non-empty cells in some 3x3 (only the cpu path widens its search past that)
+    pub fn new<BiomeT, Picker>(
+        worley: &Worley<BiomeT, Picker>,
+        image: Handle<Image>,
+        offset: (f64, f64),
+        scale: f64,
+        size: u32,
+        grid_step: u32,
+    ) -> Option<Self>
+    where
+        BiomeT: BiomeVariants + DebugColor<BiomeT> + Default + 'static,
+        Picker: BiomePicker<BiomeT> + Default,
+    {
+        let grid_step = grid_step.max(1);
+        let grid_w = size.div_ceil(grid_step) + 1;
+
+        let mut warped = Vec::with_capacity((grid_w * grid_w) as usize);
+        let mut min = (f64::MAX, f64::MAX);
+        let mut max = (f64::MIN, f64::MIN);
+        for ix in 0..grid_w {
+            for iz in 0..grid_w {
+                let x = offset.0 + (ix * grid_step) as f64 * scale;
+                let z = offset.1 + (iz * grid_step) as f64 * scale;
+                let p = worley.warped_cell_position(x, z);
+                min = (min.0.min(p.0), min.1.min(p.1));
+                max = (max.0.max(p.0), max.1.max(p.1));
+                warped.push(p);
+            }
+        }
+
+        // every cell a pixel can see, + the neighbours searched around it
+        let cell_base = IVec2::new(min.0.floor() as i32 - 1, min.1.floor() as i32 - 1);
+        let cells_w = (max.0.floor() as i64 - cell_base.x as i64 + 2).max(1) as usize;
+        let cells_h = (max.1.floor() as i64 - cell_base.y as i64 + 2).max(1) as usize;
+        if cells_w.saturating_mul(cells_h) > MAX_GPU_CELLS {
+            return None;
+        }
+
+        let mut palette_keys = Vec::new();
+        let mut palette = Vec::new();
+        let mut cells = Vec::with_capacity(cells_w * cells_h);
+        for cx in 0..cells_w as i32 {
+            for cz in 0..cells_h as i32 {
+                let (cx, cz) = (cell_base.x + cx, cell_base.y + cz);
+                let Some(biome) = worley.biome_picker.try_pick_biome(worley.seed, cx, cz) else {
+                    cells.push(EMPTY_CELL);
+                    continue;
+                };
+                let key = variant_index(&biome);
+                let idx = match palette_keys.iter().position(|k| *k == key) {
+                    Some(idx) => idx,
+                    None => {
+                        let c = LinearRgba::from(debug_color(worley, &biome));
+                        palette_keys.push(key);
+                        palette.push(Vec4::new(c.red, c.green, c.blue, c.alpha));
+                        palette.len() - 1
+                    }
+                };
+                cells.push(idx as u32);
+            }
+        }
+        // the shader only searches the 3x3 around a pixel's cell, the outer cells are only neighbours
+        let k = worley.k.min(9);
+        let filled = |i: usize, j: usize| cells[i * cells_h + j] != EMPTY_CELL;
+        for i in 1..cells_w.saturating_sub(1) {
+            for j in 1..cells_h.saturating_sub(1) {
+                let around = (i - 1..=i + 1)
+                    .flat_map(|i| (j - 1..=j + 1).map(move |j| (i, j)))
+                    .filter(|(i, j)| filled(*i, *j))
+                    .count();
+                if around < k {
+                    return None;
+                }
+            }
+        }
+        if palette.is_empty() {
+            // storage buffers can't be empty
+            palette.push(Vec4::ZERO);
+        }
+
+        let base = (cell_base.x as f64, cell_base.y as f64);
+        let warped = warped
+            .into_iter()
+            .map(|(x, z)| Vec2::new((x - base.0) as f32, (z - base.1) as f32))
+            .collect();
+        let seed_x = derive_seed(worley.seed, seed_domain::POINT_X);
+        let seed_z = derive_seed(worley.seed, seed_domain::POINT_Z);
+
+        Some(Self {
+            image,
+            params: GpuWorleyParams {
+                size,
+                grid_step,
+                grid_w,
+                cells_w: cells_w as u32,
+                cells_h: cells_h as u32,
+                k: worley.k.min(9) as u32,
+                distance_fn: distance_fn_index(worley.distance_fn_config),
+                variant_count: palette_keys.len() as u32,
+                cell_base,
+                seed_x: UVec2::new(seed_x as u32, (seed_x >> 32) as u32),
+                seed_z: UVec2::new(seed_z as u32, (seed_z >> 32) as u32),
+                sharpness: worley.sharpness as f32,
+                kill_threshold: worley.kill_percent_threshold.map_or(-1.0, |t| t as f32),
+            },
+            warped,
+            cells,
+            palette,
+            generation: NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
+        })
+    }
+}
+
+///! DistanceFn as the shader's switch index
+fn distance_fn_index(distance_fn: DistanceFn) -> u32 {
+    match distance_fn {
+        DistanceFn::Euclidean => 0,
+        DistanceFn::EuclideanSquared => 1,
+        DistanceFn::Manhattan => 2,
+        DistanceFn::Chebyshev => 3,
+        DistanceFn::Hybrid => 4,
+    }
+}
+
+///! a size x size image the shader can write to (storage textures can't be srgb, the shader writes linear colors)
+pub fn gpu_target_image(size: u32) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.texture_descriptor.usage =
+        TextureUsages::COPY_DST | TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING;
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+#[derive(Resource)]
+struct GpuWorleyPipeline {
+    layout: BindGroupLayout,
+    pipeline: CachedComputePipelineId,
+}
+
+fn init_gpu_worley_pipeline(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    asset_server: Res<AssetServer>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    let layout = render_device.create_bind_group_layout(
+        "worley_gpu_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::COMPUTE,
+            (
+                uniform_buffer::<GpuWorleyParams>(false),
+                storage_buffer_read_only::<Vec<Vec2>>(false),
+                storage_buffer_read_only::<Vec<u32>>(false),
+                storage_buffer_read_only::<Vec<Vec4>>(false),
+                texture_storage_2d(TextureFormat::Rgba8Unorm, StorageTextureAccess::WriteOnly),
+            ),
+        ),
+    );
+    let pipeline = pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+        label: Some("worley_gpu_pipeline".into()),
+        layout: vec![layout.clone()],
+        shader: asset_server.load(SHADER_PATH),
+        entry_point: Some("main".into()),
+        ..default()
+    });
+    commands.insert_resource(GpuWorleyPipeline { layout, pipeline });
+}
+
+///! Some only on the frame a job gets dispatched
+#[derive(Resource, Default)]
+struct GpuWorleyBindGroup {
+    bind_group: Option<BindGroup>,
+    workgroups: u32,
+    generation: u64,
+}
+
+fn prepare_gpu_worley(
+    mut prepared: ResMut<GpuWorleyBindGroup>,
+    job: Option<Res<GpuWorleyJob>>,
+    pipeline: Res<GpuWorleyPipeline>,
+    pipeline_cache: Res<PipelineCache>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    prepared.bind_group = None;
+    let Some(job) = job else {
+        return;
+    };
+    if job.generation == prepared.generation {
+        return;
+    }
+    // shader still compiling or image not uploaded yet, try again next frame
+    if pipeline_cache
+        .get_compute_pipeline(pipeline.pipeline)
+        .is_none()
+    {
+        return;
+    }
+    let Some(image) = gpu_images.get(&job.image) else {
+        return;
+    };
+
+    let mut params = UniformBuffer::from(job.params);
+    params.write_buffer(&render_device, &render_queue);
+    let mut warped = StorageBuffer::from(job.warped.clone());
+    warped.write_buffer(&render_device, &render_queue);
+    let mut cells = StorageBuffer::from(job.cells.clone());
+    cells.write_buffer(&render_device, &render_queue);
+    let mut palette = StorageBuffer::from(job.palette.clone());
+    palette.write_buffer(&render_device, &render_queue);
+    let (Some(params), Some(warped), Some(cells), Some(palette)) = (
+        params.binding(),
+        warped.binding(),
+        cells.binding(),
+        palette.binding(),
+    ) else {
+        return;
+    };
+
+    prepared.bind_group = Some(render_device.create_bind_group(
+        "worley_gpu_bind_group",
+        &pipeline.layout,
+        &BindGroupEntries::sequential((params, warped, cells, palette, &image.texture_view)),
+    ));
+    prepared.workgroups = job.params.size.div_ceil(WORKGROUP_SIZE);
+    prepared.generation = job.generation;
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct GpuWorleyLabel;
+
+struct GpuWorleyNode;
+
+impl render_graph::Node for GpuWorleyNode {
+    fn run(
+        &self,
+        _graph: &mut render_graph::RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), render_graph::NodeRunError> {
+        let prepared = world.resource::<GpuWorleyBindGroup>();
+        let Some(bind_group) = &prepared.bind_group else {
+            return Ok(());
+        };
+        let pipeline = world.resource::<GpuWorleyPipeline>();
+        let Some(compute_pipeline) = world
+            .resource::<PipelineCache>()
+            .get_compute_pipeline(pipeline.pipeline)
+        else {
+            return Ok(());
+        };
+        let mut pass = render_context
+            .command_encoder()
+            .begin_compute_pass(&ComputePassDescriptor::default());
+        pass.set_bind_group(0, bind_group, &[]);
+        pass.set_pipeline(compute_pipeline);
+        pass.dispatch_workgroups(prepared.workgroups, prepared.workgroups, 1);
+        Ok(())
+    }
+}
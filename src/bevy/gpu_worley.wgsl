@@ -0,0 +1,185 @@
+// gpu side of bevy::gpu: cell search, distance weighting and color blending per pixel.
+// biome picks per cell and the warped positions come from the cpu, see GpuWorleyJob
+
+struct Params {
+    size: u32,
+    grid_step: u32,
+    grid_w: u32,
+    cells_w: u32,
+    cells_h: u32,
+    k: u32,
+    distance_fn: u32,
+    variant_count: u32,
+    cell_base: vec2<i32>,
+    seed_x: vec2<u32>,
+    seed_z: vec2<u32>,
+    sharpness: f32,
+    kill_threshold: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// warped cell space positions relative to cell_base, on a grid every grid_step pixels
+@group(0) @binding(1) var<storage, read> warped: array<vec2<f32>>;
+// variant index per cell, EMPTY_CELL for cells without a biome
+@group(0) @binding(2) var<storage, read> cells: array<u32>;
+// linear rgba per variant
+@group(0) @binding(3) var<storage, read> palette: array<vec4<f32>>;
+@group(0) @binding(4) var output: texture_storage_2d<rgba8unorm, write>;
+
+const EMPTY_CELL: u32 = 0xffffffffu;
+
+// 64 bit integers as (lo, hi), to reproduce the cpu's fxhash exactly
+
+fn mul32(a: u32, b: u32) -> vec2<u32> {
+    let a_lo = a & 0xffffu;
+    let a_hi = a >> 16u;
+    let b_lo = b & 0xffffu;
+    let b_hi = b >> 16u;
+    let ll = a_lo * b_lo;
+    let lh = a_lo * b_hi;
+    let hl = a_hi * b_lo;
+    let hh = a_hi * b_hi;
+    let mid = (ll >> 16u) + (lh & 0xffffu) + (hl & 0xffffu);
+    let lo = (ll & 0xffffu) | (mid << 16u);
+    let hi = hh + (lh >> 16u) + (hl >> 16u) + (mid >> 16u);
+    return vec2<u32>(lo, hi);
+}
+
+fn mul64(a: vec2<u32>, b: vec2<u32>) -> vec2<u32> {
+    let p = mul32(a.x, b.x);
+    return vec2<u32>(p.x, p.y + a.x * b.y + a.y * b.x);
+}
+
+fn rotl5(a: vec2<u32>) -> vec2<u32> {
+    return vec2<u32>((a.x << 5u) | (a.y >> 27u), (a.y << 5u) | (a.x >> 27u));
+}
+
+const FX_K: vec2<u32> = vec2<u32>(0x27220a95u, 0x517cc1b7u);
+
+// utils::hash_u64: fxhash of (seed: u64, x: i32, z: i32)
+fn hash_u64(seed: vec2<u32>, x: i32, z: i32) -> vec2<u32> {
+    var h = mul64(seed, FX_K);
+    h = mul64(rotl5(h) ^ vec2<u32>(bitcast<u32>(x), 0u), FX_K);
+    h = mul64(rotl5(h) ^ vec2<u32>(bitcast<u32>(z), 0u), FX_K);
+    return h;
+}
+
+// DistanceFn, same order as distance_fn_index
+fn distance(d: vec2<f32>) -> f32 {
+    switch params.distance_fn {
+        case 0u: {
+            return length(d);
+        }
+        case 1u: {
+            return dot(d, d);
+        }
+        case 2u: {
+            return abs(d.x) + abs(d.y);
+        }
+        case 3u: {
+            return max(abs(d.x), abs(d.y));
+        }
+        default: {
+            return (length(d) + abs(d.x) + abs(d.y)) / 2.0;
+        }
+    }
+}
+
+fn warped_at(gx: u32, gz: u32) -> vec2<f32> {
+    let fx = f32(gx) / f32(params.grid_step);
+    let fz = f32(gz) / f32(params.grid_step);
+    let ix = min(u32(fx), params.grid_w - 2u);
+    let iz = min(u32(fz), params.grid_w - 2u);
+    let tx = fx - f32(ix);
+    let tz = fz - f32(iz);
+    let p00 = warped[ix * params.grid_w + iz];
+    let p01 = warped[ix * params.grid_w + iz + 1u];
+    let p10 = warped[(ix + 1u) * params.grid_w + iz];
+    let p11 = warped[(ix + 1u) * params.grid_w + iz + 1u];
+    return mix(mix(p00, p01, tz), mix(p10, p11, tz), tx);
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.size || id.y >= params.size {
+        return;
+    }
+    // image rows are x, columns are z (like the cpu preview)
+    let gx = id.y;
+    let gz = id.x;
+    let p = warped_at(gx, gz);
+    let cell = vec2<i32>(floor(p));
+
+    var dists: array<f32, 9>;
+    var biomes: array<u32, 9>;
+    var n = 0u;
+    for (var dx = -1; dx <= 1; dx++) {
+        for (var dz = -1; dz <= 1; dz++) {
+            let c = cell + vec2<i32>(dx, dz);
+            if c.x < 0 || c.y < 0 || u32(c.x) >= params.cells_w || u32(c.y) >= params.cells_h {
+                continue;
+            }
+            let biome = cells[u32(c.x) * params.cells_h + u32(c.y)];
+            if biome == EMPTY_CELL {
+                continue;
+            }
+            let world_cell = c + params.cell_base;
+            let h1 = hash_u64(params.seed_x, world_cell.x, world_cell.y);
+            let h2 = hash_u64(params.seed_z, world_cell.x, world_cell.y);
+            let point = vec2<f32>(
+                f32(c.x) + f32(h1.x & 0xffffu) / 65535.0,
+                f32(c.y) + f32(h2.x & 0xffffu) / 65535.0,
+            );
+            dists[n] = distance(p - point);
+            biomes[n] = biome;
+            n++;
+        }
+    }
+
+    // select the k nearest
+    let k = min(params.k, n);
+    for (var i = 0u; i < k; i++) {
+        var best = i;
+        for (var j = i + 1u; j < n; j++) {
+            if dists[j] < dists[best] {
+                best = j;
+            }
+        }
+        let d = dists[i];
+        dists[i] = dists[best];
+        dists[best] = d;
+        let b = biomes[i];
+        biomes[i] = biomes[best];
+        biomes[best] = b;
+    }
+
+    // 1 / d^sharpness, relative to the nearest so f32 doesn't overflow
+    var weights: array<f32, 9>;
+    var sum = 0.0;
+    for (var i = 0u; i < k; i++) {
+        var w = 0.0;
+        if dists[0] < 1e-9 {
+            w = select(0.0, 1.0, i == 0u);
+        } else {
+            w = pow(dists[0] / dists[i], params.sharpness);
+        }
+        weights[i] = w;
+        sum += w;
+    }
+    var kept = 0.0;
+    for (var i = 0u; i < k; i++) {
+        weights[i] /= sum;
+        if params.kill_threshold >= 0.0 && weights[i] <= params.kill_threshold {
+            weights[i] = 0.0;
+        }
+        kept += weights[i];
+    }
+
+    var color = vec3<f32>(0.0);
+    for (var i = 0u; i < k; i++) {
+        if biomes[i] < params.variant_count {
+            color += palette[biomes[i]].rgb * weights[i] / max(kept, 1e-9);
+        }
+    }
+    textureStore(output, vec2<i32>(i32(gz), i32(gx)), vec4<f32>(color, 1.0));
+}
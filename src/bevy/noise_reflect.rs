@@ -0,0 +1,28 @@
+use bevy::reflect::reflect_remote;
+use bracket_fast_noise::prelude::{FractalType, NoiseType};
+
+// bracket_fast_noise types are foreign, so they are reflected through remote wrappers.
+// use them on your own fields with `#[reflect(remote = NoiseTypeReflect)]`
+
+///! remote Reflect for bracket_fast_noise NoiseType
+#[reflect_remote(NoiseType)]
+pub enum NoiseTypeReflect {
+    Value,
+    ValueFractal,
+    Perlin,
+    PerlinFractal,
+    Simplex,
+    SimplexFractal,
+    Cellular,
+    WhiteNoise,
+    Cubic,
+    CubicFractal,
+}
+
+///! remote Reflect for bracket_fast_noise FractalType
+#[reflect_remote(FractalType)]
+pub enum FractalTypeReflect {
+    FBM,
+    Billow,
+    RigidMulti,
+}
@@ -0,0 +1,235 @@
+// the debug preview's cpu renderer, usable without egui or a window,
+// e.g. to write pngs from a headless app or a test for golden image comparisons
+
+use bevy::prelude::*;
+
+use crate::{
+    bevy::traits::{DebugColor, color_blind_color, palette_color},
+    biome_picker::{BiomePicker, BiomeVariants, variant_index},
+    worley::Worley,
+};
+
+///! debug drawing on top of (or instead of) the blended biome colors
+#[derive(Clone, Copy, Default)]
+pub struct PreviewOverlay {
+    ///! mark the (warped) feature point of every cell
+    pub cell_points: bool,
+    ///! darken pixels where F2 - F1 is small, the approximate cell borders
+    pub cell_borders: bool,
+    ///! white lines on the integer cell lattice before warp (every zoom world units),
+    ///! to tell artifacts of the cell size apart from warp artifacts
+    pub cell_grid: bool,
+    ///! tint pixels magenta where kill_percent_threshold removed one or more biomes
+    pub killed_biomes: bool,
+    ///! color biomes from traits::COLOR_BLIND_PALETTE instead of DebugColor/debug_palette
+    pub color_blind_palette: bool,
+    ///! hatch each pixel with a pattern of its dominant biome (plain, rows, columns, diagonals,
+    ///! grid, dots, then the same with wider spacing), to tell biomes apart without color
+    pub biome_patterns: bool,
+}
+
+impl PreviewOverlay {
+    ///! the preview color of a biome, variant_idx is its index in BiomeT::variants()
+    pub fn biome_color<BiomeT: DebugColor<BiomeT>>(
+        &self,
+        palette: &[Option<[f32; 3]>],
+        variant_idx: Option<usize>,
+        biome: &BiomeT,
+    ) -> Srgba {
+        match variant_idx {
+            Some(idx) if self.color_blind_palette => color_blind_color(idx),
+            _ => palette_color(palette, variant_idx, biome),
+        }
+    }
+}
+
+// whether pixel (gx, gz) is on the hatching of variant index idx
+fn biome_pattern(idx: usize, gx: u32, gz: u32) -> bool {
+    let period = 5 + (idx / 7) as i64 * 3;
+    let (x, z) = (gx as i64, gz as i64);
+    let on = |v: i64| v.rem_euclid(period) == 0;
+    match idx % 7 {
+        0 => false,
+        1 => on(x),
+        2 => on(z),
+        3 => on(x + z),
+        4 => on(x - z),
+        5 => on(x) || on(z),
+        _ => on(x) && on(z),
+    }
+}
+
+///! output of render_preview
+pub struct PreviewRender {
+    pub size: u32,
+    ///! size x size rgba8 pixels
+    pub rgba: Vec<u8>,
+    ///! fraction of the image per BiomeT::variants() index (weights summed over all pixels)
+    pub coverage: Vec<f64>,
+    pub stats: RenderStats,
+}
+
+///! how expensive a render_preview was
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderStats {
+    pub elapsed: std::time::Duration,
+    ///! Worley::get calls (+ Worley::cell_sample calls for overlays)
+    pub samples: u64,
+}
+
+impl RenderStats {
+    pub fn samples_per_second(&self) -> f64 {
+        self.samples as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+}
+
+///! render a size x size image of blended DebugColors.
+///! pixel (row gx, column gz) samples the world at offset + (gx, gz) * scale
+pub fn render_preview<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    offset: (f64, f64),
+    scale: f64,
+    size: u32,
+    overlay: PreviewOverlay,
+    height_fn: Option<fn(&BiomeT) -> f32>,
+) -> PreviewRender
+where
+    BiomeT: BiomeVariants + 'static + DebugColor<BiomeT> + std::default::Default,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let start = bevy::platform::time::Instant::now();
+    let mut samples = 0;
+    let variants = BiomeT::variants();
+    let mut coverage = vec![0.0; variants.len()];
+    let mut img_data = Vec::with_capacity((size * size * 4) as usize);
+    // one pixel, in cell space
+    let pixel = scale / worley.zoom;
+    // heights are mapped to gray by the range of all variants
+    let height = height_fn.map(|height_fn| {
+        let (min, max) = variants
+            .iter()
+            .map(|v| height_fn(v) as f64)
+            .fold((f64::MAX, f64::MIN), |(lo, hi), h| (lo.min(h), hi.max(h)));
+        (height_fn, min, (max - min).max(1e-9))
+    });
+    for gx in 0..size {
+        for gz in 0..size {
+            let (x, z) = (gx as f64 * scale + offset.0, gz as f64 * scale + offset.1);
+            let weights = worley.get(x, z);
+            samples += 1;
+
+            // blend colors
+            let mut r = 0.0;
+            let mut g = 0.0;
+            let mut b = 0.0;
+            let mut dominant = (0.0, None);
+            for (w, biome) in &weights {
+                let idx = variant_index(biome);
+                let c = overlay.biome_color(&worley.debug_palette, idx, biome);
+                r += c.red as f64 * w;
+                g += c.green as f64 * w;
+                b += c.blue as f64 * w;
+                if let Some(idx) = idx {
+                    coverage[idx] += w;
+                }
+                if *w > dominant.0 {
+                    dominant = (*w, idx);
+                }
+            }
+
+            if let Some((height_fn, min, range)) = height {
+                let h: f64 = weights.iter().map(|(w, b)| w * height_fn(b) as f64).sum();
+                let v = ((h - min) / range).clamp(0.0, 1.0);
+                (r, g, b) = (v, v, v);
+            }
+
+            if overlay.biome_patterns
+                && let Some(idx) = dominant.1
+                && biome_pattern(idx, gx, gz)
+            {
+                // dark lines on light colors, light lines on dark ones
+                let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+                let target = if luminance > 0.5 { 0.0 } else { 1.0 };
+                (r, g, b) = ((r + target) * 0.5, (g + target) * 0.5, (b + target) * 0.5);
+            }
+
+            if overlay.cell_points || overlay.cell_borders {
+                let cell = worley.cell_sample(x, z);
+                samples += 1;
+                if overlay.cell_borders && cell.f2 - cell.f1 < pixel * 1.5 {
+                    (r, g, b) = (r * 0.35, g * 0.35, b * 0.35);
+                }
+                let (dx, dz) = (cell.warped.0 - cell.point.0, cell.warped.1 - cell.point.1);
+                if overlay.cell_points && (dx * dx + dz * dz).sqrt() < pixel * 1.5 {
+                    (r, g, b) = (0.0, 0.0, 0.0);
+                }
+            }
+
+            if overlay.killed_biomes && worley.killed_count(x, z) > 0 {
+                samples += 1;
+                (r, g, b) = (r * 0.4 + 0.6, g * 0.4, b * 0.4 + 0.6);
+            }
+
+            if overlay.cell_grid {
+                // a lattice line passes between this pixel and the previous one
+                let crosses =
+                    |v: f64| (v / worley.zoom).floor() != ((v - scale) / worley.zoom).floor();
+                if crosses(x) || crosses(z) {
+                    (r, g, b) = (1.0, 1.0, 1.0);
+                }
+            }
+
+            let color = Srgba::new(r as f32, g as f32, b as f32, 1.0);
+            img_data.push((color.red * 255.0) as u8);
+            img_data.push((color.green * 255.0) as u8);
+            img_data.push((color.blue * 255.0) as u8);
+            img_data.push(255 as u8);
+        }
+    }
+    let pixel_count = (size * size).max(1) as f64;
+    for c in coverage.iter_mut() {
+        *c /= pixel_count;
+    }
+    PreviewRender {
+        size,
+        rgba: img_data,
+        coverage,
+        stats: RenderStats {
+            elapsed: start.elapsed(),
+            samples,
+        },
+    }
+}
+
+#[cfg(feature = "image")]
+impl PreviewRender {
+    ///! the pixels as an image, row gx and column gz like the debug preview texture
+    pub fn to_image(&self) -> image::RgbaImage {
+        image::RgbaImage::from_raw(self.size, self.size, self.rgba.clone())
+            .expect("size x size rgba8 pixels")
+    }
+}
+
+///! render_preview written to a png, without an App. the same arguments render the same
+///! pixels as the DebugPlugin preview showing that area
+///! ```ignore
+///! let stats = save_preview_png(&worley, (0.0, 0.0), 4.0, 512, PreviewOverlay::default(), None, "out/biomes.png")?;
+///! ```
+#[cfg(feature = "image")]
+pub fn save_preview_png<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    offset: (f64, f64),
+    scale: f64,
+    size: u32,
+    overlay: PreviewOverlay,
+    height_fn: Option<fn(&BiomeT) -> f32>,
+    path: impl AsRef<std::path::Path>,
+) -> image::ImageResult<RenderStats>
+where
+    BiomeT: BiomeVariants + 'static + DebugColor<BiomeT> + std::default::Default,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let render = render_preview(worley, offset, scale, size, overlay, height_fn);
+    render.to_image().save(path)?;
+    Ok(render.stats)
+}
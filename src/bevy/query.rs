@@ -0,0 +1,61 @@
+use std::marker::PhantomData;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use tinyvec::TinyVec;
+
+use crate::{
+    bevy::traits::GetWorley,
+    biome_picker::{BiomePicker, BiomeVariants},
+    worley::Worley,
+};
+
+///! read access to the Worley of WorleyResT, without the GetWorley plumbing.
+///! 2d positions are (x, z) in world units, 3d positions ignore y
+///! ```ignore
+///! fn system(worley: WorleyQuery<MapSettings, Biome, SimpleBiomePicker<Biome>>, q: Query<&Transform>) {
+///!     for transform in q.iter() {
+///!         let biome = worley.dominant_at(transform.translation);
+///!     }
+///! }
+///! ```
+#[derive(SystemParam)]
+pub struct WorleyQuery<'w, WorleyResT, BiomeT, Picker>
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Default + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    res: Res<'w, WorleyResT>,
+    _phantom: PhantomData<fn() -> (BiomeT, Picker)>,
+}
+
+impl<'w, WorleyResT, BiomeT, Picker> WorleyQuery<'w, WorleyResT, BiomeT, Picker>
+where
+    WorleyResT: Resource + GetWorley<BiomeT, Picker>,
+    BiomeT: BiomeVariants + Default + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    pub fn worley(&self) -> &Worley<BiomeT, Picker> {
+        self.res.get_worley()
+    }
+
+    ///! true if the resource changed since this system last ran
+    pub fn is_changed(&self) -> bool {
+        self.res.is_changed()
+    }
+
+    ///! the highest weighted biome at (x, z)
+    pub fn biome_at(&self, pos: Vec2) -> Option<BiomeT> {
+        self.worley().dominant(pos.x as f64, pos.y as f64)
+    }
+
+    ///! the highest weighted biome under a 3d position (e.g. a Transform's translation)
+    pub fn dominant_at(&self, pos: Vec3) -> Option<BiomeT> {
+        self.worley().dominant(pos.x as f64, pos.z as f64)
+    }
+
+    ///! all (weight, biome) at (x, z), see Worley::get
+    pub fn weights_at(&self, pos: Vec2) -> TinyVec<[(f64, BiomeT); 9]> {
+        self.worley().get(pos.x as f64, pos.y as f64)
+    }
+}
@@ -0,0 +1,85 @@
+use std::ops::{Deref, DerefMut};
+
+use bevy::prelude::*;
+
+use crate::{
+    bevy::traits::GetWorley,
+    biome_picker::{BiomePicker, BiomeVariants},
+    worley::Worley,
+};
+
+///! a ready made resource holding a Worley, for when you don't need your own settings struct.
+///! use it as WorleyResT of the plugins, derefs to the Worley
+///! ```ignore
+///! app.insert_resource(WorleyResource(worley))
+///!     .add_plugins(DebugPlugin::<WorleyResource<Biome, SimpleBiomePicker<Biome>>, Biome, SimpleBiomePicker<Biome>>::default());
+///! ```
+#[derive(Resource)]
+pub struct WorleyResource<BiomeT, Picker>(pub Worley<BiomeT, Picker>)
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static;
+
+impl<BiomeT, Picker> Default for WorleyResource<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self(Worley::default())
+    }
+}
+
+impl<BiomeT, Picker> Clone for WorleyResource<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static,
+{
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<BiomeT, Picker> From<Worley<BiomeT, Picker>> for WorleyResource<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    fn from(worley: Worley<BiomeT, Picker>) -> Self {
+        Self(worley)
+    }
+}
+
+impl<BiomeT, Picker> GetWorley<BiomeT, Picker> for WorleyResource<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    fn get_worley(&self) -> &Worley<BiomeT, Picker> {
+        &self.0
+    }
+    fn get_worley_mut(&mut self) -> &mut Worley<BiomeT, Picker> {
+        &mut self.0
+    }
+}
+
+impl<BiomeT, Picker> Deref for WorleyResource<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    type Target = Worley<BiomeT, Picker>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<BiomeT, Picker> DerefMut for WorleyResource<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync + 'static,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
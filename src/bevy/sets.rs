@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+
+///! the systems the crate's plugins add, to order your own systems against:
+///! ```ignore
+///! app.add_systems(Update, spawn_vegetation.after(WorleySet::ChunkGen));
+///! app.add_systems(PostUpdate, tint_preview.after(WorleySet::RebuildPreview));
+///! ```
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WorleySet {
+    ///! PreUpdate: copy loaded or hot reloaded `.worley.ron` assets into the resource
+    ApplyAsset,
+    ///! PreUpdate: empty WorleyChunkCache after a WorleyChanged
+    ClearCache,
+    ///! Update: refresh the chunk snapshot, spawn chunk tasks and insert finished WorleyChunks
+    ChunkGen,
+    ///! Update and the egui passes: the DebugPlugin's inspector, input and debounced edits
+    DebugUi,
+    ///! PostUpdate: rebuild the Worley after reflection edits and regenerate the debug preview
+    RebuildPreview,
+    ///! Update: WorleyGizmosPlugin's toggle key and drawing
+    Gizmos,
+    ///! Last: send WorleyChanged when the config hash differs
+    DetectChanges,
+}
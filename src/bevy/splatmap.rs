@@ -0,0 +1,114 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{
+    biome_picker::{BiomePicker, BiomeVariants, variant_index},
+    worley::Worley,
+};
+
+///! which RGBA channel (0..4) each biome writes its weight to, indexed like BiomeT::variants().
+///! None (or a missing entry): the biome is left out of the splatmap
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SplatChannels(pub Vec<Option<u8>>);
+
+impl SplatChannels {
+    ///! the first four variants to R, G, B and A, the rest left out
+    pub fn first_four<BiomeT: BiomeVariants>() -> Self {
+        Self(
+            (0..BiomeT::variants().len())
+                .map(|i| (i < 4).then_some(i as u8))
+                .collect(),
+        )
+    }
+
+    ///! assign biome to channel (0..4)
+    pub fn with<BiomeT: BiomeVariants + 'static>(mut self, biome: BiomeT, channel: u8) -> Self {
+        assert!(
+            channel < 4,
+            "a splatmap has 4 channels, got channel {}",
+            channel
+        );
+        if let Some(idx) = variant_index(&biome) {
+            if self.0.len() <= idx {
+                self.0.resize(idx + 1, None);
+            }
+            self.0[idx] = Some(channel);
+        }
+        self
+    }
+
+    ///! the channel biome writes to
+    pub fn channel<BiomeT: BiomeVariants + 'static>(&self, biome: &BiomeT) -> Option<u8> {
+        variant_index(biome).and_then(|idx| self.0.get(idx).copied().flatten())
+    }
+}
+
+///! the blend weights over rect (world x, z) packed into an Rgba8Unorm image, for terrain
+///! material shaders. the first four biome variants go to R, G, B and A,
+///! see generate_splatmap_with for choosing the channels
+pub fn generate_splatmap<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    rect: Rect,
+    resolution: UVec2,
+) -> Image
+where
+    BiomeT: BiomeVariants + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    generate_splatmap_with(
+        worley,
+        rect,
+        resolution,
+        &SplatChannels::first_four::<BiomeT>(),
+    )
+}
+
+///! generate_splatmap with an explicit biome -> channel assignment.
+///! pixel (u, v) samples the center of its texel: u along world x, v along world z.
+///! weights of biomes without a channel are dropped and the rest renormalized, so the
+///! channels of a pixel sum to 1 unless none of its biomes has a channel (all 0)
+pub fn generate_splatmap_with<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    rect: Rect,
+    resolution: UVec2,
+    channels: &SplatChannels,
+) -> Image
+where
+    BiomeT: BiomeVariants + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let size = rect.size().as_dvec2() / resolution.max(UVec2::ONE).as_dvec2();
+    let mut data = Vec::with_capacity((resolution.x * resolution.y * 4) as usize);
+    for v in 0..resolution.y {
+        for u in 0..resolution.x {
+            let x = rect.min.x as f64 + (u as f64 + 0.5) * size.x;
+            let z = rect.min.y as f64 + (v as f64 + 0.5) * size.y;
+            let mut splat = [0.0; 4];
+            for (w, biome) in worley.get(x, z).iter() {
+                if let Some(channel) = channels.channel(biome) {
+                    splat[channel as usize] += w;
+                }
+            }
+            let total: f64 = splat.iter().sum();
+            if total > 0.0 {
+                splat.iter_mut().for_each(|w| *w /= total);
+            }
+            data.extend(splat.map(|w| (w * 255.0).round().clamp(0.0, 255.0) as u8));
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: resolution.x,
+            height: resolution.y,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        // weights, not colors: no srgb curve
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    )
+}
@@ -0,0 +1,159 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    mesh::{Indices, PrimitiveTopology},
+    prelude::*,
+};
+
+use crate::{
+    bevy::{splatmap::SplatChannels, traits::DebugHeight},
+    biome_picker::{BiomePicker, BiomeVariants},
+    worley::Worley,
+};
+
+///! what a terrain mesh stores in Mesh::ATTRIBUTE_COLOR
+#[derive(Clone, Debug, Default)]
+pub enum VertexWeights<BiomeT> {
+    ///! no color attribute
+    #[default]
+    None,
+    ///! the biome colors blended by weight (in linear space), e.g. DebugColor::get_color
+    Color(fn(&BiomeT) -> Srgba),
+    ///! up to four biome weights as RGBA, like generate_splatmap_with, for splatting shaders
+    Splat(SplatChannels),
+}
+
+///! the region and detail of build_terrain_mesh
+#[derive(Clone, Debug)]
+pub struct TerrainMesh<BiomeT> {
+    ///! world (x, z) area covered by the mesh
+    pub rect: Rect,
+    ///! quads along x and z, the mesh has (resolution + 1)^2 vertices
+    pub resolution: UVec2,
+    pub weights: VertexWeights<BiomeT>,
+}
+
+impl<BiomeT> TerrainMesh<BiomeT> {
+    pub fn new(rect: Rect, resolution: UVec2) -> Self {
+        Self {
+            rect,
+            resolution,
+            weights: VertexWeights::None,
+        }
+    }
+
+    pub fn with_weights(mut self, weights: VertexWeights<BiomeT>) -> Self {
+        self.weights = weights;
+        self
+    }
+}
+
+///! a single grid mesh over terrain.rect with the blended DebugHeight as y.
+///! positions are relative to rect.min (place the entity at rect.min.x, 0, rect.min.y),
+///! UV_0 runs 0..1 over the rect, so a splatmap of the same rect lines up.
+///! normals sample one vertex beyond the rect, neighbouring meshes shade seamlessly
+pub fn build_terrain_mesh<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    terrain: &TerrainMesh<BiomeT>,
+) -> Mesh
+where
+    BiomeT: BiomeVariants + DebugHeight + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let res = terrain.resolution.max(UVec2::ONE);
+    let step = terrain.rect.size() / res.as_vec2();
+    let (w, h) = (res.x as usize + 1, res.y as usize + 1);
+    let world = |u: i64, v: i64| {
+        (
+            terrain.rect.min.x as f64 + u as f64 * step.x as f64,
+            terrain.rect.min.y as f64 + v as f64 * step.y as f64,
+        )
+    };
+
+    // heights with a one vertex border, for the normals.
+    // the blend weights are kept (in the same layout) for the vertex weights
+    let bw = w + 2;
+    let keep_weights = !matches!(terrain.weights, VertexWeights::None);
+    let mut heights = vec![0.0; bw * (h + 2)];
+    let mut samples = Vec::with_capacity(if keep_weights { heights.len() } else { 0 });
+    for v in 0..h + 2 {
+        for u in 0..bw {
+            let (x, z) = world(u as i64 - 1, v as i64 - 1);
+            let weights = worley.get(x, z);
+            heights[v * bw + u] = weights
+                .iter()
+                .map(|(weight, biome)| biome.get_height() * *weight as f32)
+                .sum();
+            if keep_weights {
+                samples.push(weights);
+            }
+        }
+    }
+    let height = |u: usize, v: usize| heights[(v + 1) * bw + u + 1];
+
+    let mut positions = Vec::with_capacity(w * h);
+    let mut normals = Vec::with_capacity(w * h);
+    let mut uvs = Vec::with_capacity(w * h);
+    let mut colors = Vec::new();
+    for v in 0..h {
+        for u in 0..w {
+            positions.push([u as f32 * step.x, height(u, v), v as f32 * step.y]);
+            // central differences, the border makes u - 1 and u + 1 always valid
+            let dx = (heights[(v + 1) * bw + u + 2] - heights[(v + 1) * bw + u]) / (2.0 * step.x);
+            let dz = (heights[(v + 2) * bw + u + 1] - heights[v * bw + u + 1]) / (2.0 * step.y);
+            normals.push(Vec3::new(-dx, 1.0, -dz).normalize().to_array());
+            uvs.push([u as f32 / res.x as f32, v as f32 / res.y as f32]);
+
+            match &terrain.weights {
+                VertexWeights::None => {}
+                VertexWeights::Color(color_fn) => {
+                    let mut color = [0.0; 4];
+                    for (weight, biome) in samples[(v + 1) * bw + u + 1].iter() {
+                        let c = LinearRgba::from(color_fn(biome)).to_f32_array();
+                        for (acc, c) in color.iter_mut().zip(c) {
+                            *acc += c * *weight as f32;
+                        }
+                    }
+                    colors.push(color);
+                }
+                VertexWeights::Splat(channels) => {
+                    let mut splat = [0.0; 4];
+                    for (weight, biome) in samples[(v + 1) * bw + u + 1].iter() {
+                        if let Some(channel) = channels.channel(biome) {
+                            splat[channel as usize] += *weight as f32;
+                        }
+                    }
+                    let total: f32 = splat.iter().sum();
+                    if total > 0.0 {
+                        splat.iter_mut().for_each(|s| *s /= total);
+                    }
+                    colors.push(splat);
+                }
+            }
+        }
+    }
+
+    let mut indices = Vec::with_capacity(res.x as usize * res.y as usize * 6);
+    for v in 0..h - 1 {
+        for u in 0..w - 1 {
+            let i00 = (v * w + u) as u32;
+            let i10 = i00 + 1;
+            let i01 = i00 + w as u32;
+            let i11 = i01 + 1;
+            // counter clockwise seen from above
+            indices.extend([i00, i01, i10, i10, i01, i11]);
+        }
+    }
+
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices));
+    if !colors.is_empty() {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    }
+    mesh
+}
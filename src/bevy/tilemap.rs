@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::{
+    biome_picker::{BiomePicker, BiomeVariants},
+    utils::{derive_seed, hash_f32, seed_domain},
+    worley::Worley,
+};
+
+///! the tile texture of a biome (index into the tilemap's texture atlas)
+pub trait BiomeTile {
+    fn tile_index(&self) -> u32;
+}
+
+///! how a tile picks between the biomes blending at its position
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TileDither {
+    ///! always the highest weighted biome, hard borders
+    #[default]
+    Dominant,
+    ///! each biome with a chance of its weight, a noisy border
+    Random,
+    ///! like Random, with a 4x4 bayer matrix instead of a hash, a regular pattern
+    Ordered,
+}
+
+///! where the tilemap sits in the world
+#[derive(Clone, Copy, Debug)]
+pub struct TilemapFill {
+    ///! world (x, z) of tile (0, 0)
+    pub origin: (f64, f64),
+    ///! world units per tile
+    pub tile_world_size: f64,
+    pub dither: TileDither,
+}
+
+impl Default for TilemapFill {
+    fn default() -> Self {
+        Self {
+            origin: (0.0, 0.0),
+            tile_world_size: 1.0,
+            dither: TileDither::default(),
+        }
+    }
+}
+
+const BAYER_4X4: [u32; 16] = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+
+///! the tile index of tile (tx, ty), None if every cell around it is empty.
+///! tilemap y is the world z axis
+pub fn tile_index_at<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    fill: &TilemapFill,
+    tx: u32,
+    ty: u32,
+) -> Option<u32>
+where
+    BiomeT: BiomeVariants + BiomeTile + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let x = fill.origin.0 + tx as f64 * fill.tile_world_size;
+    let z = fill.origin.1 + ty as f64 * fill.tile_world_size;
+    let threshold = match fill.dither {
+        TileDither::Dominant => {
+            return worley.dominant(x, z).map(|biome| biome.tile_index());
+        }
+        TileDither::Random => hash_f32(
+            derive_seed(worley.seed, seed_domain::DITHER),
+            tx as i32,
+            ty as i32,
+        ) as f64,
+        TileDither::Ordered => (BAYER_4X4[((ty % 4) * 4 + tx % 4) as usize] as f64 + 0.5) / 16.0,
+    };
+    let weights = worley.get(x, z);
+    let mut cumulative = 0.0;
+    for (w, biome) in weights.iter() {
+        cumulative += w;
+        if threshold < cumulative {
+            return Some(biome.tile_index());
+        }
+    }
+    // rounding left the sum slightly below 1
+    weights.last().map(|(_, biome)| biome.tile_index())
+}
+
+///! fill every tile of a tilemap layer from the worley.
+///! missing tiles are spawned, existing ones get a new TileTextureIndex,
+///! tiles over empty cells are despawned
+///! ```ignore
+///! let mut storage = TileStorage::empty(map_size);
+///! fill_tilemap(&mut commands, worley, tilemap_entity, &mut storage, &TilemapFill::default());
+///! commands.entity(tilemap_entity).insert(TilemapBundle { storage, size: map_size, .. });
+///! ```
+pub fn fill_tilemap<BiomeT, Picker>(
+    commands: &mut Commands,
+    worley: &Worley<BiomeT, Picker>,
+    tilemap_entity: Entity,
+    storage: &mut TileStorage,
+    fill: &TilemapFill,
+) where
+    BiomeT: BiomeVariants + BiomeTile + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let size = storage.size;
+    for tx in 0..size.x {
+        for ty in 0..size.y {
+            let position = TilePos { x: tx, y: ty };
+            let existing = storage.get(&position);
+            match (tile_index_at(worley, fill, tx, ty), existing) {
+                (Some(index), Some(tile)) => {
+                    commands.entity(tile).insert(TileTextureIndex(index));
+                }
+                (Some(index), None) => {
+                    let tile = commands
+                        .spawn(TileBundle {
+                            position,
+                            tilemap_id: TilemapId(tilemap_entity),
+                            texture_index: TileTextureIndex(index),
+                            ..default()
+                        })
+                        .id();
+                    commands.entity(tilemap_entity).add_child(tile);
+                    storage.set(&position, tile);
+                }
+                (None, Some(tile)) => {
+                    commands.entity(tile).despawn();
+                    storage.remove(&position);
+                }
+                (None, None) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use bevy::color::{Hsla, Srgba};
+
+use crate::{
+    biome_picker::{BiomePicker, BiomeVariants, variant_index},
+    worley::Worley,
+};
+
+///! color of biome to display in debug worley texture.
+///! with feature="derive", `#[derive(DebugColor)]` implements it with auto_debug_color
+pub trait DebugColor<BiomeT> {
+    fn get_color(&self) -> Srgba;
+}
+
+#[cfg(feature = "derive")]
+pub use worley_biomes_derive::DebugColor;
+
+///! a generated color per variant: hues evenly spaced over BiomeT::variants(),
+///! alternating lightness so neighbouring variants stay apart when there are many
+pub fn auto_debug_color<BiomeT: BiomeVariants + 'static>(biome: &BiomeT) -> Srgba {
+    let idx = variant_index(biome).unwrap_or(0);
+    let hue = idx as f32 / BiomeT::variants().len().max(1) as f32 * 360.0;
+    let lightness = if idx % 2 == 0 { 0.5 } else { 0.65 };
+    Hsla::hsl(hue, 0.7, lightness).into()
+}
+
+///! the biome's color in the debug views: the worley's debug_palette override, or DebugColor
+pub fn debug_color<BiomeT, Picker>(worley: &Worley<BiomeT, Picker>, biome: &BiomeT) -> Srgba
+where
+    BiomeT: BiomeVariants + DebugColor<BiomeT> + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    if worley.debug_palette.is_empty() {
+        return biome.get_color();
+    }
+    palette_color(&worley.debug_palette, variant_index(biome), biome)
+}
+
+///! debug_color, with the variant index already known
+pub fn palette_color<BiomeT: DebugColor<BiomeT>>(
+    palette: &[Option<[f32; 3]>],
+    variant_idx: Option<usize>,
+    biome: &BiomeT,
+) -> Srgba {
+    match variant_idx.and_then(|i| palette.get(i).copied().flatten()) {
+        Some([r, g, b]) => Srgba::new(r, g, b, 1.0),
+        None => biome.get_color(),
+    }
+}
+
+///! the Okabe-Ito colors without black, distinguishable with the common color vision
+///! deficiencies (red-green and blue-yellow)
+pub const COLOR_BLIND_PALETTE: [[f32; 3]; 7] = [
+    [0.902, 0.624, 0.0],
+    [0.337, 0.706, 0.914],
+    [0.0, 0.62, 0.451],
+    [0.941, 0.894, 0.259],
+    [0.0, 0.447, 0.698],
+    [0.835, 0.369, 0.0],
+    [0.8, 0.475, 0.655],
+];
+
+///! COLOR_BLIND_PALETTE color of variant index variant_idx. the colors repeat after 7 variants,
+///! the preview's biome_patterns tell those apart
+pub fn color_blind_color(variant_idx: usize) -> Srgba {
+    let [r, g, b] = COLOR_BLIND_PALETTE[variant_idx % COLOR_BLIND_PALETTE.len()];
+    Srgba::new(r, g, b, 1.0)
+}
+
+///! height of a biome, blended by weight: the y of terrain_mesh::build_terrain_mesh, and the
+///! debug preview's grayscale heights (see DebugPlugin::with_heights)
+pub trait DebugHeight {
+    fn get_height(&self) -> f32;
+}
+
+///! Deserialize with feature="serde", any type without. lets a plugin keep one set of bounds
+///! while only loading assets with feature="serde"
+#[cfg(feature = "serde")]
+pub trait MaybeDeserialize: for<'de> serde::Deserialize<'de> {}
+
+#[cfg(feature = "serde")]
+impl<T: for<'de> serde::Deserialize<'de>> MaybeDeserialize for T {}
+
+#[cfg(not(feature = "serde"))]
+pub trait MaybeDeserialize {}
+
+#[cfg(not(feature = "serde"))]
+impl<T> MaybeDeserialize for T {}
+
+///! Serialize + Deserialize with feature="serde", any type without, see MaybeDeserialize
+#[cfg(feature = "serde")]
+pub trait MaybeSerde: MaybeDeserialize + serde::Serialize {}
+
+#[cfg(feature = "serde")]
+impl<T: MaybeDeserialize + serde::Serialize> MaybeSerde for T {}
+
+#[cfg(not(feature = "serde"))]
+pub trait MaybeSerde {}
+
+#[cfg(not(feature = "serde"))]
+impl<T> MaybeSerde for T {}
+
+///! required for the debug_plugin to find what worley to visualize
+pub trait GetWorley<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    fn get_worley<'a>(&'a self) -> &'a Worley<BiomeT, Picker>;
+    fn get_worley_mut<'a>(&'a mut self) -> &'a mut Worley<BiomeT, Picker>;
+}
@@ -0,0 +1,81 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+#[cfg(feature = "serde")]
+use crate::bevy::asset::WorleyAssetPlugin;
+use crate::{
+    bevy::{
+        changed::WorleyChangedPlugin,
+        noise_reflect::{FractalTypeReflect, NoiseTypeReflect},
+        resource::WorleyResource,
+        traits::MaybeDeserialize,
+    },
+    biome_picker::{BiomePicker, BiomeVariants},
+    distance_fn::DistanceFn,
+    warp::{WarpMode, WarpSeed, WarpSettings, WarpSpace},
+};
+
+///! the integration glue for shipping builds, no egui and no preview:
+///! initializes WorleyResource<BiomeT, Picker>, registers the crate's types for reflection,
+///! sends WorleyChanged and (with feature="serde") loads `.worley.ron` assets into the resource.
+///! needs only feature="bevy-core"
+///! ```ignore
+///! app.insert_resource(WorleyResource(worley))
+///!     .add_plugins(WorleyPlugin::<Biome, SimpleBiomePicker<Biome>>::default());
+///! ```
+pub struct WorleyPlugin<BiomeT, Picker> {
+    pub _phantom: PhantomData<fn() -> (BiomeT, Picker)>,
+}
+
+impl<BiomeT, Picker> Default for WorleyPlugin<BiomeT, Picker> {
+    fn default() -> Self {
+        Self {
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<BiomeT, Picker> Plugin for WorleyPlugin<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static + MaybeDeserialize,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static + MaybeDeserialize,
+{
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorleyResource<BiomeT, Picker>>();
+        register_reflect_types(app);
+        WorleyChangedPlugin::<WorleyResource<BiomeT, Picker>, BiomeT, Picker>::add_once(app);
+        add_asset_plugin::<BiomeT, Picker>(app);
+    }
+}
+
+// loads `.worley.ron` assets into the resource
+#[cfg(feature = "serde")]
+fn add_asset_plugin<BiomeT, Picker>(app: &mut App)
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static + MaybeDeserialize,
+    Picker: BiomePicker<BiomeT> + Default + Clone + Send + Sync + 'static + MaybeDeserialize,
+{
+    if !app.is_plugin_added::<WorleyAssetPlugin<WorleyResource<BiomeT, Picker>, BiomeT, Picker>>() {
+        app.add_plugins(WorleyAssetPlugin::<
+            WorleyResource<BiomeT, Picker>,
+            BiomeT,
+            Picker,
+        >::default());
+    }
+}
+
+// no asset loader without feature="serde"
+#[cfg(not(feature = "serde"))]
+fn add_asset_plugin<BiomeT, Picker>(_app: &mut App) {}
+
+// the crate's non generic types, Worley<BiomeT, Picker> itself has to be registered by the user
+pub(crate) fn register_reflect_types(app: &mut App) {
+    app.register_type::<DistanceFn>()
+        .register_type::<WarpSettings>()
+        .register_type::<WarpMode>()
+        .register_type::<WarpSeed>()
+        .register_type::<WarpSpace>()
+        .register_type::<NoiseTypeReflect>()
+        .register_type::<FractalTypeReflect>();
+}
@@ -1,34 +1,226 @@
-use rand::Rng;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use rand::{Rng, RngCore, SeedableRng};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::utils::{hash_u64, seeded_rng};
+use crate::utils::{SplitMix64, derive_seed, hash_f32, hash_u64, seed_domain, seeded_rng_with};
+
+pub mod anti_cluster;
+pub mod combinators;
+pub mod coverage;
+pub mod dynamic;
+pub mod fixed;
+#[cfg(feature = "image")]
+pub mod image_mask;
+pub mod land_mask;
+pub mod markov;
+pub mod mask_blend;
+pub mod mutation;
+pub mod noise;
+pub mod sized;
+pub mod sparse;
+pub mod subset;
+#[cfg(feature = "dyn-serde")]
+pub mod tagged;
+#[cfg(feature = "serde")]
+pub mod weights_by_name;
+pub use anti_cluster::{AntiClusterMode, AntiClusterPicker};
+pub use combinators::{CellPredicate, FallbackPicker, MapPicker, SwitchPicker};
+pub use coverage::{CoverageArea, CoveragePicker};
+pub use dynamic::DynPicker;
+pub use fixed::FixedPicker;
+#[cfg(feature = "image")]
+pub use image_mask::{ImageMapping, ImageMaskPicker};
+pub use land_mask::LandMaskPicker;
+pub use markov::{MarkovPicker, ParentDirection};
+pub use mask_blend::MaskBlendPicker;
+pub use mutation::MutationPicker;
+pub use noise::NoisePicker;
+pub use sized::SizedPicker;
+pub use sparse::SparsePicker;
+pub use subset::SubsetPicker;
+#[cfg(feature = "dyn-serde")]
+pub use tagged::{SIMPLE_PICKER_TAG, SerializablePicker, TaggedPicker, register_picker};
+
+#[cfg(feature = "derive")]
+pub use worley_biomes_derive::BiomeVariants;
 
+///! `seed` is the world seed, pickers derive their own seed from it (see utils::seed_domain)
 pub trait BiomePicker<BiomeT> {
     fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT;
+
+    ///! None: featureless cell. Worley::get skips it and blends the next-nearest non-empty cells instead,
+    ///! searching up to MAX_SEARCH_RADIUS cells out. wrapper pickers forward it to their inner pickers.
+    ///! pickers without empty cells can keep the default
+    #[inline(always)]
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        Some(self.pick_biome(seed, cell_x, cell_z))
+    }
+
+    ///! hash of the picker's settings, part of Worley::config_hash. wrapper pickers combine their
+    ///! inner pickers' hashes. the default only hashes the type name: settings changes of a picker
+    ///! keeping it aren't seen, and it can differ between compiler versions. implement it
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        std::any::type_name::<Self>().hash(&mut h);
+        h.finish()
+    }
+
+    ///! Err with the reason if picking would panic or be skewed (e.g. empty or NaN weights),
+    ///! see Worley::validate. pickers without such settings can keep the default
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    ///! fix what validate complains about, returning a warning per change, see Worley::sanitize
+    fn sanitize(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+// validate of a nested picker, its error prefixed with the field it sits in
+fn validate_field<BiomeT>(field: &str, picker: &impl BiomePicker<BiomeT>) -> Result<(), String> {
+    picker
+        .validate()
+        .map_err(|err| format!("{}: {}", field, err))
+}
+
+// sanitize of a nested picker, its warnings prefixed with the field it sits in
+fn sanitize_field<BiomeT>(
+    field: &str,
+    picker: &mut impl BiomePicker<BiomeT>,
+    warnings: &mut Vec<String>,
+) {
+    warnings.extend(
+        picker
+            .sanitize()
+            .into_iter()
+            .map(|warning| format!("{}: {}", field, warning)),
+    );
+}
+
+// a probability field: Err unless finite and in 0..=1
+fn validate_chance(field: &str, chance: f32) -> Result<(), String> {
+    match chance.is_finite() && (0.0..=1.0).contains(&chance) {
+        true => Ok(()),
+        false => Err(format!("{} must be in 0..=1, is {}", field, chance)),
+    }
+}
+
+// clamp a probability field into 0..=1, NaN falls back to `default`
+fn sanitize_chance(field: &str, chance: &mut f32, default: f32, warnings: &mut Vec<String>) {
+    if validate_chance(field, *chance).is_ok() {
+        return;
+    }
+    let fixed = match chance.is_nan() {
+        true => default,
+        false => chance.clamp(0.0, 1.0),
+    };
+    warnings.push(format!("{} {} set to {}", field, chance, fixed));
+    *chance = fixed;
+}
+
+// biomes aren't Hash, variants are told apart by enum discriminant
+fn hash_biome<BiomeT>(biome: &BiomeT, h: &mut impl Hasher) {
+    std::mem::discriminant(biome).hash(h);
+}
+
+fn hash_weights<BiomeT>(weights: &[(BiomeT, f32)], h: &mut impl Hasher) {
+    for (biome, weight) in weights {
+        std::mem::discriminant(biome).hash(h);
+        weight.to_bits().hash(h);
+    }
+}
+
+fn validate_weights<BiomeT>(weights: &[(BiomeT, f32)]) -> Result<(), String> {
+    if weights.is_empty() {
+        return Err("weights are empty".to_string());
+    }
+    if let Some((_, w)) = weights.iter().find(|(_, w)| !(w.is_finite() && *w >= 0.0)) {
+        return Err(format!("weights must be finite and >= 0, found {}", w));
+    }
+    let sum: f32 = weights.iter().map(|(_, w)| w).sum();
+    if (sum - 1.0).abs() > WEIGHT_SUM_TOLERANCE {
+        return Err(format!("weights must sum to 1, sum to {}", sum));
+    }
+    Ok(())
+}
+
+const WEIGHT_SUM_TOLERANCE: f32 = 1e-3;
+
+// drop invalid weights and normalize the rest, false if nothing usable is left
+fn sanitize_weights<BiomeT>(weights: &mut Vec<(BiomeT, f32)>, warnings: &mut Vec<String>) -> bool {
+    let len = weights.len();
+    weights.retain(|(_, w)| w.is_finite() && *w >= 0.0);
+    if weights.len() != len {
+        warnings.push(format!(
+            "dropped {} weights that were negative or not finite",
+            len - weights.len()
+        ));
+    }
+    let sum: f32 = weights.iter().map(|(_, w)| w).sum();
+    if sum <= 0.0 {
+        return false;
+    }
+    if (sum - 1.0).abs() > WEIGHT_SUM_TOLERANCE {
+        warnings.push(format!("weights summed to {}, normalized to 1", sum));
+        for (_, w) in weights.iter_mut() {
+            *w /= sum;
+        }
+    }
+    true
 }
 
 ///! trait needed to know what variants are available
+///! with feature="derive", fieldless enums can use `#[derive(BiomeVariants)]`
 pub trait BiomeVariants: Copy {
     fn variants() -> &'static [Self]; // list of all variants
 }
 
+// index of biome in BiomeT::variants(). BiomeT isn't required to be PartialEq (or Hash), so
+// variants are told apart by enum discriminant: data carrying variants match whatever their data
+pub(crate) fn variant_index<BiomeT: BiomeVariants + 'static>(biome: &BiomeT) -> Option<usize> {
+    let discriminant = std::mem::discriminant(biome);
+    BiomeT::variants()
+        .iter()
+        .position(|v| std::mem::discriminant(v) == discriminant)
+}
+
+///! with feature="strum", any enum deriving `strum::VariantArray` is a BiomeVariants.
+///! (don't also implement or derive BiomeVariants for it, the impls would conflict)
+#[cfg(feature = "strum")]
+impl<T> BiomeVariants for T
+where
+    T: strum::VariantArray + Copy,
+{
+    fn variants() -> &'static [Self] {
+        T::VARIANTS
+    }
+}
+
 ///! used to generates a biome VARIANT, based upon a "cell" position
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Default)]
+#[cfg_attr(feature = "bevy-core", derive(bevy::reflect::TypePath))]
+#[derive(Default, Clone)]
 pub enum SimpleBiomePicker<BiomeT: BiomeVariants> {
     // all variants have same chance of being selected
     #[default]
+    #[cfg_attr(feature = "serde", serde(alias = "UniformDistribution"))]
     Any,
     // all variants have same chance of being selected
     AnyOf(Vec<BiomeT>),
-    // weighted odds for biomes to be selected
-    Weighted(Vec<(BiomeT, f32)>),
+    // weighted odds for biomes to be selected, saved keyed by biome (see weights_by_name)
+    Weighted(#[cfg_attr(feature = "serde", serde(with = "weights_by_name"))] Vec<(BiomeT, f32)>),
+    // every cell is this biome, useful for tests and tuning warp/sharpness in isolation
+    Single(BiomeT),
 }
 
 impl<BiomeT: BiomeVariants + 'static> BiomePicker<BiomeT> for SimpleBiomePicker<BiomeT> {
     #[inline(always)]
     fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        let seed = derive_seed(seed, seed_domain::PICK);
         match self {
             SimpleBiomePicker::Any => {
                 let variants = BiomeT::variants();
@@ -40,21 +232,142 @@ impl<BiomeT: BiomeVariants + 'static> BiomePicker<BiomeT> for SimpleBiomePicker<
                 variants[idx]
             }
             SimpleBiomePicker::Weighted(weights) => {
-                // turn hash into rng
-                let mut rng = seeded_rng(seed, cell_x, cell_z);
-                let roll: f32 = rng.random();
-
-                let mut cumulative = 0.0;
-                for (biome, weight) in weights {
-                    cumulative += weight;
-                    if roll < cumulative {
-                        return *biome;
-                    }
+                pick_by_roll(weights, hash_f32(seed, cell_x, cell_z))
+            }
+            SimpleBiomePicker::Single(biome) => *biome,
+        }
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        std::mem::discriminant(self).hash(&mut h);
+        match self {
+            SimpleBiomePicker::Any => {}
+            SimpleBiomePicker::AnyOf(variants) => {
+                for biome in variants {
+                    std::mem::discriminant(biome).hash(&mut h);
                 }
-                // fallback (shouldn’t happen if weights sum to 1.0)
-                weights.last().unwrap().0
             }
+            SimpleBiomePicker::Weighted(weights) => hash_weights(weights, &mut h),
+            SimpleBiomePicker::Single(biome) => std::mem::discriminant(biome).hash(&mut h),
         }
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            SimpleBiomePicker::AnyOf(variants) if variants.is_empty() => {
+                Err("AnyOf has no biomes".to_string())
+            }
+            SimpleBiomePicker::Weighted(weights) => validate_weights(weights),
+            _ => Ok(()),
+        }
+    }
+
+    ///! an empty AnyOf or unusable Weighted falls back to Any
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let usable = match self {
+            SimpleBiomePicker::AnyOf(variants) => !variants.is_empty(),
+            SimpleBiomePicker::Weighted(weights) => sanitize_weights(weights, &mut warnings),
+            _ => true,
+        };
+        if !usable {
+            warnings.push("picker has no usable biomes, replaced with Any".to_string());
+            *self = SimpleBiomePicker::Any;
+        }
+        warnings
+    }
+}
+
+// roll a weighted biome, with a per cell seeded R
+#[inline(always)]
+fn pick_weighted<BiomeT: Copy, R: SeedableRng + RngCore>(
+    weights: &[(BiomeT, f32)],
+    seed: u64,
+    cell_x: i32,
+    cell_z: i32,
+) -> BiomeT {
+    // turn hash into rng
+    let mut rng = seeded_rng_with::<R>(seed, cell_x, cell_z);
+    pick_by_roll(weights, rng.random())
+}
+
+// the biome a roll in 0.0..1.0 lands on
+#[inline(always)]
+fn pick_by_roll<BiomeT: Copy>(weights: &[(BiomeT, f32)], roll: f32) -> BiomeT {
+    let mut cumulative = 0.0;
+    for (biome, weight) in weights {
+        cumulative += weight;
+        if roll < cumulative {
+            return *biome;
+        }
+    }
+    // fallback (shouldn’t happen if weights sum to 1.0)
+    weights.last().unwrap().0
+}
+
+///! same weights as SimpleBiomePicker::Weighted, but rolled with a pluggable rng R
+///! (any `SeedableRng + RngCore`, e.g. rand_pcg::Pcg32, or StdRng for worlds generated before
+///! SplitMix64 became the default)
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "BiomeT: Serialize",
+        deserialize = "BiomeT: Deserialize<'de>"
+    ))
+)]
+pub struct WeightedPicker<BiomeT, R = SplitMix64> {
+    // weighted odds for biomes to be selected, saved keyed by biome (see weights_by_name)
+    #[cfg_attr(feature = "serde", serde(with = "weights_by_name"))]
+    pub weights: Vec<(BiomeT, f32)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _rng: PhantomData<fn() -> R>,
+}
+
+impl<BiomeT, R> Default for WeightedPicker<BiomeT, R> {
+    fn default() -> Self {
+        Self {
+            weights: Vec::new(),
+            _rng: PhantomData,
+        }
+    }
+}
+
+impl<BiomeT, R> WeightedPicker<BiomeT, R> {
+    pub fn new(weights: Vec<(BiomeT, f32)>) -> Self {
+        Self {
+            weights,
+            _rng: PhantomData,
+        }
+    }
+}
+
+impl<BiomeT: Copy, R: SeedableRng + RngCore> BiomePicker<BiomeT> for WeightedPicker<BiomeT, R> {
+    #[inline(always)]
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        let seed = derive_seed(seed, seed_domain::PICK);
+        pick_weighted::<BiomeT, R>(&self.weights, seed, cell_x, cell_z)
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        hash_weights(&self.weights, &mut h);
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_weights(&self.weights)
+    }
+
+    ///! without BiomeVariants there is no fallback, weights left empty stay invalid
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !sanitize_weights(&mut self.weights, &mut warnings) {
+            warnings.push("weights have nothing usable left".to_string());
+        }
+        warnings
     }
 }
 
@@ -84,3 +397,80 @@ impl<BiomeT: BiomeVariants + 'static> BiomePicker<BiomeT> for SimpleBiomePicker<
 //         }
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    enum TestBiome {
+        #[default]
+        Plains,
+        Forest,
+    }
+
+    impl BiomeVariants for TestBiome {
+        fn variants() -> &'static [Self] {
+            &[TestBiome::Plains, TestBiome::Forest]
+        }
+    }
+
+    type Simple = SimpleBiomePicker<TestBiome>;
+
+    #[test]
+    fn wrappers_forward_validate_and_sanitize() {
+        let mut picker = MutationPicker::<TestBiome, _> {
+            inner: SparsePicker {
+                inner: Simple::AnyOf(Vec::new()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            picker.validate(),
+            Err("inner: inner: AnyOf has no biomes".to_string())
+        );
+        assert_eq!(picker.sanitize().len(), 1);
+        assert!(picker.validate().is_ok());
+        assert!(matches!(picker.inner.inner, SimpleBiomePicker::Any));
+
+        let mut boxed: Box<dyn BiomePicker<TestBiome>> = Box::new(Simple::AnyOf(Vec::new()));
+        assert!(boxed.validate().is_err());
+        assert_eq!(boxed.sanitize().len(), 1);
+
+        let mut dynamic = DynPicker::new(Simple::Weighted(vec![(TestBiome::Forest, f32::NAN)]));
+        assert!(dynamic.validate().is_err());
+        assert!(!dynamic.sanitize().is_empty());
+    }
+
+    #[test]
+    fn wrappers_hash_their_settings_and_inner_pickers() {
+        let sparse = |inner: Simple, density: f32| {
+            MapPicker {
+                inner: SparsePicker {
+                    inner,
+                    density,
+                    ..Default::default()
+                },
+                map: vec![(TestBiome::Plains, TestBiome::Forest)],
+            }
+            .config_hash()
+        };
+        let base = sparse(Simple::Single(TestBiome::Plains), 0.5);
+        assert_eq!(base, sparse(Simple::Single(TestBiome::Plains), 0.5));
+        assert_ne!(base, sparse(Simple::Single(TestBiome::Forest), 0.5));
+        assert_ne!(base, sparse(Simple::Single(TestBiome::Plains), 0.25));
+
+        let fixed = FixedPicker::new(TestBiome::Plains)
+            .with_cell(0, 0, TestBiome::Forest)
+            .with_cell(1, 2, TestBiome::Plains);
+        let reordered = FixedPicker::new(TestBiome::Plains)
+            .with_cell(1, 2, TestBiome::Plains)
+            .with_cell(0, 0, TestBiome::Forest);
+        assert_eq!(fixed.config_hash(), reordered.config_hash());
+        assert_ne!(
+            fixed.config_hash(),
+            FixedPicker::new(TestBiome::Plains).config_hash()
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use std::hash::{Hash, Hasher};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::biome_picker::{BiomePicker, BiomeVariants, hash_biome};
+use crate::utils::{derive_seed, hash_f32, hash_u64, seed_domain};
+
+///! how AntiClusterPicker decorrelates neighboring cells
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum AntiClusterMode {
+    ///! reroll when the cell's roll matches the west or north neighbor's first roll.
+    ///! approximate: the neighbors' first rolls, not the biomes they end up with after their own
+    ///! rerolls (resolving those would chain across the whole row), so equal neighbors get rare,
+    ///! not impossible. still random looking
+    #[default]
+    NeighborReject,
+    ///! interleaved gradient noise over cell coordinates, shifted per seed.
+    ///! neighbors (almost) never match, but the layout is visibly more regular
+    InterleavedGradient,
+}
+
+///! uniform variant picking where long runs of the same biome are statistically rare
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "BiomeT: Serialize",
+        deserialize = "BiomeT: Deserialize<'de>"
+    ))
+)]
+pub struct AntiClusterPicker<BiomeT: BiomeVariants> {
+    ///! variants to pick from, empty: all BiomeT::variants()
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub variants: Vec<BiomeT>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mode: AntiClusterMode,
+}
+
+impl<BiomeT: BiomeVariants> Default for AntiClusterPicker<BiomeT> {
+    fn default() -> Self {
+        Self {
+            variants: Vec::new(),
+            mode: AntiClusterMode::default(),
+        }
+    }
+}
+
+// rerolls before NeighborReject gives up and accepts a match
+const MAX_REROLLS: u64 = 4;
+
+impl<BiomeT: BiomeVariants + 'static> AntiClusterPicker<BiomeT> {
+    fn variant_slice(&self) -> &[BiomeT] {
+        match self.variants.is_empty() {
+            true => BiomeT::variants(),
+            false => &self.variants,
+        }
+    }
+
+    #[inline(always)]
+    fn base_index(seed: u64, cell_x: i32, cell_z: i32, len: usize) -> usize {
+        (hash_u64(seed, cell_x, cell_z) % len as u64) as usize
+    }
+}
+
+impl<BiomeT: BiomeVariants + 'static> BiomePicker<BiomeT> for AntiClusterPicker<BiomeT> {
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        let variants = self.variant_slice();
+        let len = variants.len();
+        let seed = derive_seed(seed, seed_domain::ANTI_CLUSTER);
+        let idx = match self.mode {
+            AntiClusterMode::NeighborReject => {
+                // the neighbors' first rolls, see NeighborReject for why not their final biome
+                let west = Self::base_index(seed, cell_x - 1, cell_z, len);
+                let north = Self::base_index(seed, cell_x, cell_z - 1, len);
+                let mut idx = Self::base_index(seed, cell_x, cell_z, len);
+                let mut reroll = 0;
+                while (idx == west || idx == north) && reroll < MAX_REROLLS {
+                    reroll += 1;
+                    idx = Self::base_index(seed.wrapping_add(reroll), cell_x, cell_z, len);
+                }
+                idx
+            }
+            AntiClusterMode::InterleavedGradient => {
+                // Jimenez 2014 interleaved gradient noise
+                let ign = (52.982_918_f64
+                    * (0.067_110_56 * cell_x as f64 + 0.005_837_15 * cell_z as f64).fract())
+                .fract();
+                let shift = hash_f32(seed, 0, 0) as f64;
+                let v = (ign + shift).rem_euclid(1.0);
+                ((v * len as f64) as usize).min(len - 1)
+            }
+        };
+        variants[idx]
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        for biome in &self.variants {
+            hash_biome(biome, &mut h);
+        }
+        self.mode.hash(&mut h);
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self.variant_slice().is_empty() {
+            true => Err("no variants to pick from".to_string()),
+            false => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biome_picker::SimpleBiomePicker;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    enum TestBiome {
+        #[default]
+        Plains,
+        Forest,
+    }
+
+    impl BiomeVariants for TestBiome {
+        fn variants() -> &'static [Self] {
+            &[TestBiome::Plains, TestBiome::Forest]
+        }
+    }
+
+    // NeighborReject is approximate: equal west neighbors get rarer than uniform picking, not impossible
+    #[test]
+    fn neighbor_reject_makes_equal_neighbors_rare() {
+        let equal_west = |picker: &dyn BiomePicker<TestBiome>| {
+            let mut equal = 0;
+            for x in 0..64 {
+                for z in 0..64 {
+                    equal += (picker.pick_biome(3, x, z) == picker.pick_biome(3, x - 1, z)) as u32;
+                }
+            }
+            equal as f64 / (64.0 * 64.0)
+        };
+        let uniform = equal_west(&SimpleBiomePicker::Any);
+        let rejecting = equal_west(&AntiClusterPicker::default());
+        assert!(rejecting < uniform, "{} vs {}", rejecting, uniform);
+    }
+}
@@ -0,0 +1,244 @@
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::biome_picker::{BiomePicker, hash_biome, sanitize_field, validate_field};
+
+///! post-processes the biome picked by `inner` using a lookup table.
+///! biomes not listed in `map` pass through unchanged
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "BiomeT: Serialize, Inner: Serialize",
+        deserialize = "BiomeT: Deserialize<'de>, Inner: Deserialize<'de>"
+    ))
+)]
+#[derive(Default)]
+pub struct MapPicker<BiomeT, Inner>
+where
+    Inner: BiomePicker<BiomeT>,
+{
+    pub inner: Inner,
+    ///! (from, to)
+    pub map: Vec<(BiomeT, BiomeT)>,
+}
+
+impl<BiomeT, Inner> BiomePicker<BiomeT> for MapPicker<BiomeT, Inner>
+where
+    BiomeT: Copy + PartialEq,
+    Inner: BiomePicker<BiomeT>,
+{
+    #[inline(always)]
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        let biome = self.inner.pick_biome(seed, cell_x, cell_z);
+        self.map
+            .iter()
+            .find(|(from, _to)| *from == biome)
+            .map_or(biome, |(_from, to)| *to)
+    }
+
+    #[inline(always)]
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        let biome = self.inner.try_pick_biome(seed, cell_x, cell_z)?;
+        Some(
+            self.map
+                .iter()
+                .find(|(from, _to)| *from == biome)
+                .map_or(biome, |(_from, to)| *to),
+        )
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.inner.config_hash().hash(&mut h);
+        for (from, to) in &self.map {
+            hash_biome(from, &mut h);
+            hash_biome(to, &mut h);
+        }
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_field("inner", &self.inner)
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        sanitize_field("inner", &mut self.inner, &mut warnings);
+        warnings
+    }
+}
+
+///! picks from `primary`, and if it returns `sentinel` (or an empty cell) falls through to `fallback`
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "BiomeT: Serialize, Primary: Serialize, Fallback: Serialize",
+        deserialize = "BiomeT: Deserialize<'de>, Primary: Deserialize<'de>, Fallback: Deserialize<'de>"
+    ))
+)]
+#[derive(Default)]
+pub struct FallbackPicker<BiomeT, Primary, Fallback>
+where
+    Primary: BiomePicker<BiomeT>,
+    Fallback: BiomePicker<BiomeT>,
+{
+    pub primary: Primary,
+    pub fallback: Fallback,
+    ///! the biome value meaning "no answer" from primary
+    pub sentinel: BiomeT,
+}
+
+impl<BiomeT, Primary, Fallback> BiomePicker<BiomeT> for FallbackPicker<BiomeT, Primary, Fallback>
+where
+    BiomeT: PartialEq,
+    Primary: BiomePicker<BiomeT>,
+    Fallback: BiomePicker<BiomeT>,
+{
+    #[inline(always)]
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        let biome = self.primary.pick_biome(seed, cell_x, cell_z);
+        match biome == self.sentinel {
+            true => self.fallback.pick_biome(seed, cell_x, cell_z),
+            false => biome,
+        }
+    }
+
+    #[inline(always)]
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        match self.primary.try_pick_biome(seed, cell_x, cell_z) {
+            Some(biome) if biome != self.sentinel => Some(biome),
+            _ => self.fallback.try_pick_biome(seed, cell_x, cell_z),
+        }
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.primary.config_hash().hash(&mut h);
+        self.fallback.config_hash().hash(&mut h);
+        hash_biome(&self.sentinel, &mut h);
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_field("primary", &self.primary)?;
+        validate_field("fallback", &self.fallback)
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        sanitize_field("primary", &mut self.primary, &mut warnings);
+        sanitize_field("fallback", &mut self.fallback, &mut warnings);
+        warnings
+    }
+}
+
+///! serializable predicate over cell coordinates, used by `SwitchPicker`
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CellPredicate {
+    ///! cell_x < value
+    XBelow(i32),
+    ///! cell_z < value
+    ZBelow(i32),
+    ///! cell is within this (chebyshev) cell distance of the origin
+    WithinRadius(u32),
+    ///! alternating square blocks of this many cells, true on "black" squares
+    Checker(u32),
+    Not(Box<CellPredicate>),
+    And(Box<CellPredicate>, Box<CellPredicate>),
+    Or(Box<CellPredicate>, Box<CellPredicate>),
+}
+
+impl Default for CellPredicate {
+    fn default() -> Self {
+        CellPredicate::XBelow(0)
+    }
+}
+
+impl CellPredicate {
+    pub fn test(&self, cell_x: i32, cell_z: i32) -> bool {
+        match self {
+            CellPredicate::XBelow(value) => cell_x < *value,
+            CellPredicate::ZBelow(value) => cell_z < *value,
+            CellPredicate::WithinRadius(radius) => {
+                cell_x.unsigned_abs().max(cell_z.unsigned_abs()) <= *radius
+            }
+            CellPredicate::Checker(size) => {
+                let size = (*size).max(1) as i32;
+                (cell_x.div_euclid(size) + cell_z.div_euclid(size)).rem_euclid(2) == 0
+            }
+            CellPredicate::Not(p) => !p.test(cell_x, cell_z),
+            CellPredicate::And(a, b) => a.test(cell_x, cell_z) && b.test(cell_x, cell_z),
+            CellPredicate::Or(a, b) => a.test(cell_x, cell_z) || b.test(cell_x, cell_z),
+        }
+    }
+}
+
+///! chooses between two pickers by a predicate on the cell coordinates
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "IfTrue: Serialize, IfFalse: Serialize",
+        deserialize = "IfTrue: Deserialize<'de>, IfFalse: Deserialize<'de>"
+    ))
+)]
+#[derive(Default)]
+pub struct SwitchPicker<BiomeT, IfTrue, IfFalse>
+where
+    IfTrue: BiomePicker<BiomeT>,
+    IfFalse: BiomePicker<BiomeT>,
+{
+    pub predicate: CellPredicate,
+    pub if_true: IfTrue,
+    pub if_false: IfFalse,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _phantom: PhantomData<BiomeT>,
+}
+
+impl<BiomeT, IfTrue, IfFalse> BiomePicker<BiomeT> for SwitchPicker<BiomeT, IfTrue, IfFalse>
+where
+    IfTrue: BiomePicker<BiomeT>,
+    IfFalse: BiomePicker<BiomeT>,
+{
+    #[inline(always)]
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        match self.predicate.test(cell_x, cell_z) {
+            true => self.if_true.pick_biome(seed, cell_x, cell_z),
+            false => self.if_false.pick_biome(seed, cell_x, cell_z),
+        }
+    }
+
+    #[inline(always)]
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        match self.predicate.test(cell_x, cell_z) {
+            true => self.if_true.try_pick_biome(seed, cell_x, cell_z),
+            false => self.if_false.try_pick_biome(seed, cell_x, cell_z),
+        }
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.predicate.hash(&mut h);
+        self.if_true.config_hash().hash(&mut h);
+        self.if_false.config_hash().hash(&mut h);
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_field("if_true", &self.if_true)?;
+        validate_field("if_false", &self.if_false)
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        sanitize_field("if_true", &mut self.if_true, &mut warnings);
+        sanitize_field("if_false", &mut self.if_false, &mut warnings);
+        warnings
+    }
+}
@@ -0,0 +1,170 @@
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::biome_picker::{BiomePicker, BiomeVariants, hash_biome, sanitize_field, validate_field};
+use crate::utils::{derive_seed, hash_u64, seed_domain};
+
+///! where CoveragePicker guarantees every variant to appear
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub enum CoverageArea {
+    ///! within `radius` cells of `center`, everything outside is left to the inner picker
+    #[default]
+    Center,
+    ///! the plane is tiled in blocks of (2 * radius + 1) cells, each containing every variant.
+    ///! so any point sees every variant within about 2 blocks
+    Everywhere,
+}
+
+///! guarantees every biome variant appears at least once within a radius,
+///! by deterministically reserving one cell per variant. other cells come from `inner`
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "BiomeT: Serialize, Inner: Serialize",
+        deserialize = "BiomeT: Deserialize<'de>, Inner: Deserialize<'de>"
+    ))
+)]
+pub struct CoveragePicker<BiomeT, Inner>
+where
+    BiomeT: BiomeVariants,
+    Inner: BiomePicker<BiomeT>,
+{
+    pub inner: Inner,
+    ///! variants to guarantee, empty: all BiomeT::variants()
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub variants: Vec<BiomeT>,
+    ///! (chebyshev) radius in cells. if the area holds fewer cells than variants, only the first ones fit
+    #[cfg_attr(feature = "serde", serde(default = "default_radius"))]
+    pub radius: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub center: (i32, i32),
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub area: CoverageArea,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _phantom: PhantomData<BiomeT>,
+}
+
+#[cfg(feature = "serde")]
+fn default_radius() -> u32 {
+    3
+}
+
+impl<BiomeT, Inner> Default for CoveragePicker<BiomeT, Inner>
+where
+    BiomeT: BiomeVariants,
+    Inner: BiomePicker<BiomeT> + Default,
+{
+    fn default() -> Self {
+        Self {
+            inner: Inner::default(),
+            variants: Vec::new(),
+            radius: 3,
+            center: (0, 0),
+            area: CoverageArea::default(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+impl<BiomeT, Inner> CoveragePicker<BiomeT, Inner>
+where
+    BiomeT: BiomeVariants + 'static,
+    Inner: BiomePicker<BiomeT>,
+{
+    fn variant_slice(&self) -> &[BiomeT] {
+        match self.variants.is_empty() {
+            true => BiomeT::variants(),
+            false => &self.variants,
+        }
+    }
+
+    ///! the reserved variant of a cell, if any
+    pub fn reserved(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        let side = self.radius as i64 * 2 + 1;
+        let cells = (side * side) as u64;
+
+        // local position inside the block, and which block we're in
+        let (lx, lz) = (
+            cell_x as i64 - self.center.0 as i64 + self.radius as i64,
+            cell_z as i64 - self.center.1 as i64 + self.radius as i64,
+        );
+        let (block_x, block_z) = match self.area {
+            CoverageArea::Center => {
+                if lx < 0 || lz < 0 || lx >= side || lz >= side {
+                    return None;
+                }
+                (0, 0)
+            }
+            CoverageArea::Everywhere => (lx.div_euclid(side) as i32, lz.div_euclid(side) as i32),
+        };
+        let local = (lz.rem_euclid(side) * side + lx.rem_euclid(side)) as u64;
+
+        // reserved cells are start + i * stride (mod cells), distinct as long as stride is coprime to cells
+        let h = hash_u64(derive_seed(seed, seed_domain::COVERAGE), block_x, block_z);
+        let start = h % cells;
+        let mut stride = (h >> 32) % cells.max(2) | 1;
+        while gcd(stride, cells) != 1 {
+            stride += 1;
+        }
+
+        let variants = self.variant_slice();
+        let offset = (local + cells - start) % cells;
+        variants
+            .iter()
+            .take(cells as usize)
+            .enumerate()
+            .find(|(i, _biome)| (*i as u64 * stride) % cells == offset)
+            .map(|(_i, biome)| *biome)
+    }
+}
+
+impl<BiomeT, Inner> BiomePicker<BiomeT> for CoveragePicker<BiomeT, Inner>
+where
+    BiomeT: BiomeVariants + 'static,
+    Inner: BiomePicker<BiomeT>,
+{
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        self.reserved(seed, cell_x, cell_z)
+            .unwrap_or_else(|| self.inner.pick_biome(seed, cell_x, cell_z))
+    }
+
+    ///! reserved cells are never empty, the others are empty where inner's are
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        self.reserved(seed, cell_x, cell_z)
+            .or_else(|| self.inner.try_pick_biome(seed, cell_x, cell_z))
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.inner.config_hash().hash(&mut h);
+        for biome in &self.variants {
+            hash_biome(biome, &mut h);
+        }
+        self.radius.hash(&mut h);
+        self.center.hash(&mut h);
+        self.area.hash(&mut h);
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_field("inner", &self.inner)
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        sanitize_field("inner", &mut self.inner, &mut warnings);
+        warnings
+    }
+}
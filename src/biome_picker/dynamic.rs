@@ -0,0 +1,77 @@
+use crate::biome_picker::{BiomePicker, BiomeVariants, SimpleBiomePicker};
+
+impl<BiomeT, P> BiomePicker<BiomeT> for Box<P>
+where
+    P: BiomePicker<BiomeT> + ?Sized,
+{
+    #[inline(always)]
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        (**self).pick_biome(seed, cell_x, cell_z)
+    }
+
+    #[inline(always)]
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        (**self).try_pick_biome(seed, cell_x, cell_z)
+    }
+
+    fn config_hash(&self) -> u64 {
+        (**self).config_hash()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        (**self).validate()
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        (**self).sanitize()
+    }
+}
+
+///! a type erased picker, so one `Worley<BiomeT, DynPicker<BiomeT>>` type can swap
+///! picking strategies at runtime (e.g. chosen in a world creation menu).
+///! costs a virtual call per candidate cell compared to a concrete picker
+pub struct DynPicker<BiomeT>(pub Box<dyn BiomePicker<BiomeT> + Send + Sync>);
+
+impl<BiomeT> DynPicker<BiomeT> {
+    pub fn new(picker: impl BiomePicker<BiomeT> + Send + Sync + 'static) -> Self {
+        Self(Box::new(picker))
+    }
+
+    ///! swap the picking strategy
+    pub fn set(&mut self, picker: impl BiomePicker<BiomeT> + Send + Sync + 'static) {
+        self.0 = Box::new(picker);
+    }
+}
+
+impl<BiomeT> Default for DynPicker<BiomeT>
+where
+    BiomeT: BiomeVariants + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new(SimpleBiomePicker::<BiomeT>::Any)
+    }
+}
+
+impl<BiomeT> BiomePicker<BiomeT> for DynPicker<BiomeT> {
+    #[inline(always)]
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        self.0.pick_biome(seed, cell_x, cell_z)
+    }
+
+    #[inline(always)]
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        self.0.try_pick_biome(seed, cell_x, cell_z)
+    }
+
+    fn config_hash(&self) -> u64 {
+        self.0.config_hash()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.0.validate()
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        self.0.sanitize()
+    }
+}
@@ -0,0 +1,163 @@
+use std::hash::{Hash, Hasher};
+
+use fxhash::FxHashMap;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::biome_picker::{BiomePicker, hash_biome};
+
+///! picks from an explicit (cell -> biome) map, cells not in the map get `default`.
+///! meant for unit tests, golden images, and hand-placed layouts
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "BiomeT: Serialize",
+        deserialize = "BiomeT: Deserialize<'de>"
+    ))
+)]
+#[derive(Default, Clone)]
+pub struct FixedPicker<BiomeT> {
+    // saved as a list of ((x, z), biome), json and toml can't key a map by tuple
+    #[cfg_attr(feature = "serde", serde(with = "cells_serde"))]
+    pub cells: FxHashMap<(i32, i32), BiomeT>,
+    pub default: BiomeT,
+}
+
+impl<BiomeT> FixedPicker<BiomeT> {
+    pub fn new(default: BiomeT) -> Self {
+        Self {
+            cells: FxHashMap::default(),
+            default,
+        }
+    }
+
+    ///! builder style insert
+    pub fn with_cell(mut self, cell_x: i32, cell_z: i32, biome: BiomeT) -> Self {
+        self.cells.insert((cell_x, cell_z), biome);
+        self
+    }
+}
+
+// human readable formats get a list of ((x, z), biome) sorted by cell, loading also accepts
+// the older map form. binary formats (postcard) keep the map
+#[cfg(feature = "serde")]
+mod cells_serde {
+    use std::marker::PhantomData;
+
+    use fxhash::FxHashMap;
+    use serde::{
+        Deserialize, Deserializer, Serialize, Serializer,
+        de::{MapAccess, SeqAccess, Visitor},
+    };
+
+    pub fn serialize<BiomeT, S>(
+        cells: &FxHashMap<(i32, i32), BiomeT>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        BiomeT: Serialize,
+        S: Serializer,
+    {
+        if !serializer.is_human_readable() {
+            return cells.serialize(serializer);
+        }
+        let mut list: Vec<_> = cells.iter().collect();
+        list.sort_by_key(|(cell, _)| **cell);
+        serializer.collect_seq(list)
+    }
+
+    pub fn deserialize<'de, BiomeT, D>(
+        deserializer: D,
+    ) -> Result<FxHashMap<(i32, i32), BiomeT>, D::Error>
+    where
+        BiomeT: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        if !deserializer.is_human_readable() {
+            return FxHashMap::deserialize(deserializer);
+        }
+        deserializer.deserialize_any(CellsVisitor(PhantomData))
+    }
+
+    struct CellsVisitor<BiomeT>(PhantomData<BiomeT>);
+
+    impl<'de, BiomeT: Deserialize<'de>> Visitor<'de> for CellsVisitor<BiomeT> {
+        type Value = FxHashMap<(i32, i32), BiomeT>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a list of ((x, z), biome), or a map of (x, z) to biome")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut cells = FxHashMap::default();
+            while let Some((cell, biome)) = seq.next_element()? {
+                cells.insert(cell, biome);
+            }
+            Ok(cells)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut cells = FxHashMap::default();
+            while let Some((cell, biome)) = map.next_entry()? {
+                cells.insert(cell, biome);
+            }
+            Ok(cells)
+        }
+    }
+}
+
+impl<BiomeT: Copy> BiomePicker<BiomeT> for FixedPicker<BiomeT> {
+    #[inline(always)]
+    fn pick_biome(&self, _seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        *self.cells.get(&(cell_x, cell_z)).unwrap_or(&self.default)
+    }
+
+    // summed per cell, so the map's iteration order doesn't matter
+    fn config_hash(&self) -> u64 {
+        let cells = self.cells.iter().fold(0u64, |sum, (cell, biome)| {
+            let mut h = fxhash::FxHasher64::default();
+            cell.hash(&mut h);
+            hash_biome(biome, &mut h);
+            sum.wrapping_add(h.finish())
+        });
+        let mut h = fxhash::FxHasher64::default();
+        self.cells.len().hash(&mut h);
+        cells.hash(&mut h);
+        hash_biome(&self.default, &mut h);
+        h.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biome_picker::BiomeVariants;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    enum TestBiome {
+        #[default]
+        Plains,
+        Forest,
+    }
+
+    impl BiomeVariants for TestBiome {
+        fn variants() -> &'static [Self] {
+            &[TestBiome::Plains, TestBiome::Forest]
+        }
+    }
+
+    #[test]
+    fn fixed_picker_looks_cells_up() {
+        let picker = FixedPicker::new(TestBiome::Plains)
+            .with_cell(0, 0, TestBiome::Forest)
+            .with_cell(-5, 9, TestBiome::Forest);
+        for seed in [0, 1, u64::MAX] {
+            assert_eq!(picker.pick_biome(seed, 0, 0), TestBiome::Forest);
+            assert_eq!(picker.pick_biome(seed, -5, 9), TestBiome::Forest);
+            assert_eq!(picker.pick_biome(seed, 9, -5), TestBiome::Plains);
+            assert_eq!(picker.pick_biome(seed, 1, 0), TestBiome::Plains);
+            assert_eq!(picker.try_pick_biome(seed, 1, 0), Some(TestBiome::Plains));
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use image::{DynamicImage, ImageError};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::biome_picker::{BiomePicker, hash_biome};
+use crate::utils::cell_point;
+
+///! how pixel values turn into biomes
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "BiomeT: Serialize",
+        deserialize = "BiomeT: Deserialize<'de>"
+    ))
+)]
+#[derive(Clone, Debug)]
+pub enum ImageMapping<BiomeT> {
+    ///! (inclusive upper luma value, biome), sorted ascending. luma above every range uses the last biome
+    GrayRanges(Vec<(u8, BiomeT)>),
+    ///! (rgb, biome), each pixel uses the nearest palette color. for indexed/painted maps
+    Palette(Vec<([u8; 3], BiomeT)>),
+}
+
+impl<BiomeT> Default for ImageMapping<BiomeT> {
+    fn default() -> Self {
+        ImageMapping::GrayRanges(Vec::new())
+    }
+}
+
+///! samples a user painted raster at the cell feature point, so artists can paint
+///! rough continents and let worley add the organic cell borders.
+///! the raster itself isn't serialized, only `path`, call `reload()` after deserializing.
+///! until then validate is an Err, so Worley::from_ron_str and the other validating loaders
+///! reject it, while sanitize (e.g. Worley::from_format_str_sanitized) reloads it
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "BiomeT: Serialize",
+        deserialize = "BiomeT: Deserialize<'de>"
+    ))
+)]
+pub struct ImageMaskPicker<BiomeT> {
+    ///! where the raster was loaded from
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub path: Option<PathBuf>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mapping: ImageMapping<BiomeT>,
+    ///! used for cells outside the image (and when no raster is loaded)
+    pub outside: BiomeT,
+    ///! how many pixels one cell covers
+    #[cfg_attr(feature = "serde", serde(default = "default_pixels_per_cell"))]
+    pub pixels_per_cell: f64,
+    ///! cell position of the image's top left corner
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub origin: (f64, f64),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    width: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    height: u32,
+    ///! rgb8 pixels, row major
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pixels: Vec<u8>,
+    // hash of width, height and pixels, kept by set_image so config_hash doesn't rehash the raster
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pixels_hash: u64,
+}
+
+#[cfg(feature = "serde")]
+fn default_pixels_per_cell() -> f64 {
+    1.0
+}
+
+impl<BiomeT: Default> Default for ImageMaskPicker<BiomeT> {
+    fn default() -> Self {
+        Self {
+            path: None,
+            mapping: ImageMapping::default(),
+            outside: BiomeT::default(),
+            pixels_per_cell: 1.0,
+            origin: (0.0, 0.0),
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+            pixels_hash: 0,
+        }
+    }
+}
+
+impl<BiomeT: Copy> ImageMaskPicker<BiomeT> {
+    pub fn from_path(
+        path: impl AsRef<Path>,
+        mapping: ImageMapping<BiomeT>,
+        outside: BiomeT,
+    ) -> Result<Self, ImageError> {
+        let mut picker = Self {
+            path: Some(path.as_ref().to_path_buf()),
+            mapping,
+            outside,
+            pixels_per_cell: 1.0,
+            origin: (0.0, 0.0),
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+            pixels_hash: 0,
+        };
+        picker.reload()?;
+        Ok(picker)
+    }
+
+    ///! replace the raster, keeps `path` as is
+    pub fn set_image(&mut self, image: &DynamicImage) {
+        let rgb = image.to_rgb8();
+        self.width = rgb.width();
+        self.height = rgb.height();
+        self.pixels = rgb.into_raw();
+        let mut h = fxhash::FxHasher64::default();
+        (self.width, self.height).hash(&mut h);
+        self.pixels.hash(&mut h);
+        self.pixels_hash = h.finish();
+    }
+
+    ///! (re)load the raster from `path`, does nothing without a path
+    pub fn reload(&mut self) -> Result<(), ImageError> {
+        if let Some(path) = &self.path {
+            let image = image::open(path)?;
+            self.set_image(&image);
+        }
+        Ok(())
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        !self.pixels.is_empty()
+    }
+
+    fn pixel(&self, px: i64, pz: i64) -> Option<[u8; 3]> {
+        if px < 0 || pz < 0 || px >= self.width as i64 || pz >= self.height as i64 {
+            return None;
+        }
+        let i = (pz as usize * self.width as usize + px as usize) * 3;
+        Some([self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]])
+    }
+
+    fn map_pixel(&self, rgb: [u8; 3]) -> BiomeT {
+        match &self.mapping {
+            ImageMapping::GrayRanges(ranges) => {
+                // rec. 601 luma
+                let luma = (0.299 * rgb[0] as f32 + 0.587 * rgb[1] as f32 + 0.114 * rgb[2] as f32)
+                    .round() as u8;
+                ranges
+                    .iter()
+                    .find(|(upper, _biome)| luma <= *upper)
+                    .or(ranges.last())
+                    .map_or(self.outside, |(_upper, biome)| *biome)
+            }
+            ImageMapping::Palette(palette) => palette
+                .iter()
+                .min_by_key(|(color, _biome)| {
+                    color
+                        .iter()
+                        .zip(rgb.iter())
+                        .map(|(a, b)| (*a as i32 - *b as i32).pow(2))
+                        .sum::<i32>()
+                })
+                .map_or(self.outside, |(_color, biome)| *biome),
+        }
+    }
+}
+
+impl<BiomeT: Copy> BiomePicker<BiomeT> for ImageMaskPicker<BiomeT> {
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        let (fx, fz) = cell_point(seed, cell_x, cell_z);
+        let px = ((fx - self.origin.0) * self.pixels_per_cell).floor() as i64;
+        let pz = ((fz - self.origin.1) * self.pixels_per_cell).floor() as i64;
+        self.pixel(px, pz)
+            .map_or(self.outside, |rgb| self.map_pixel(rgb))
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.path.hash(&mut h);
+        match &self.mapping {
+            ImageMapping::GrayRanges(ranges) => {
+                0u8.hash(&mut h);
+                for (upper, biome) in ranges {
+                    upper.hash(&mut h);
+                    hash_biome(biome, &mut h);
+                }
+            }
+            ImageMapping::Palette(palette) => {
+                1u8.hash(&mut h);
+                for (color, biome) in palette {
+                    color.hash(&mut h);
+                    hash_biome(biome, &mut h);
+                }
+            }
+        }
+        hash_biome(&self.outside, &mut h);
+        self.pixels_per_cell.to_bits().hash(&mut h);
+        self.origin.0.to_bits().hash(&mut h);
+        self.origin.1.to_bits().hash(&mut h);
+        self.pixels_hash.hash(&mut h);
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(path) = &self.path
+            && !self.is_loaded()
+        {
+            return Err(format!("raster {:?} isn't loaded, call reload()", path));
+        }
+        match self.pixels_per_cell.is_finite() && self.pixels_per_cell > 0.0 {
+            true => Ok(()),
+            false => Err(format!(
+                "pixels_per_cell must be > 0, is {}",
+                self.pixels_per_cell
+            )),
+        }
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Some(path) = self.path.clone()
+            && !self.is_loaded()
+        {
+            warnings.push(match self.reload() {
+                Ok(()) => format!("raster reloaded from {:?}", path),
+                Err(err) => format!("raster {:?} failed to reload: {}", path, err),
+            });
+        }
+        if !(self.pixels_per_cell.is_finite() && self.pixels_per_cell > 0.0) {
+            warnings.push(format!(
+                "pixels_per_cell {} reset to 1",
+                self.pixels_per_cell
+            ));
+            self.pixels_per_cell = 1.0;
+        }
+        warnings
+    }
+}
@@ -0,0 +1,124 @@
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use bracket_fast_noise::prelude::FastNoise;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::biome_picker::{BiomePicker, sanitize_field, validate_field};
+use crate::utils::cell_point;
+use crate::warp::hash_noise;
+
+///! splits cells into water and land by sampling a continentalness noise
+///! at the cell feature point, then delegates to the matching inner picker.
+///! sampling at the feature point (not the sample position) keeps the split per-cell deterministic
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "Water: Serialize, Land: Serialize",
+        deserialize = "Water: Deserialize<'de>, Land: Deserialize<'de>"
+    ))
+)]
+pub struct LandMaskPicker<BiomeT, Water, Land>
+where
+    Water: BiomePicker<BiomeT>,
+    Land: BiomePicker<BiomeT>,
+{
+    ///! used for cells below sea_level
+    pub water: Water,
+    ///! used for cells at or above sea_level
+    pub land: Land,
+    ///! sampled in cell space, so frequency is relative to cell size
+    #[cfg_attr(feature = "serde", serde(default = "default_continentalness"))]
+    pub continentalness: FastNoise,
+    ///! noise threshold, roughly -1.0 -> 1.0. higher value: more water
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sea_level: f32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _phantom: PhantomData<BiomeT>,
+}
+
+fn default_continentalness() -> FastNoise {
+    let mut continentalness = FastNoise::default();
+    continentalness.frequency = 0.1;
+    continentalness
+}
+
+impl<BiomeT, Water, Land> Default for LandMaskPicker<BiomeT, Water, Land>
+where
+    Water: BiomePicker<BiomeT> + Default,
+    Land: BiomePicker<BiomeT> + Default,
+{
+    fn default() -> Self {
+        Self {
+            water: Water::default(),
+            land: Land::default(),
+            continentalness: default_continentalness(),
+            sea_level: 0.0,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<BiomeT, Water, Land> LandMaskPicker<BiomeT, Water, Land>
+where
+    Water: BiomePicker<BiomeT>,
+    Land: BiomePicker<BiomeT>,
+{
+    ///! true if the cell is classified as water
+    pub fn is_water(&self, seed: u64, cell_x: i32, cell_z: i32) -> bool {
+        let (fx, fz) = cell_point(seed, cell_x, cell_z);
+        self.continentalness.get_noise(fx as f32, fz as f32) < self.sea_level
+    }
+}
+
+impl<BiomeT, Water, Land> BiomePicker<BiomeT> for LandMaskPicker<BiomeT, Water, Land>
+where
+    Water: BiomePicker<BiomeT>,
+    Land: BiomePicker<BiomeT>,
+{
+    #[inline(always)]
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        match self.is_water(seed, cell_x, cell_z) {
+            true => self.water.pick_biome(seed, cell_x, cell_z),
+            false => self.land.pick_biome(seed, cell_x, cell_z),
+        }
+    }
+
+    #[inline(always)]
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        match self.is_water(seed, cell_x, cell_z) {
+            true => self.water.try_pick_biome(seed, cell_x, cell_z),
+            false => self.land.try_pick_biome(seed, cell_x, cell_z),
+        }
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.water.config_hash().hash(&mut h);
+        self.land.config_hash().hash(&mut h);
+        hash_noise(&self.continentalness, &mut h);
+        self.sea_level.to_bits().hash(&mut h);
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !self.sea_level.is_finite() {
+            return Err(format!("sea_level must be finite, is {}", self.sea_level));
+        }
+        validate_field("water", &self.water)?;
+        validate_field("land", &self.land)
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !self.sea_level.is_finite() {
+            warnings.push(format!("sea_level {} reset to 0", self.sea_level));
+            self.sea_level = 0.0;
+        }
+        sanitize_field("water", &mut self.water, &mut warnings);
+        sanitize_field("land", &mut self.land, &mut warnings);
+        warnings
+    }
+}
@@ -0,0 +1,192 @@
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::biome_picker::{
+    BiomePicker, WEIGHT_SUM_TOLERANCE, hash_biome, hash_weights, sanitize_field, validate_field,
+};
+use crate::utils::{derive_seed, hash_f32, seed_domain};
+
+///! which neighbor a cell inherits its biome from
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub enum ParentDirection {
+    ///! the cell at (x - 1, z)
+    #[default]
+    West,
+    ///! the cell at (x, z - 1)
+    North,
+}
+
+impl ParentDirection {
+    ///! the cell at position `pos` along the chain axis passing through (cell_x, cell_z)
+    #[inline(always)]
+    fn cell_at(&self, cell_x: i32, cell_z: i32, pos: i32) -> (i32, i32) {
+        match self {
+            ParentDirection::West => (pos, cell_z),
+            ParentDirection::North => (cell_x, pos),
+        }
+    }
+}
+
+///! picks a cell's biome from a transition table, conditioned on the biome of its parent cell.
+///! chains restart every `chain_length` cells at an anchor cell picked by `anchor`,
+///! so a cell only ever walks back at most chain_length cells, and every cell of a chain agrees on its history.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "BiomeT: Serialize, Anchor: Serialize",
+        deserialize = "BiomeT: Deserialize<'de>, Anchor: Deserialize<'de>"
+    ))
+)]
+pub struct MarkovPicker<BiomeT, Anchor>
+where
+    Anchor: BiomePicker<BiomeT>,
+{
+    ///! picks the first biome of each chain
+    pub anchor: Anchor,
+    ///! (from, [(to, weight)]), weights per row should sum to 1.0.
+    ///! biomes without a row never change
+    pub transitions: Vec<(BiomeT, Vec<(BiomeT, f32)>)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub direction: ParentDirection,
+    ///! cells per chain, longer chains: longer progressions but more work per pick
+    #[cfg_attr(feature = "serde", serde(default = "default_chain_length"))]
+    pub chain_length: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _phantom: PhantomData<BiomeT>,
+}
+
+#[cfg(feature = "serde")]
+fn default_chain_length() -> u32 {
+    8
+}
+
+impl<BiomeT, Anchor> Default for MarkovPicker<BiomeT, Anchor>
+where
+    Anchor: BiomePicker<BiomeT> + Default,
+{
+    fn default() -> Self {
+        Self {
+            anchor: Anchor::default(),
+            transitions: Vec::new(),
+            direction: ParentDirection::default(),
+            chain_length: 8,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<BiomeT, Anchor> MarkovPicker<BiomeT, Anchor>
+where
+    BiomeT: Copy + PartialEq,
+    Anchor: BiomePicker<BiomeT>,
+{
+    fn transition(&self, from: BiomeT, roll: f32) -> BiomeT {
+        let Some((_from, row)) = self.transitions.iter().find(|(f, _row)| *f == from) else {
+            return from;
+        };
+        let mut cumulative = 0.0;
+        for (to, weight) in row {
+            cumulative += weight;
+            if roll < cumulative {
+                return *to;
+            }
+        }
+        // weights summing below 1.0 leave the remainder to "stay the same"
+        from
+    }
+}
+
+impl<BiomeT, Anchor> BiomePicker<BiomeT> for MarkovPicker<BiomeT, Anchor>
+where
+    BiomeT: Copy + PartialEq,
+    Anchor: BiomePicker<BiomeT>,
+{
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        let chain_length = self.chain_length.max(1) as i32;
+        let pos = match self.direction {
+            ParentDirection::West => cell_x,
+            ParentDirection::North => cell_z,
+        };
+        let anchor_pos = pos.div_euclid(chain_length) * chain_length;
+
+        let (ax, az) = self.direction.cell_at(cell_x, cell_z, anchor_pos);
+        let mut biome = self.anchor.pick_biome(seed, ax, az);
+        let transition_seed = derive_seed(seed, seed_domain::MARKOV);
+        for p in anchor_pos + 1..=pos {
+            let (cx, cz) = self.direction.cell_at(cell_x, cell_z, p);
+            biome = self.transition(biome, hash_f32(transition_seed, cx, cz));
+        }
+        biome
+    }
+
+    ///! a cell is empty where the anchor picker leaves it empty, the others follow their chain
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        self.anchor.try_pick_biome(seed, cell_x, cell_z)?;
+        Some(self.pick_biome(seed, cell_x, cell_z))
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.anchor.config_hash().hash(&mut h);
+        for (from, row) in &self.transitions {
+            hash_biome(from, &mut h);
+            row.len().hash(&mut h);
+            hash_weights(row, &mut h);
+        }
+        self.direction.hash(&mut h);
+        self.chain_length.hash(&mut h);
+        h.finish()
+    }
+
+    ///! rows may sum below 1 (the rest stays the same biome), not above
+    fn validate(&self) -> Result<(), String> {
+        for (i, (_from, row)) in self.transitions.iter().enumerate() {
+            if let Some((_to, w)) = row.iter().find(|(_to, w)| !(w.is_finite() && *w >= 0.0)) {
+                return Err(format!(
+                    "transitions[{}]: weights must be finite and >= 0, found {}",
+                    i, w
+                ));
+            }
+            let sum: f32 = row.iter().map(|(_to, w)| w).sum();
+            if sum > 1.0 + WEIGHT_SUM_TOLERANCE {
+                return Err(format!(
+                    "transitions[{}]: weights must sum to <= 1, sum to {}",
+                    i, sum
+                ));
+            }
+        }
+        validate_field("anchor", &self.anchor)
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (i, (_from, row)) in self.transitions.iter_mut().enumerate() {
+            let len = row.len();
+            row.retain(|(_to, w)| w.is_finite() && *w >= 0.0);
+            if row.len() != len {
+                warnings.push(format!(
+                    "transitions[{}]: dropped {} weights that were negative or not finite",
+                    i,
+                    len - row.len()
+                ));
+            }
+            let sum: f32 = row.iter().map(|(_to, w)| w).sum();
+            if sum > 1.0 + WEIGHT_SUM_TOLERANCE {
+                warnings.push(format!(
+                    "transitions[{}]: weights summed to {}, normalized to 1",
+                    i, sum
+                ));
+                for (_to, w) in row.iter_mut() {
+                    *w /= sum;
+                }
+            }
+        }
+        sanitize_field("anchor", &mut self.anchor, &mut warnings);
+        warnings
+    }
+}
@@ -0,0 +1,151 @@
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use bracket_fast_noise::prelude::FastNoise;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::biome_picker::{BiomePicker, sanitize_field, validate_field};
+use crate::utils::{cell_point, derive_seed, hash_f32, seed_domain};
+use crate::warp::hash_noise;
+
+///! replaces picker `a` with picker `b` inside blobs defined by a noise mask,
+///! e.g. "corrupted biomes" spreading through the normal world.
+///! the mask is evaluated at the cell feature point, so results don't depend on chunk/sample position
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "A: Serialize, B: Serialize",
+        deserialize = "A: Deserialize<'de>, B: Deserialize<'de>"
+    ))
+)]
+pub struct MaskBlendPicker<BiomeT, A, B>
+where
+    A: BiomePicker<BiomeT>,
+    B: BiomePicker<BiomeT>,
+{
+    pub a: A,
+    pub b: B,
+    ///! sampled in cell space, so frequency is relative to cell size
+    #[cfg_attr(feature = "serde", serde(default = "default_mask_noise"))]
+    pub mask_noise: FastNoise,
+    ///! mask values above this use `b`
+    #[cfg_attr(feature = "serde", serde(default = "default_threshold"))]
+    pub threshold: f32,
+    ///! width of the transition band around threshold. inside it, cells randomly
+    ///! pick `b` with a chance rising from 0 to 1, so blob borders dissolve gradually
+    #[cfg_attr(feature = "serde", serde(default = "default_softness"))]
+    pub softness: f32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _phantom: PhantomData<BiomeT>,
+}
+
+fn default_mask_noise() -> FastNoise {
+    let mut mask_noise = FastNoise::default();
+    mask_noise.frequency = 0.15;
+    mask_noise
+}
+
+#[cfg(feature = "serde")]
+fn default_threshold() -> f32 {
+    0.3
+}
+
+#[cfg(feature = "serde")]
+fn default_softness() -> f32 {
+    0.1
+}
+
+impl<BiomeT, A, B> Default for MaskBlendPicker<BiomeT, A, B>
+where
+    A: BiomePicker<BiomeT> + Default,
+    B: BiomePicker<BiomeT> + Default,
+{
+    fn default() -> Self {
+        Self {
+            a: A::default(),
+            b: B::default(),
+            mask_noise: default_mask_noise(),
+            threshold: 0.3,
+            softness: 0.1,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<BiomeT, A, B> MaskBlendPicker<BiomeT, A, B>
+where
+    A: BiomePicker<BiomeT>,
+    B: BiomePicker<BiomeT>,
+{
+    ///! true if the cell uses picker `b`
+    pub fn uses_b(&self, seed: u64, cell_x: i32, cell_z: i32) -> bool {
+        let (fx, fz) = cell_point(seed, cell_x, cell_z);
+        let mask = self.mask_noise.get_noise(fx as f32, fz as f32);
+        if self.softness <= 0.0 {
+            return mask > self.threshold;
+        }
+        // chance of b: 0 at (threshold - softness/2) -> 1 at (threshold + softness/2)
+        let chance = ((mask - self.threshold) / self.softness + 0.5).clamp(0.0, 1.0);
+        hash_f32(derive_seed(seed, seed_domain::MASK_BLEND), cell_x, cell_z) < chance
+    }
+}
+
+impl<BiomeT, A, B> BiomePicker<BiomeT> for MaskBlendPicker<BiomeT, A, B>
+where
+    A: BiomePicker<BiomeT>,
+    B: BiomePicker<BiomeT>,
+{
+    #[inline(always)]
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        match self.uses_b(seed, cell_x, cell_z) {
+            true => self.b.pick_biome(seed, cell_x, cell_z),
+            false => self.a.pick_biome(seed, cell_x, cell_z),
+        }
+    }
+
+    #[inline(always)]
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        match self.uses_b(seed, cell_x, cell_z) {
+            true => self.b.try_pick_biome(seed, cell_x, cell_z),
+            false => self.a.try_pick_biome(seed, cell_x, cell_z),
+        }
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.a.config_hash().hash(&mut h);
+        self.b.config_hash().hash(&mut h);
+        hash_noise(&self.mask_noise, &mut h);
+        self.threshold.to_bits().hash(&mut h);
+        self.softness.to_bits().hash(&mut h);
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !self.threshold.is_finite() {
+            return Err(format!("threshold must be finite, is {}", self.threshold));
+        }
+        if !(self.softness.is_finite() && self.softness >= 0.0) {
+            return Err(format!("softness must be >= 0, is {}", self.softness));
+        }
+        validate_field("a", &self.a)?;
+        validate_field("b", &self.b)
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !self.threshold.is_finite() {
+            warnings.push(format!("threshold {} reset to 0.3", self.threshold));
+            self.threshold = 0.3;
+        }
+        if !(self.softness.is_finite() && self.softness >= 0.0) {
+            warnings.push(format!("softness {} reset to 0.1", self.softness));
+            self.softness = 0.1;
+        }
+        sanitize_field("a", &mut self.a, &mut warnings);
+        sanitize_field("b", &mut self.b, &mut warnings);
+        warnings
+    }
+}
@@ -0,0 +1,111 @@
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::biome_picker::{
+    BiomePicker, hash_biome, sanitize_chance, sanitize_field, validate_chance, validate_field,
+};
+use crate::utils::{derive_seed, hash_f32, seed_domain};
+
+///! with a small per-cell chance, replaces the picked biome with its mutated counterpart
+///! (Forest -> HauntedForest). biomes without a mutation are never replaced
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "BiomeT: Serialize, Inner: Serialize",
+        deserialize = "BiomeT: Deserialize<'de>, Inner: Deserialize<'de>"
+    ))
+)]
+pub struct MutationPicker<BiomeT, Inner>
+where
+    Inner: BiomePicker<BiomeT>,
+{
+    pub inner: Inner,
+    ///! (normal, mutated)
+    pub mutations: Vec<(BiomeT, BiomeT)>,
+    ///! chance of a cell mutating, 0.0 -> 1.0
+    #[cfg_attr(feature = "serde", serde(default = "default_chance"))]
+    pub chance: f32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _phantom: PhantomData<BiomeT>,
+}
+
+#[cfg(feature = "serde")]
+fn default_chance() -> f32 {
+    0.05
+}
+
+impl<BiomeT, Inner> Default for MutationPicker<BiomeT, Inner>
+where
+    Inner: BiomePicker<BiomeT> + Default,
+{
+    fn default() -> Self {
+        Self {
+            inner: Inner::default(),
+            mutations: Vec::new(),
+            chance: 0.05,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<BiomeT, Inner> MutationPicker<BiomeT, Inner>
+where
+    BiomeT: Copy + PartialEq,
+    Inner: BiomePicker<BiomeT>,
+{
+    fn mutate(&self, seed: u64, cell_x: i32, cell_z: i32, biome: BiomeT) -> BiomeT {
+        let Some((_normal, mutated)) = self.mutations.iter().find(|(normal, _m)| *normal == biome)
+        else {
+            return biome;
+        };
+        match hash_f32(derive_seed(seed, seed_domain::MUTATION), cell_x, cell_z) < self.chance {
+            true => *mutated,
+            false => biome,
+        }
+    }
+}
+
+impl<BiomeT, Inner> BiomePicker<BiomeT> for MutationPicker<BiomeT, Inner>
+where
+    BiomeT: Copy + PartialEq,
+    Inner: BiomePicker<BiomeT>,
+{
+    #[inline(always)]
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        let biome = self.inner.pick_biome(seed, cell_x, cell_z);
+        self.mutate(seed, cell_x, cell_z, biome)
+    }
+
+    #[inline(always)]
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        let biome = self.inner.try_pick_biome(seed, cell_x, cell_z)?;
+        Some(self.mutate(seed, cell_x, cell_z, biome))
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.inner.config_hash().hash(&mut h);
+        for (normal, mutated) in &self.mutations {
+            hash_biome(normal, &mut h);
+            hash_biome(mutated, &mut h);
+        }
+        self.chance.to_bits().hash(&mut h);
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_chance("chance", self.chance)?;
+        validate_field("inner", &self.inner)
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        sanitize_chance("chance", &mut self.chance, 0.05, &mut warnings);
+        sanitize_field("inner", &mut self.inner, &mut warnings);
+        warnings
+    }
+}
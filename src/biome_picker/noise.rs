@@ -0,0 +1,104 @@
+use std::hash::{Hash, Hasher};
+
+use bracket_fast_noise::prelude::FastNoise;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::biome_picker::{BiomePicker, hash_biome};
+use crate::utils::cell_point;
+use crate::warp::hash_noise;
+
+///! maps a noise value, sampled at the cell feature point, to biomes by value ranges.
+///! e.g. bands: [(-0.3, Ocean), (0.2, Plains)], above: Mountains
+///! gives < -0.3 Ocean, < 0.2 Plains, else Mountains. large scale coherent biome bands
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "BiomeT: Serialize",
+        deserialize = "BiomeT: Deserialize<'de>"
+    ))
+)]
+pub struct NoisePicker<BiomeT> {
+    ///! sampled in cell space, so frequency is relative to cell size
+    #[cfg_attr(feature = "serde", serde(default = "default_noise"))]
+    pub noise: FastNoise,
+    ///! (upper bound, biome), sorted by ascending upper bound
+    pub bands: Vec<(f32, BiomeT)>,
+    ///! used for values above every band
+    pub above: BiomeT,
+}
+
+fn default_noise() -> FastNoise {
+    let mut noise = FastNoise::default();
+    noise.frequency = 0.1;
+    noise
+}
+
+impl<BiomeT: Default> Default for NoisePicker<BiomeT> {
+    fn default() -> Self {
+        Self {
+            noise: default_noise(),
+            bands: Vec::new(),
+            above: BiomeT::default(),
+        }
+    }
+}
+
+impl<BiomeT> NoisePicker<BiomeT> {
+    ///! the noise value a cell is classified by
+    pub fn cell_value(&self, seed: u64, cell_x: i32, cell_z: i32) -> f32 {
+        let (fx, fz) = cell_point(seed, cell_x, cell_z);
+        self.noise.get_noise(fx as f32, fz as f32)
+    }
+}
+
+impl<BiomeT: Copy> BiomePicker<BiomeT> for NoisePicker<BiomeT> {
+    #[inline(always)]
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        let value = self.cell_value(seed, cell_x, cell_z);
+        self.bands
+            .iter()
+            .find(|(upper, _biome)| value < *upper)
+            .map_or(self.above, |(_upper, biome)| *biome)
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        hash_noise(&self.noise, &mut h);
+        for (upper, biome) in &self.bands {
+            upper.to_bits().hash(&mut h);
+            hash_biome(biome, &mut h);
+        }
+        hash_biome(&self.above, &mut h);
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some((upper, _biome)) = self.bands.iter().find(|(upper, _biome)| upper.is_nan()) {
+            return Err(format!("band bounds can't be NaN, found {}", upper));
+        }
+        match self.bands.is_sorted_by(|a, b| a.0 <= b.0) {
+            true => Ok(()),
+            false => Err("bands must be sorted by ascending upper bound".to_string()),
+        }
+    }
+
+    ///! drops NaN bounds and sorts the bands
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let len = self.bands.len();
+        self.bands.retain(|(upper, _biome)| !upper.is_nan());
+        if self.bands.len() != len {
+            warnings.push(format!(
+                "dropped {} NaN band bounds",
+                len - self.bands.len()
+            ));
+        }
+        if !self.bands.is_sorted_by(|a, b| a.0 <= b.0) {
+            warnings.push("bands sorted by ascending upper bound".to_string());
+            self.bands.sort_by(|a, b| a.0.total_cmp(&b.0));
+        }
+        warnings
+    }
+}
@@ -0,0 +1,273 @@
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::biome_picker::{BiomePicker, hash_biome, sanitize_field, validate_field};
+use crate::utils::{derive_seed, hash_u64, seed_domain};
+
+// rerolls for single-size cells before accepting a multi-cell biome
+const MAX_REROLLS: i32 = 4;
+
+// coordinate offset of a block or reroll draw, so each draw is independent of the others while
+// inner still gets the world seed. within +-2^19 cells
+fn draw_offset(size_seed: u64, draw: i32, kind: i32) -> (i32, i32) {
+    let h = hash_u64(size_seed, draw, kind);
+    ((h as i32) >> 12, ((h >> 32) as i32) >> 12)
+}
+
+///! makes some biomes systematically larger than others.
+///! each biome gets a size class in cells (default 1). cells are grouped in blocks of
+///! size x size, and a block that picks a biome of that size assigns it to all its cells,
+///! which then merge into one big region. e.g. Ocean: 3 gives 3x3 cell oceans
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(
+        from = "SizedPickerFile<BiomeT, Inner>",
+        bound(
+            serialize = "BiomeT: Serialize, Inner: Serialize",
+            deserialize = "BiomeT: Deserialize<'de>, Inner: Deserialize<'de>"
+        )
+    )
+)]
+pub struct SizedPicker<BiomeT, Inner>
+where
+    Inner: BiomePicker<BiomeT>,
+{
+    pub inner: Inner,
+    ///! (biome, size in cells), unlisted biomes are size 1. call rebuild() after editing it
+    pub sizes: Vec<(BiomeT, u32)>,
+    // the distinct sizes above 1, largest first, derived from sizes
+    #[cfg_attr(feature = "serde", serde(skip))]
+    size_classes: Vec<u32>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _phantom: PhantomData<BiomeT>,
+}
+
+// what SizedPicker loads from, size_classes is derived from sizes
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(
+    rename = "SizedPicker",
+    bound(deserialize = "BiomeT: Deserialize<'de>, Inner: Deserialize<'de>")
+)]
+struct SizedPickerFile<BiomeT, Inner> {
+    inner: Inner,
+    sizes: Vec<(BiomeT, u32)>,
+}
+
+#[cfg(feature = "serde")]
+impl<BiomeT, Inner> From<SizedPickerFile<BiomeT, Inner>> for SizedPicker<BiomeT, Inner>
+where
+    Inner: BiomePicker<BiomeT>,
+{
+    fn from(file: SizedPickerFile<BiomeT, Inner>) -> Self {
+        Self::new(file.inner, file.sizes)
+    }
+}
+
+impl<BiomeT, Inner> Default for SizedPicker<BiomeT, Inner>
+where
+    Inner: BiomePicker<BiomeT> + Default,
+{
+    fn default() -> Self {
+        Self {
+            inner: Inner::default(),
+            sizes: Vec::new(),
+            size_classes: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<BiomeT, Inner> SizedPicker<BiomeT, Inner>
+where
+    Inner: BiomePicker<BiomeT>,
+{
+    pub fn new(inner: Inner, sizes: Vec<(BiomeT, u32)>) -> Self {
+        let mut picker = Self {
+            inner,
+            sizes,
+            size_classes: Vec::new(),
+            _phantom: PhantomData,
+        };
+        picker.rebuild();
+        picker
+    }
+
+    ///! rebuild the size classes picking walks, call after editing sizes
+    pub fn rebuild(&mut self) {
+        // largest size class first, so big biomes claim their blocks before smaller ones
+        self.size_classes = self
+            .sizes
+            .iter()
+            .map(|(_b, size)| *size)
+            .filter(|size| *size > 1)
+            .collect();
+        self.size_classes.sort_unstable_by(|a, b| b.cmp(a));
+        self.size_classes.dedup();
+    }
+
+    // sizes edited without rebuild()
+    fn stale(&self) -> bool {
+        let listed = |class: &u32| self.sizes.iter().any(|(_b, size)| size == class);
+        self.sizes
+            .iter()
+            .any(|(_b, size)| *size > 1 && !self.size_classes.contains(size))
+            || !self.size_classes.iter().all(listed)
+    }
+}
+
+impl<BiomeT, Inner> SizedPicker<BiomeT, Inner>
+where
+    BiomeT: PartialEq,
+    Inner: BiomePicker<BiomeT>,
+{
+    pub fn size_of(&self, biome: &BiomeT) -> u32 {
+        self.sizes
+            .iter()
+            .find(|(b, _size)| b == biome)
+            .map_or(1, |(_b, size)| (*size).max(1))
+    }
+}
+
+impl<BiomeT, Inner> SizedPicker<BiomeT, Inner>
+where
+    BiomeT: PartialEq,
+    Inner: BiomePicker<BiomeT>,
+{
+    // pick: pick_biome or try_pick_biome of inner, always called with the world seed.
+    // an empty block claims nothing, an empty reroll keeps the previous roll
+    fn pick_with(
+        &self,
+        seed: u64,
+        cell_x: i32,
+        cell_z: i32,
+        pick: impl Fn(u64, i32, i32) -> Option<BiomeT>,
+    ) -> Option<BiomeT> {
+        let size_seed = derive_seed(seed, seed_domain::SIZE_CLASS);
+        for &size in &self.size_classes {
+            let s = size as i32;
+            let (ox, oz) = draw_offset(size_seed, s, 0);
+            let biome = pick(
+                seed,
+                cell_x.div_euclid(s).wrapping_add(ox),
+                cell_z.div_euclid(s).wrapping_add(oz),
+            );
+            if let Some(biome) = biome
+                && self.size_of(&biome) == size
+            {
+                return Some(biome);
+            }
+        }
+
+        // no block claimed this cell, it needs a single-cell biome
+        let mut biome = pick(seed, cell_x, cell_z)?;
+        let mut reroll = 0;
+        while self.size_of(&biome) > 1 && reroll < MAX_REROLLS {
+            reroll += 1;
+            let (ox, oz) = draw_offset(size_seed, reroll, 1);
+            let Some(next) = pick(seed, cell_x.wrapping_add(ox), cell_z.wrapping_add(oz)) else {
+                break;
+            };
+            biome = next;
+        }
+        Some(biome)
+    }
+}
+
+impl<BiomeT, Inner> BiomePicker<BiomeT> for SizedPicker<BiomeT, Inner>
+where
+    BiomeT: PartialEq,
+    Inner: BiomePicker<BiomeT>,
+{
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        self.pick_with(seed, cell_x, cell_z, |seed, x, z| {
+            Some(self.inner.pick_biome(seed, x, z))
+        })
+        .expect("pick_biome always picks")
+    }
+
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        self.pick_with(seed, cell_x, cell_z, |seed, x, z| {
+            self.inner.try_pick_biome(seed, x, z)
+        })
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.inner.config_hash().hash(&mut h);
+        for (biome, size) in &self.sizes {
+            hash_biome(biome, &mut h);
+            size.hash(&mut h);
+        }
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.stale() {
+            return Err("sizes changed without rebuild()".to_string());
+        }
+        validate_field("inner", &self.inner)
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.stale() {
+            warnings.push("size classes rebuilt from sizes".to_string());
+            self.rebuild();
+        }
+        sanitize_field("inner", &mut self.inner, &mut warnings);
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biome_picker::{BiomeVariants, SimpleBiomePicker, SubsetPicker};
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    enum TestBiome {
+        #[default]
+        Plains,
+        Forest,
+    }
+
+    impl BiomeVariants for TestBiome {
+        fn variants() -> &'static [Self] {
+            &[TestBiome::Plains, TestBiome::Forest]
+        }
+    }
+
+    // inner is picked with the world seed, so a nested subset is the world's subset everywhere
+    #[test]
+    fn nested_subset_sees_the_world_seed() {
+        let subset = SubsetPicker::new(SimpleBiomePicker::Any, 1, Vec::new());
+        let picker = SizedPicker::new(subset, vec![(TestBiome::Forest, 3)]);
+        for seed in [0, 7, u64::MAX] {
+            let world_subset = picker.inner.subset(seed);
+            for x in -16..16 {
+                for z in -16..16 {
+                    assert_eq!(picker.pick_biome(seed, x, z), world_subset[0]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sized_classes_follow_sizes() {
+        let mut picker = SizedPicker::new(SimpleBiomePicker::Any, vec![(TestBiome::Forest, 3)]);
+        assert!(picker.validate().is_ok());
+        picker.sizes.push((TestBiome::Plains, 2));
+        assert!(picker.validate().is_err());
+        assert_eq!(picker.sanitize().len(), 1);
+        assert!(picker.validate().is_ok());
+        picker.sizes.clear();
+        assert!(picker.validate().is_err());
+        picker.rebuild();
+        assert!(picker.validate().is_ok());
+    }
+}
@@ -0,0 +1,128 @@
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::biome_picker::{
+    BiomePicker, sanitize_chance, sanitize_field, validate_chance, validate_field,
+};
+use crate::utils::{derive_seed, hash_f32, seed_domain};
+
+///! only a fraction of cells get a biome from `inner`, the rest are empty,
+///! and Worley::get lets the nearest non-empty cells grow over them.
+///! useful for sparse special regions (oases, ruins) with a normal picker around them
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "Inner: Serialize",
+        deserialize = "Inner: Deserialize<'de>"
+    ))
+)]
+pub struct SparsePicker<BiomeT, Inner>
+where
+    Inner: BiomePicker<BiomeT>,
+{
+    pub inner: Inner,
+    ///! chance of a cell being non-empty, 0.0 -> 1.0
+    #[cfg_attr(feature = "serde", serde(default = "default_density"))]
+    pub density: f32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _phantom: PhantomData<BiomeT>,
+}
+
+#[cfg(feature = "serde")]
+fn default_density() -> f32 {
+    0.5
+}
+
+impl<BiomeT, Inner> Default for SparsePicker<BiomeT, Inner>
+where
+    Inner: BiomePicker<BiomeT> + Default,
+{
+    fn default() -> Self {
+        Self {
+            inner: Inner::default(),
+            density: 0.5,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<BiomeT, Inner> SparsePicker<BiomeT, Inner>
+where
+    Inner: BiomePicker<BiomeT>,
+{
+    pub fn is_empty_cell(&self, seed: u64, cell_x: i32, cell_z: i32) -> bool {
+        hash_f32(derive_seed(seed, seed_domain::SPARSE), cell_x, cell_z) >= self.density
+    }
+}
+
+impl<BiomeT, Inner> BiomePicker<BiomeT> for SparsePicker<BiomeT, Inner>
+where
+    Inner: BiomePicker<BiomeT>,
+{
+    ///! ignores emptiness, for callers that need a biome for every cell
+    #[inline(always)]
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        self.inner.pick_biome(seed, cell_x, cell_z)
+    }
+
+    #[inline(always)]
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        match self.is_empty_cell(seed, cell_x, cell_z) {
+            true => None,
+            false => self.inner.try_pick_biome(seed, cell_x, cell_z),
+        }
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.inner.config_hash().hash(&mut h);
+        self.density.to_bits().hash(&mut h);
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_chance("density", self.density)?;
+        validate_field("inner", &self.inner)
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        sanitize_chance("density", &mut self.density, 0.5, &mut warnings);
+        sanitize_field("inner", &mut self.inner, &mut warnings);
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biome_picker::{BiomeVariants, SimpleBiomePicker};
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    enum TestBiome {
+        #[default]
+        Plains,
+        Forest,
+    }
+
+    impl BiomeVariants for TestBiome {
+        fn variants() -> &'static [Self] {
+            &[TestBiome::Plains, TestBiome::Forest]
+        }
+    }
+
+    #[test]
+    fn density_is_clamped() {
+        let mut picker = SparsePicker::<TestBiome, SimpleBiomePicker<TestBiome>> {
+            density: 1.5,
+            ..Default::default()
+        };
+        assert!(picker.validate().is_err());
+        picker.sanitize();
+        assert_eq!(picker.density, 1.0);
+    }
+}
@@ -0,0 +1,256 @@
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::biome_picker::{BiomePicker, BiomeVariants, hash_biome, sanitize_field, validate_field};
+use crate::utils::{derive_seed, hash_u64, seed_domain};
+
+///! each world seed only gets `count` of the variants (e.g. 6 of 10), so different seeds feel different.
+///! biomes picked by `inner` outside the subset are replaced by a subset member
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(bound(
+        serialize = "BiomeT: Serialize, Inner: Serialize",
+        deserialize = "BiomeT: Deserialize<'de>, Inner: Deserialize<'de>"
+    ))
+)]
+pub struct SubsetPicker<BiomeT, Inner>
+where
+    BiomeT: BiomeVariants,
+    Inner: BiomePicker<BiomeT>,
+{
+    pub inner: Inner,
+    ///! how many variants each world gets. call rebuild() after editing it
+    #[cfg_attr(feature = "serde", serde(default = "default_count"))]
+    pub count: usize,
+    ///! variants to choose the subset from, empty: all BiomeT::variants(). call rebuild() after editing it
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub variants: Vec<BiomeT>,
+    // (seed, subset) of the last seed picked with, replaced when the seed changes
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cache: RwLock<Option<(u64, Arc<[BiomeT]>)>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _phantom: PhantomData<BiomeT>,
+}
+
+#[cfg(feature = "serde")]
+fn default_count() -> usize {
+    4
+}
+
+impl<BiomeT, Inner> Default for SubsetPicker<BiomeT, Inner>
+where
+    BiomeT: BiomeVariants,
+    Inner: BiomePicker<BiomeT> + Default,
+{
+    fn default() -> Self {
+        Self {
+            inner: Inner::default(),
+            count: 4,
+            variants: Vec::new(),
+            cache: RwLock::new(None),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<BiomeT, Inner> SubsetPicker<BiomeT, Inner>
+where
+    BiomeT: BiomeVariants + 'static,
+    Inner: BiomePicker<BiomeT>,
+{
+    pub fn new(inner: Inner, count: usize, variants: Vec<BiomeT>) -> Self {
+        Self {
+            inner,
+            count,
+            variants,
+            cache: RwLock::new(None),
+            _phantom: PhantomData,
+        }
+    }
+
+    ///! drop the cached subset, call after editing count or variants
+    pub fn rebuild(&mut self) {
+        *self.cache.get_mut().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    // the subset of `seed`, shuffled once per seed change (e.g. Worley::set_seed)
+    fn cached_subset(&self, seed: u64) -> Arc<[BiomeT]> {
+        let cache = self.cache.read().unwrap_or_else(|e| e.into_inner());
+        if let Some((cached_seed, subset)) = &*cache
+            && *cached_seed == seed
+        {
+            return subset.clone();
+        }
+        drop(cache);
+        let subset: Arc<[BiomeT]> = self.subset(seed).into();
+        *self.cache.write().unwrap_or_else(|e| e.into_inner()) = Some((seed, subset.clone()));
+        subset
+    }
+
+    // count or variants edited without rebuild()
+    fn stale(&self) -> bool
+    where
+        BiomeT: PartialEq,
+    {
+        self.cache
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_some_and(|(seed, subset)| **subset != *self.subset(*seed))
+    }
+
+    ///! the variants this world seed gets
+    pub fn subset(&self, seed: u64) -> Vec<BiomeT> {
+        let variants = match self.variants.is_empty() {
+            true => BiomeT::variants(),
+            false => &self.variants,
+        };
+        let subset_seed = derive_seed(seed, seed_domain::SUBSET);
+        // seeded shuffle: order variants by a per-seed hash of their index
+        let mut order: Vec<(u64, usize)> = (0..variants.len())
+            .map(|i| (hash_u64(subset_seed, i as i32, 0), i))
+            .collect();
+        order.sort_unstable();
+        order
+            .iter()
+            .take(self.count.max(1))
+            .map(|(_h, i)| variants[*i])
+            .collect()
+    }
+
+    fn restrict(
+        &self,
+        seed: u64,
+        cell_x: i32,
+        cell_z: i32,
+        biome: BiomeT,
+        subset: &[BiomeT],
+    ) -> BiomeT
+    where
+        BiomeT: PartialEq,
+    {
+        if subset.is_empty() || subset.contains(&biome) {
+            return biome;
+        }
+        // separate from the shuffle hashes, which use (index, 0) as coordinates
+        let remap_seed = derive_seed(derive_seed(seed, seed_domain::SUBSET), 1);
+        let h = hash_u64(remap_seed, cell_x, cell_z);
+        subset[(h % subset.len() as u64) as usize]
+    }
+}
+
+impl<BiomeT, Inner> BiomePicker<BiomeT> for SubsetPicker<BiomeT, Inner>
+where
+    BiomeT: BiomeVariants + PartialEq + 'static,
+    Inner: BiomePicker<BiomeT>,
+{
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        let biome = self.inner.pick_biome(seed, cell_x, cell_z);
+        self.restrict(seed, cell_x, cell_z, biome, &self.cached_subset(seed))
+    }
+
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        let biome = self.inner.try_pick_biome(seed, cell_x, cell_z)?;
+        Some(self.restrict(seed, cell_x, cell_z, biome, &self.cached_subset(seed)))
+    }
+
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.inner.config_hash().hash(&mut h);
+        self.count.hash(&mut h);
+        for biome in &self.variants {
+            hash_biome(biome, &mut h);
+        }
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.stale() {
+            return Err("count or variants changed without rebuild()".to_string());
+        }
+        validate_field("inner", &self.inner)
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.stale() {
+            warnings.push("cached subset rebuilt".to_string());
+            self.rebuild();
+        }
+        sanitize_field("inner", &mut self.inner, &mut warnings);
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biome_picker::SimpleBiomePicker;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    enum TestBiome {
+        #[default]
+        Plains,
+        Forest,
+    }
+
+    impl BiomeVariants for TestBiome {
+        fn variants() -> &'static [Self] {
+            &[TestBiome::Plains, TestBiome::Forest]
+        }
+    }
+
+    fn cached_seed<Inner: BiomePicker<TestBiome>>(
+        picker: &SubsetPicker<TestBiome, Inner>,
+    ) -> Option<u64> {
+        picker
+            .cache
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|(seed, _subset)| *seed)
+    }
+
+    #[test]
+    fn cache_follows_the_seed() {
+        let picker = SubsetPicker::new(SimpleBiomePicker::Any, 1, Vec::new());
+        assert_eq!(cached_seed(&picker), None);
+        for seed in [7, 8, 7, u64::MAX] {
+            let subset = picker.subset(seed);
+            for cell in 0..32 {
+                assert_eq!(picker.pick_biome(seed, cell, -cell), subset[0]);
+            }
+            assert_eq!(cached_seed(&picker), Some(seed));
+        }
+    }
+
+    #[test]
+    fn subset_is_cached_per_seed() {
+        let mut picker = SubsetPicker::new(SimpleBiomePicker::Any, 1, Vec::new());
+        let seed = 7;
+        let first = picker.pick_biome(seed, 0, 0);
+        for cell in 1..32 {
+            assert_eq!(picker.pick_biome(seed, cell, -cell), first);
+        }
+        // another seed isn't served the cached subset
+        assert_eq!(
+            picker.pick_biome(seed + 1, 0, 0),
+            picker.subset(seed + 1)[0]
+        );
+
+        let other = match first {
+            TestBiome::Plains => TestBiome::Forest,
+            TestBiome::Forest => TestBiome::Plains,
+        };
+        picker.variants = vec![other];
+        assert!(picker.validate().is_err());
+        picker.rebuild();
+        assert_eq!(picker.pick_biome(seed, 0, 0), other);
+        assert!(picker.validate().is_ok());
+    }
+}
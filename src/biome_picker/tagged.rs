@@ -0,0 +1,235 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{LazyLock, RwLock};
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{DeserializeOwned, DeserializeSeed, Error as _, MapAccess, Visitor},
+    ser::SerializeMap,
+};
+
+use crate::biome_picker::{BiomePicker, BiomeVariants, SimpleBiomePicker};
+
+///! the tag SimpleBiomePicker is saved with, it needs no registration
+pub const SIMPLE_PICKER_TAG: &str = "SimpleBiomePicker";
+
+///! a picker TaggedPicker can hold, implemented for every Serialize + Clone picker
+pub trait SerializablePicker<BiomeT>: BiomePicker<BiomeT> + Send + Sync {
+    fn as_erased(&self) -> &dyn erased_serde::Serialize;
+    fn clone_box(&self) -> Box<dyn SerializablePicker<BiomeT>>;
+}
+
+impl<BiomeT, P> SerializablePicker<BiomeT> for P
+where
+    P: BiomePicker<BiomeT> + Serialize + Clone + Send + Sync + 'static,
+{
+    fn as_erased(&self) -> &dyn erased_serde::Serialize {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn SerializablePicker<BiomeT>> {
+        Box::new(self.clone())
+    }
+}
+
+type DeserializeFn<BiomeT> =
+    for<'de> fn(
+        &mut dyn erased_serde::Deserializer<'de>,
+    ) -> Result<Box<dyn SerializablePicker<BiomeT>>, erased_serde::Error>;
+
+#[derive(Default)]
+struct Registry {
+    // (BiomeT, tag) -> DeserializeFn<BiomeT>
+    by_tag: HashMap<(TypeId, &'static str), Box<dyn Any + Send + Sync>>,
+    // picker type -> tag
+    by_type: HashMap<TypeId, &'static str>,
+}
+
+static REGISTRY: LazyLock<RwLock<Registry>> = LazyLock::new(Default::default);
+
+fn deserialize_boxed<BiomeT, P>(
+    deserializer: &mut dyn erased_serde::Deserializer<'_>,
+) -> Result<Box<dyn SerializablePicker<BiomeT>>, erased_serde::Error>
+where
+    P: SerializablePicker<BiomeT> + DeserializeOwned + 'static,
+{
+    Ok(Box::new(erased_serde::deserialize::<P>(deserializer)?))
+}
+
+///! make P loadable as a TaggedPicker<BiomeT> saved under tag, call at startup before loading.
+///! registering the same tag again replaces it
+pub fn register_picker<BiomeT, P>(tag: &'static str)
+where
+    BiomeT: 'static,
+    P: BiomePicker<BiomeT> + Serialize + DeserializeOwned + Clone + Send + Sync + 'static,
+{
+    let deserialize: DeserializeFn<BiomeT> = deserialize_boxed::<BiomeT, P>;
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    registry
+        .by_tag
+        .insert((TypeId::of::<BiomeT>(), tag), Box::new(deserialize));
+    registry.by_type.insert(TypeId::of::<P>(), tag);
+}
+
+// the registry's &'static tag, and how to deserialize it
+fn registered<BiomeT: 'static>(tag: &str) -> Option<(&'static str, DeserializeFn<BiomeT>)> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry
+        .by_tag
+        .iter()
+        .find(|((biome, t), _)| *biome == TypeId::of::<BiomeT>() && *t == tag)
+        .and_then(|((_, t), f)| Some((*t, *f.downcast_ref::<DeserializeFn<BiomeT>>()?)))
+}
+
+///! a type erased picker that (de)serializes with a type tag, `{"my_picker": (..)}`, so a saved
+///! `Worley<BiomeT, TaggedPicker<BiomeT>>` can switch to any picker registered with register_picker.
+///! like DynPicker, costs a virtual call per candidate cell
+pub struct TaggedPicker<BiomeT> {
+    tag: &'static str,
+    picker: Box<dyn SerializablePicker<BiomeT>>,
+}
+
+impl<BiomeT: BiomeVariants + Serialize + Send + Sync + 'static> TaggedPicker<BiomeT> {
+    ///! None if P isn't registered (SimpleBiomePicker always is)
+    pub fn new<P>(picker: P) -> Option<Self>
+    where
+        P: BiomePicker<BiomeT> + Serialize + Clone + Send + Sync + 'static,
+    {
+        let tag = if TypeId::of::<P>() == TypeId::of::<SimpleBiomePicker<BiomeT>>() {
+            SIMPLE_PICKER_TAG
+        } else {
+            *REGISTRY
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .by_type
+                .get(&TypeId::of::<P>())?
+        };
+        Some(Self {
+            tag,
+            picker: Box::new(picker),
+        })
+    }
+
+    pub fn tag(&self) -> &'static str {
+        self.tag
+    }
+}
+
+impl<BiomeT> Clone for TaggedPicker<BiomeT> {
+    fn clone(&self) -> Self {
+        Self {
+            tag: self.tag,
+            picker: self.picker.clone_box(),
+        }
+    }
+}
+
+impl<BiomeT> Default for TaggedPicker<BiomeT>
+where
+    BiomeT: BiomeVariants + Serialize + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self {
+            tag: SIMPLE_PICKER_TAG,
+            picker: Box::new(SimpleBiomePicker::<BiomeT>::Any),
+        }
+    }
+}
+
+impl<BiomeT> BiomePicker<BiomeT> for TaggedPicker<BiomeT> {
+    #[inline(always)]
+    fn pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> BiomeT {
+        self.picker.pick_biome(seed, cell_x, cell_z)
+    }
+
+    #[inline(always)]
+    fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<BiomeT> {
+        self.picker.try_pick_biome(seed, cell_x, cell_z)
+    }
+
+    // pickers keeping the default config_hash still differ by tag
+    fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.tag.hash(&mut h);
+        self.picker.config_hash().hash(&mut h);
+        h.finish()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.picker.validate()
+    }
+
+    fn sanitize(&mut self) -> Vec<String> {
+        self.picker.sanitize()
+    }
+}
+
+struct Erased<'a, BiomeT>(&'a dyn SerializablePicker<BiomeT>);
+
+impl<BiomeT> Serialize for Erased<'_, BiomeT> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        erased_serde::serialize(self.0.as_erased(), serializer)
+    }
+}
+
+impl<BiomeT> Serialize for TaggedPicker<BiomeT> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.tag, &Erased(&*self.picker))?;
+        map.end()
+    }
+}
+
+struct PickerSeed<BiomeT>(DeserializeFn<BiomeT>);
+
+impl<'de, BiomeT> DeserializeSeed<'de> for PickerSeed<BiomeT> {
+    type Value = Box<dyn SerializablePicker<BiomeT>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.0)(&mut erased).map_err(D::Error::custom)
+    }
+}
+
+struct TaggedVisitor<BiomeT>(std::marker::PhantomData<BiomeT>);
+
+impl<'de, BiomeT> Visitor<'de> for TaggedVisitor<BiomeT>
+where
+    BiomeT: BiomeVariants + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    type Value = TaggedPicker<BiomeT>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a map of one registered picker tag to its config")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let Some(tag) = map.next_key::<String>()? else {
+            return Err(A::Error::custom("missing picker tag"));
+        };
+        let (tag, deserialize) = if tag == SIMPLE_PICKER_TAG {
+            let deserialize: DeserializeFn<BiomeT> =
+                deserialize_boxed::<BiomeT, SimpleBiomePicker<BiomeT>>;
+            (SIMPLE_PICKER_TAG, deserialize)
+        } else {
+            registered::<BiomeT>(&tag).ok_or_else(|| {
+                A::Error::custom(format!(
+                    "picker tag {:?} is not registered, see register_picker",
+                    tag
+                ))
+            })?
+        };
+        let picker = map.next_value_seed(PickerSeed(deserialize))?;
+        Ok(TaggedPicker { tag, picker })
+    }
+}
+
+impl<'de, BiomeT> Deserialize<'de> for TaggedPicker<BiomeT>
+where
+    BiomeT: BiomeVariants + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(TaggedVisitor(std::marker::PhantomData))
+    }
+}
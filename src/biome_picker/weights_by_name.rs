@@ -0,0 +1,65 @@
+use std::marker::PhantomData;
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{MapAccess, SeqAccess, Visitor},
+    ser::SerializeMap,
+};
+
+// `#[serde(with = "worley_biomes::biome_picker::weights_by_name")]` for `Vec<(BiomeT, f32)>`:
+// human readable formats get a map keyed by biome, `{Desert: 0.5, Forest: 0.5}` in RON,
+// with a derived BiomeT that is the variant name, so reordering the enum can't shift weights.
+// loading also accepts the older `[(Desert, 0.5), ..]` list, unknown names are an error.
+// binary formats (postcard) keep the list, they can't tell a map from a list
+
+pub fn serialize<BiomeT, S>(weights: &[(BiomeT, f32)], serializer: S) -> Result<S::Ok, S::Error>
+where
+    BiomeT: Serialize,
+    S: Serializer,
+{
+    if !serializer.is_human_readable() {
+        return weights.serialize(serializer);
+    }
+    let mut map = serializer.serialize_map(Some(weights.len()))?;
+    for (biome, weight) in weights {
+        map.serialize_entry(biome, weight)?;
+    }
+    map.end()
+}
+
+pub fn deserialize<'de, BiomeT, D>(deserializer: D) -> Result<Vec<(BiomeT, f32)>, D::Error>
+where
+    BiomeT: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    if !deserializer.is_human_readable() {
+        return Vec::deserialize(deserializer);
+    }
+    deserializer.deserialize_any(WeightsVisitor(PhantomData))
+}
+
+struct WeightsVisitor<BiomeT>(PhantomData<BiomeT>);
+
+impl<'de, BiomeT: Deserialize<'de>> Visitor<'de> for WeightsVisitor<BiomeT> {
+    type Value = Vec<(BiomeT, f32)>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a map of biome to weight, or a list of (biome, weight)")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut weights = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(entry) = map.next_entry()? {
+            weights.push(entry);
+        }
+        Ok(weights)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut weights = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(entry) = seq.next_element()? {
+            weights.push(entry);
+        }
+        Ok(weights)
+    }
+}
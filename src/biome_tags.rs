@@ -0,0 +1,152 @@
+use tinyvec::TinyVec;
+
+use crate::biome_picker::{BiomePicker, BiomeVariants};
+use crate::worley::Worley;
+
+///! built in tag names, reported by BiomeTags::has_tag and Worley::dominant_tag
+pub const TAG_WATER: &str = "water";
+pub const TAG_COLD: &str = "cold";
+
+///! most positions Worley::tag_coverage samples per call
+pub const MAX_COVERAGE_SAMPLES: u64 = 1 << 16;
+
+///! optional categories for biomes, for gameplay that cares about "any water biome"
+///! rather than exact variants
+pub trait BiomeTags {
+    fn is_water(&self) -> bool {
+        false
+    }
+    fn is_cold(&self) -> bool {
+        false
+    }
+    ///! custom tags, on top of the built in "water"/"cold"
+    fn tags(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn has_tag(&self, tag: &str) -> bool {
+        match tag {
+            TAG_WATER if self.is_water() => true,
+            TAG_COLD if self.is_cold() => true,
+            _ => self.tags().contains(&tag),
+        }
+    }
+
+    ///! built in + custom tags
+    fn all_tags(&self) -> TinyVec<[&'static str; 4]> {
+        let mut out = TinyVec::new();
+        if self.is_water() {
+            out.push(TAG_WATER);
+        }
+        if self.is_cold() {
+            out.push(TAG_COLD);
+        }
+        out.extend(self.tags().iter().copied());
+        out
+    }
+}
+
+impl<BiomeT, Picker> Worley<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + BiomeTags + 'static + Default,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    ///! summed weight (0.0 -> 1.0) of biomes with this tag at the position
+    pub fn tag_weight(&self, x: f64, z: f64, tag: &str) -> f64 {
+        self.get(x, z)
+            .iter()
+            .filter(|(_w, biome)| biome.has_tag(tag))
+            .map(|(w, _biome)| w)
+            .sum()
+    }
+
+    ///! the tag with the highest summed weight at the position, None if no biome there has tags
+    pub fn dominant_tag(&self, x: f64, z: f64) -> Option<&'static str> {
+        let mut tag_weights: TinyVec<[(&'static str, f64); 4]> = TinyVec::new();
+        for (w, biome) in self.get(x, z).iter() {
+            for tag in biome.all_tags() {
+                match tag_weights.iter_mut().find(|(t, _w)| *t == tag) {
+                    Some((_t, tag_w)) => *tag_w += w,
+                    None => tag_weights.push((tag, *w)),
+                }
+            }
+        }
+        tag_weights
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(tag, _w)| *tag)
+    }
+
+    ///! fraction (0.0 -> 1.0) of a rect covered by biomes with this tag, sampled every `step` units.
+    ///! at most MAX_COVERAGE_SAMPLES samples, a smaller (or non-positive) step spreads those over the rect.
+    ///! 0.0 for an empty or infinite rect
+    pub fn tag_coverage(&self, min: (f64, f64), max: (f64, f64), step: f64, tag: &str) -> f64 {
+        let (w, h) = (max.0 - min.0, max.1 - min.1);
+        if !(w.is_finite() && h.is_finite() && w > 0.0 && h > 0.0) {
+            return 0.0;
+        }
+        let limit = MAX_COVERAGE_SAMPLES as f64;
+        let samples_along = |extent: f64| match step > 0.0 {
+            true => (extent / step).ceil().clamp(1.0, limit) as u64,
+            false => MAX_COVERAGE_SAMPLES,
+        };
+        let (mut nx, mut nz) = (samples_along(w), samples_along(h));
+        // thin both axes out evenly when the grid holds more than the limit
+        let grid = nx as f64 * nz as f64;
+        if grid > limit {
+            let scale = (limit / grid).sqrt();
+            nx = ((nx as f64 * scale) as u64).max(1);
+            nz = ((nz as f64 * scale) as u64).max(1);
+        }
+        // step itself unless the limit thinned the axis out (f64::max also skips a NaN step)
+        let (sx, sz) = (step.max(w / nx as f64), step.max(h / nz as f64));
+
+        let mut total = 0.0;
+        for iz in 0..nz {
+            for ix in 0..nx {
+                total += self.tag_weight(min.0 + ix as f64 * sx, min.1 + iz as f64 * sz, tag);
+            }
+        }
+        total / (nx * nz) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biome_picker::SimpleBiomePicker;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    enum TestBiome {
+        #[default]
+        Ocean,
+        Plains,
+    }
+
+    impl BiomeVariants for TestBiome {
+        fn variants() -> &'static [Self] {
+            &[TestBiome::Ocean, TestBiome::Plains]
+        }
+    }
+
+    impl BiomeTags for TestBiome {
+        fn is_water(&self) -> bool {
+            *self == TestBiome::Ocean
+        }
+    }
+
+    #[test]
+    fn coverage_with_any_step_finishes() {
+        let worley = Worley::<TestBiome, SimpleBiomePicker<TestBiome>> {
+            biome_picker: SimpleBiomePicker::Single(TestBiome::Ocean),
+            ..Default::default()
+        };
+        let (min, max) = ((0.0, 0.0), (100.0, 100.0));
+        assert_eq!(worley.tag_coverage(min, max, 10.0, TAG_WATER), 1.0);
+        for step in [0.0, -1.0, f64::NAN, 1e-300] {
+            assert_eq!(worley.tag_coverage(min, max, step, TAG_WATER), 1.0);
+        }
+        assert_eq!(worley.tag_coverage(min, max, 10.0, TAG_COLD), 0.0);
+        assert_eq!(worley.tag_coverage(max, min, 10.0, TAG_WATER), 0.0);
+    }
+}
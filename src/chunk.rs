@@ -0,0 +1,120 @@
+use tinyvec::TinyVec;
+
+use crate::biome_picker::{BiomePicker, BiomeVariants};
+use crate::worley::{WarpGrid, Worley};
+
+mod compact;
+pub use compact::{ChunkDecodeError, ChunkEncoding, MAX_COMPACT_SAMPLES};
+
+///! a square grid of Worley::get results.
+///! sample (lx, lz) is taken at world `origin + (lx, lz) * spacing`
+#[derive(Clone, Debug)]
+pub struct BiomeChunk<BiomeT: Default> {
+    ///! chunk coordinate, chunk (cx, cz) starts at world (cx, cz) * size * spacing
+    pub coord: (i32, i32),
+    ///! samples per side
+    pub size: u32,
+    ///! world units between samples
+    pub spacing: f64,
+    ///! Worley::config_hash of the Worley this was generated with
+    pub config_hash: u64,
+    ///! warp_step the warp was interpolated with (see generate_interpolated), 1: exact
+    pub warp_step: u32,
+    ///! row major (lx * size + lz), size * size entries.
+    ///! inline room for 9 weights like Worley::get, to_compact_bytes for storing many chunks
+    pub samples: Vec<TinyVec<[(f64, BiomeT); 9]>>,
+}
+
+impl<BiomeT: BiomeVariants + Default + 'static> BiomeChunk<BiomeT> {
+    ///! sample a whole chunk
+    pub fn generate<Picker>(
+        worley: &Worley<BiomeT, Picker>,
+        coord: (i32, i32),
+        size: u32,
+        spacing: f64,
+    ) -> Self
+    where
+        Picker: BiomePicker<BiomeT> + Default,
+    {
+        let (ox, oz) = Self::origin_of(coord, size, spacing);
+        let mut samples = Vec::with_capacity((size * size) as usize);
+        for lx in 0..size {
+            for lz in 0..size {
+                samples.push(worley.get(ox + lx as f64 * spacing, oz + lz as f64 * spacing));
+            }
+        }
+        Self {
+            coord,
+            size,
+            spacing,
+            config_hash: worley.config_hash(),
+            warp_step: 1,
+            samples,
+        }
+    }
+
+    ///! generate, with the warp evaluated every warp_step samples and interpolated in between
+    ///! (see WarpGrid). warp_step 0 or 1 is exact
+    pub fn generate_interpolated<Picker>(
+        worley: &Worley<BiomeT, Picker>,
+        coord: (i32, i32),
+        size: u32,
+        spacing: f64,
+        warp_step: u32,
+    ) -> Self
+    where
+        Picker: BiomePicker<BiomeT> + Default,
+    {
+        if warp_step <= 1 {
+            return Self::generate(worley, coord, size, spacing);
+        }
+        let origin = Self::origin_of(coord, size, spacing);
+        let grid = WarpGrid::new(worley, origin, spacing, (size, size), warp_step);
+        let mut samples = Vec::with_capacity((size * size) as usize);
+        for lx in 0..size {
+            for lz in 0..size {
+                samples.push(grid.get(worley, lx, lz));
+            }
+        }
+        Self {
+            coord,
+            size,
+            spacing,
+            config_hash: worley.config_hash(),
+            warp_step,
+            samples,
+        }
+    }
+
+    ///! world position of sample (0, 0) of a chunk
+    pub fn origin_of(coord: (i32, i32), size: u32, spacing: f64) -> (f64, f64) {
+        let extent = size as f64 * spacing;
+        (coord.0 as f64 * extent, coord.1 as f64 * extent)
+    }
+
+    ///! chunk coordinate containing a world position
+    pub fn coord_of(x: f64, z: f64, size: u32, spacing: f64) -> (i32, i32) {
+        let extent = size as f64 * spacing;
+        ((x / extent).floor() as i32, (z / extent).floor() as i32)
+    }
+
+    pub fn origin(&self) -> (f64, f64) {
+        Self::origin_of(self.coord, self.size, self.spacing)
+    }
+
+    ///! weights of a sample, None if out of range
+    pub fn get(&self, lx: u32, lz: u32) -> Option<&TinyVec<[(f64, BiomeT); 9]>> {
+        if lx >= self.size || lz >= self.size {
+            return None;
+        }
+        self.samples.get((lx * self.size + lz) as usize)
+    }
+
+    ///! highest weighted biome of a sample
+    pub fn dominant(&self, lx: u32, lz: u32) -> Option<BiomeT> {
+        self.get(lx, lz)?
+            .iter()
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, biome)| *biome)
+    }
+}
@@ -0,0 +1,438 @@
+use tinyvec::TinyVec;
+
+use crate::biome_picker::{BiomeVariants, variant_index};
+use crate::chunk::BiomeChunk;
+
+const MAGIC: &[u8; 3] = b"WBC";
+const VERSION: u8 = 1;
+const FLAG_WEIGHTS: u8 = 1;
+const FLAG_ZSTD: u8 = 2;
+// palette index of samples without a biome
+const EMPTY: u8 = u8::MAX;
+///! most samples (size * size) from_compact_bytes decodes, run-length encoding lets a few bytes
+///! claim any number of samples, so larger chunks are rejected instead of allocated
+pub const MAX_COMPACT_SAMPLES: usize = 1 << 20;
+
+///! what BiomeChunk::to_compact_bytes keeps
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChunkEncoding {
+    ///! every blended weight quantized to 1/255, otherwise only the dominant biome per sample
+    ///! (decoded as weight 1.0)
+    pub weights: bool,
+    ///! zstd level the encoded data is compressed with, None: uncompressed
+    #[cfg(feature = "zstd")]
+    pub zstd_level: Option<i32>,
+}
+
+#[derive(Debug)]
+pub enum ChunkDecodeError {
+    ///! not a compact chunk
+    Magic,
+    Version(u8),
+    ///! the data ended early
+    Truncated,
+    ///! a biome (variant index) that BiomeT::variants() doesn't have, or a broken palette
+    BadIndex(usize),
+    ///! size * size is above MAX_COMPACT_SAMPLES
+    TooLarge(u32),
+    ///! compressed with zstd but the "zstd" feature is off
+    ZstdDisabled,
+    #[cfg(feature = "zstd")]
+    Zstd(std::io::Error),
+}
+
+impl std::fmt::Display for ChunkDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChunkDecodeError::Magic => write!(f, "not a compact biome chunk"),
+            ChunkDecodeError::Version(version) => {
+                write!(f, "unknown compact chunk version {}", version)
+            }
+            ChunkDecodeError::Truncated => write!(f, "compact chunk data ended early"),
+            ChunkDecodeError::BadIndex(index) => write!(f, "invalid biome index {}", index),
+            ChunkDecodeError::TooLarge(size) => write!(
+                f,
+                "chunk size {} holds more than {} samples",
+                size, MAX_COMPACT_SAMPLES
+            ),
+            ChunkDecodeError::ZstdDisabled => {
+                write!(f, "chunk is zstd compressed, enable feature=\"zstd\"")
+            }
+            #[cfg(feature = "zstd")]
+            ChunkDecodeError::Zstd(err) => write!(f, "could not decompress chunk: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ChunkDecodeError {}
+
+#[cfg(feature = "zstd")]
+impl From<std::io::Error> for ChunkDecodeError {
+    fn from(err: std::io::Error) -> Self {
+        ChunkDecodeError::Zstd(err)
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], ChunkDecodeError> {
+        let (head, rest) = self
+            .0
+            .split_first_chunk::<N>()
+            .ok_or(ChunkDecodeError::Truncated)?;
+        self.0 = rest;
+        Ok(*head)
+    }
+
+    fn u8(&mut self) -> Result<u8, ChunkDecodeError> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn varint(&mut self) -> Result<u32, ChunkDecodeError> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ChunkDecodeError::Truncated)
+    }
+}
+
+impl<BiomeT: BiomeVariants + Default + 'static> BiomeChunk<BiomeT> {
+    ///! a compact encoding for save files: a palette of the biomes present, the dominant
+    ///! biome per sample run-length encoded, optionally the weights quantized to a byte.
+    ///! biomes are stored as BiomeT::variants() indices, so appending variants is fine,
+    ///! reordering them isn't. panics if the chunk holds more than 255 distinct biomes
+    pub fn to_compact_bytes(&self, encoding: ChunkEncoding) -> Vec<u8> {
+        // palette of variant indices, at most u8::MAX - 1 entries (EMPTY is reserved)
+        let mut palette: Vec<usize> = Vec::new();
+        let mut palette_index = |biome: &BiomeT| {
+            let index = variant_index(biome).expect("biome is one of BiomeT::variants()");
+            match palette.iter().position(|&p| p == index) {
+                Some(i) => i as u8,
+                None => {
+                    assert!(
+                        palette.len() < EMPTY as usize,
+                        "a chunk can hold at most {} distinct biomes",
+                        EMPTY
+                    );
+                    palette.push(index);
+                    (palette.len() - 1) as u8
+                }
+            }
+        };
+        let dominant: Vec<u8> = self
+            .samples
+            .iter()
+            .map(|weights| {
+                weights
+                    .iter()
+                    .max_by(|a, b| a.0.total_cmp(&b.0))
+                    .map_or(EMPTY, |(_, biome)| palette_index(biome))
+            })
+            .collect();
+        let weights: Vec<Vec<(u8, u8)>> = match encoding.weights {
+            true => self
+                .samples
+                .iter()
+                .map(|weights| {
+                    weights
+                        .iter()
+                        .map(|(w, biome)| {
+                            (
+                                palette_index(biome),
+                                (w.clamp(0.0, 1.0) * 255.0).round() as u8,
+                            )
+                        })
+                        .collect()
+                })
+                .collect(),
+            false => Vec::new(),
+        };
+
+        let mut body = Vec::new();
+        body.extend_from_slice(&self.coord.0.to_le_bytes());
+        body.extend_from_slice(&self.coord.1.to_le_bytes());
+        body.extend_from_slice(&self.size.to_le_bytes());
+        body.extend_from_slice(&self.spacing.to_le_bytes());
+        body.extend_from_slice(&self.config_hash.to_le_bytes());
+        body.extend_from_slice(&self.warp_step.to_le_bytes());
+        body.push(palette.len() as u8);
+        for index in &palette {
+            body.extend_from_slice(&(*index as u16).to_le_bytes());
+        }
+        // (run length, palette index)
+        let mut runs = dominant.chunk_by(|a, b| a == b);
+        for run in &mut runs {
+            write_varint(&mut body, run.len() as u32);
+            body.push(run[0]);
+        }
+        for sample in &weights {
+            body.push(sample.len() as u8);
+            for (index, w) in sample {
+                body.push(*index);
+                body.push(*w);
+            }
+        }
+
+        let mut flags = 0;
+        if encoding.weights {
+            flags |= FLAG_WEIGHTS;
+        }
+        #[cfg(feature = "zstd")]
+        if let Some(level) = encoding.zstd_level {
+            flags |= FLAG_ZSTD;
+            body = zstd::encode_all(body.as_slice(), level).expect("zstd into memory");
+        }
+        let mut out = Vec::with_capacity(MAGIC.len() + 2 + body.len());
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.push(flags);
+        out.extend_from_slice(&body);
+        out
+    }
+
+    ///! decode to_compact_bytes. without weights, each sample is its dominant biome at 1.0.
+    ///! broken or hostile input is an Err, never a panic or an allocation beyond MAX_COMPACT_SAMPLES
+    pub fn from_compact_bytes(bytes: &[u8]) -> Result<Self, ChunkDecodeError> {
+        let mut header = Reader(bytes);
+        if &header.bytes::<3>()? != MAGIC {
+            return Err(ChunkDecodeError::Magic);
+        }
+        let version = header.u8()?;
+        if version != VERSION {
+            return Err(ChunkDecodeError::Version(version));
+        }
+        let flags = header.u8()?;
+        #[cfg(feature = "zstd")]
+        let decompressed;
+        let mut r = header;
+        if flags & FLAG_ZSTD != 0 {
+            #[cfg(not(feature = "zstd"))]
+            return Err(ChunkDecodeError::ZstdDisabled);
+            #[cfg(feature = "zstd")]
+            {
+                decompressed = zstd::decode_all(r.0)?;
+                r = Reader(&decompressed);
+            }
+        }
+
+        let coord = (
+            i32::from_le_bytes(r.bytes()?),
+            i32::from_le_bytes(r.bytes()?),
+        );
+        let size = u32::from_le_bytes(r.bytes()?);
+        let spacing = f64::from_le_bytes(r.bytes()?);
+        let config_hash = u64::from_le_bytes(r.bytes()?);
+        let warp_step = u32::from_le_bytes(r.bytes()?);
+        let variants = BiomeT::variants();
+        let palette = (0..r.u8()?)
+            .map(|_| {
+                let index = u16::from_le_bytes(r.bytes()?) as usize;
+                variants
+                    .get(index)
+                    .copied()
+                    .ok_or(ChunkDecodeError::BadIndex(index))
+            })
+            .collect::<Result<Vec<BiomeT>, _>>()?;
+        let biome = |index: u8| {
+            palette
+                .get(index as usize)
+                .copied()
+                .ok_or(ChunkDecodeError::BadIndex(index as usize))
+        };
+
+        let count = size
+            .checked_mul(size)
+            .map(|count| count as usize)
+            .filter(|count| *count <= MAX_COMPACT_SAMPLES)
+            .ok_or(ChunkDecodeError::TooLarge(size))?;
+        let mut samples = Vec::new();
+        if flags & FLAG_WEIGHTS != 0 {
+            // the dominant runs are redundant with the weights
+            let mut skipped = 0usize;
+            while skipped < count {
+                skipped = skipped.saturating_add(r.varint()? as usize);
+                r.u8()?;
+            }
+            // every sample takes at least its weight count byte
+            if count > r.0.len() {
+                return Err(ChunkDecodeError::Truncated);
+            }
+            samples.reserve_exact(count);
+            for _ in 0..count {
+                let mut weights = TinyVec::new();
+                for _ in 0..r.u8()? {
+                    let b = biome(r.u8()?)?;
+                    weights.push((r.u8()? as f64 / 255.0, b));
+                }
+                samples.push(weights);
+            }
+        } else {
+            while samples.len() < count {
+                let run = r.varint()? as usize;
+                let index = r.u8()?;
+                let mut weights = TinyVec::new();
+                if index != EMPTY {
+                    weights.push((1.0, biome(index)?));
+                }
+                samples.extend(std::iter::repeat_n(weights, run.min(count - samples.len())));
+            }
+        }
+
+        Ok(Self {
+            coord,
+            size,
+            spacing,
+            config_hash,
+            warp_step,
+            samples,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    enum TestBiome {
+        #[default]
+        Plains,
+        Forest,
+        Desert,
+    }
+
+    impl BiomeVariants for TestBiome {
+        fn variants() -> &'static [Self] {
+            &[TestBiome::Plains, TestBiome::Forest, TestBiome::Desert]
+        }
+    }
+
+    // runs of one biome, a blended stretch and an empty sample
+    fn sample_chunk() -> BiomeChunk<TestBiome> {
+        let size = 4;
+        let samples = (0..size * size)
+            .map(|i| {
+                let mut weights = TinyVec::new();
+                match i {
+                    0..=6 => weights.push((1.0, TestBiome::Forest)),
+                    7 => {}
+                    _ => {
+                        weights.push((0.7, TestBiome::Desert));
+                        weights.push((0.3, TestBiome::Plains));
+                    }
+                }
+                weights
+            })
+            .collect();
+        BiomeChunk {
+            coord: (-3, 12),
+            size,
+            spacing: 0.5,
+            config_hash: u64::MAX - 7,
+            warp_step: 4,
+            samples,
+        }
+    }
+
+    fn assert_header(chunk: &BiomeChunk<TestBiome>, decoded: &BiomeChunk<TestBiome>) {
+        assert_eq!(decoded.coord, chunk.coord);
+        assert_eq!(decoded.size, chunk.size);
+        assert_eq!(decoded.spacing, chunk.spacing);
+        assert_eq!(decoded.config_hash, chunk.config_hash);
+        assert_eq!(decoded.warp_step, chunk.warp_step);
+        assert_eq!(decoded.samples.len(), chunk.samples.len());
+    }
+
+    fn assert_weights(chunk: &BiomeChunk<TestBiome>, decoded: &BiomeChunk<TestBiome>) {
+        assert_header(chunk, decoded);
+        for (original, decoded) in chunk.samples.iter().zip(&decoded.samples) {
+            assert_eq!(original.len(), decoded.len());
+            for ((w, biome), (dw, db)) in original.iter().zip(decoded.iter()) {
+                assert_eq!(biome, db);
+                assert!((w - dw).abs() <= 0.5 / 255.0);
+            }
+        }
+    }
+
+    #[test]
+    fn run_length_round_trip() {
+        let chunk = sample_chunk();
+        let bytes = chunk.to_compact_bytes(ChunkEncoding::default());
+        let decoded = BiomeChunk::<TestBiome>::from_compact_bytes(&bytes).unwrap();
+        assert_header(&chunk, &decoded);
+        for i in 0..chunk.size {
+            for j in 0..chunk.size {
+                assert_eq!(decoded.dominant(i, j), chunk.dominant(i, j));
+            }
+        }
+        assert!(decoded.samples[7].is_empty());
+        assert_eq!(decoded.samples[0][..], [(1.0, TestBiome::Forest)]);
+    }
+
+    #[test]
+    fn weights_round_trip() {
+        let chunk = sample_chunk();
+        let encoding = ChunkEncoding {
+            weights: true,
+            ..Default::default()
+        };
+        let bytes = chunk.to_compact_bytes(encoding);
+        let decoded = BiomeChunk::<TestBiome>::from_compact_bytes(&bytes).unwrap();
+        assert_weights(&chunk, &decoded);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_round_trip() {
+        let chunk = sample_chunk();
+        let encoding = ChunkEncoding {
+            weights: true,
+            zstd_level: Some(3),
+        };
+        let bytes = chunk.to_compact_bytes(encoding);
+        let decoded = BiomeChunk::<TestBiome>::from_compact_bytes(&bytes).unwrap();
+        assert_weights(&chunk, &decoded);
+    }
+
+    #[test]
+    fn broken_input_is_an_error() {
+        let chunk = sample_chunk();
+        for encoding in [
+            ChunkEncoding::default(),
+            ChunkEncoding {
+                weights: true,
+                ..Default::default()
+            },
+        ] {
+            let bytes = chunk.to_compact_bytes(encoding);
+            for len in 0..bytes.len() {
+                assert!(BiomeChunk::<TestBiome>::from_compact_bytes(&bytes[..len]).is_err());
+            }
+
+            // size * size overflowing u32, and a size within u32 but past the sample limit
+            let at = MAGIC.len() + 2 + 8;
+            for size in [u32::MAX, 1 << 16] {
+                let mut bytes = bytes.clone();
+                bytes[at..at + 4].copy_from_slice(&size.to_le_bytes());
+                assert!(matches!(
+                    BiomeChunk::<TestBiome>::from_compact_bytes(&bytes),
+                    Err(ChunkDecodeError::TooLarge(_))
+                ));
+            }
+        }
+    }
+}
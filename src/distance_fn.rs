@@ -1,12 +1,16 @@
+#[cfg(feature = "bevy-core")]
+use bevy::reflect::Reflect;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 ///! what distance function to use to measure distance to worlay
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "bevy-core", derive(Reflect))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
 pub enum DistanceFn {
     Euclidean,
+    #[default]
     EuclideanSquared,
     Manhattan,
     Chebyshev,
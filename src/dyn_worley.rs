@@ -0,0 +1,36 @@
+use tinyvec::TinyVec;
+
+use crate::biome_picker::{BiomePicker, BiomeVariants};
+use crate::worley::Worley;
+
+///! a type erased Worley, for engine side code (render, netcode) that wants to store the sampler
+///! without the BiomeT/Picker generics, e.g. `Box<dyn DynWorley>`.
+///! biomes are reported as indices into `BiomeT::variants()`
+pub trait DynWorley: Send + Sync {
+    ///! returns a vec of (0: percentage) we use for (1: variant index), see Worley::get
+    fn get_indices(&self, x: f64, z: f64) -> TinyVec<[(f64, usize); 9]>;
+    ///! number of variants indices can refer to
+    fn variant_count(&self) -> usize;
+}
+
+impl<BiomeT, Picker> DynWorley for Worley<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + PartialEq + Default + Send + Sync + 'static,
+    Picker: BiomePicker<BiomeT> + Default + Send + Sync,
+{
+    fn get_indices(&self, x: f64, z: f64) -> TinyVec<[(f64, usize); 9]> {
+        let variants = BiomeT::variants();
+        self.get(x, z)
+            .iter()
+            .filter_map(|(w, biome)| {
+                // biomes missing from variants() can't be reported
+                let idx = variants.iter().position(|v| v == biome)?;
+                Some((*w, idx))
+            })
+            .collect()
+    }
+
+    fn variant_count(&self) -> usize {
+        BiomeT::variants().len()
+    }
+}
@@ -0,0 +1,45 @@
+// bevy free exports of a rectangle of a Worley, for batch scripts and headless servers.
+// x in the output is world x, y is world z
+
+mod grid;
+#[cfg(feature = "image")]
+mod png;
+mod svg;
+pub use grid::{export_csv, export_npy, write_dominant_csv, write_weights_npy};
+#[cfg(feature = "image")]
+pub use png::{export_height_png, export_png, render_biome_map, render_height_map};
+pub use svg::{SvgLayers, export_svg, write_svg};
+
+///! an axis aligned world area, (x, z)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MapRect {
+    pub min: (f64, f64),
+    pub max: (f64, f64),
+}
+
+impl MapRect {
+    pub fn new(min: (f64, f64), max: (f64, f64)) -> Self {
+        Self { min, max }
+    }
+
+    ///! a square of half_extent in each direction around center
+    pub fn from_center(center: (f64, f64), half_extent: f64) -> Self {
+        Self {
+            min: (center.0 - half_extent, center.1 - half_extent),
+            max: (center.0 + half_extent, center.1 + half_extent),
+        }
+    }
+
+    pub fn size(&self) -> (f64, f64) {
+        (self.max.0 - self.min.0, self.max.1 - self.min.1)
+    }
+
+    ///! world position of the center of sample (ix, iy), when resolution samples cover the rect
+    pub fn sample_pos(&self, resolution: (u32, u32), ix: u32, iy: u32) -> (f64, f64) {
+        let (w, h) = self.size();
+        (
+            self.min.0 + (ix as f64 + 0.5) / resolution.0 as f64 * w,
+            self.min.1 + (iy as f64 + 0.5) / resolution.1 as f64 * h,
+        )
+    }
+}
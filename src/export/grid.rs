@@ -0,0 +1,116 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    biome_picker::{BiomePicker, BiomeVariants, variant_index},
+    export::MapRect,
+    worley::Worley,
+};
+
+///! csv of the dominant biome per sample, as index into BiomeT::variants(), -1 where no biome.
+///! a line per y (world z), a column per x
+pub fn write_dominant_csv<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    rect: MapRect,
+    resolution: (u32, u32),
+    mut writer: impl Write,
+) -> std::io::Result<()>
+where
+    BiomeT: BiomeVariants + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    for iy in 0..resolution.1 {
+        for ix in 0..resolution.0 {
+            let (x, z) = rect.sample_pos(resolution, ix, iy);
+            let index = worley
+                .dominant(x, z)
+                .and_then(|biome| variant_index(&biome))
+                .map_or(-1, |i| i as i64);
+            if ix > 0 {
+                writer.write_all(b",")?;
+            }
+            write!(writer, "{}", index)?;
+        }
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+///! a NumPy `.npy` (v1.0) float32 array of shape (y, x, variants), the weight of every
+///! BiomeT::variants() entry per sample, in the order of variants. `numpy.load` reads it
+pub fn write_weights_npy<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    rect: MapRect,
+    resolution: (u32, u32),
+    mut writer: impl Write,
+) -> std::io::Result<()>
+where
+    BiomeT: BiomeVariants + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let variants = BiomeT::variants().len();
+    let mut header = format!(
+        "{{'descr': '<f4', 'fortran_order': False, 'shape': ({}, {}, {}), }}",
+        resolution.1, resolution.0, variants
+    );
+    // magic (6) + version (2) + header length (2) + header, padded with spaces and
+    // ending in a newline to a multiple of 64 bytes
+    let unpadded = 10 + header.len() + 1;
+    header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+    header.push('\n');
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+
+    let mut weights = vec![0.0f32; variants];
+    for iy in 0..resolution.1 {
+        for ix in 0..resolution.0 {
+            let (x, z) = rect.sample_pos(resolution, ix, iy);
+            weights.fill(0.0);
+            for (w, biome) in worley.get(x, z).iter() {
+                if let Some(i) = variant_index(biome) {
+                    weights[i] += *w as f32;
+                }
+            }
+            for w in &weights {
+                writer.write_all(&w.to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+///! write_dominant_csv into a file
+pub fn export_csv<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    rect: MapRect,
+    resolution: (u32, u32),
+    path: impl AsRef<Path>,
+) -> std::io::Result<()>
+where
+    BiomeT: BiomeVariants + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_dominant_csv(worley, rect, resolution, &mut writer)?;
+    writer.flush()
+}
+
+///! write_weights_npy into a file
+pub fn export_npy<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    rect: MapRect,
+    resolution: (u32, u32),
+    path: impl AsRef<Path>,
+) -> std::io::Result<()>
+where
+    BiomeT: BiomeVariants + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_weights_npy(worley, rect, resolution, &mut writer)?;
+    writer.flush()
+}
@@ -0,0 +1,95 @@
+use std::path::Path;
+
+use image::{ImageBuffer, ImageError, Luma, Rgb, RgbImage};
+
+use crate::{
+    biome_picker::{BiomePicker, BiomeVariants},
+    export::MapRect,
+    worley::Worley,
+};
+
+///! the biome colors blended by weight, empty samples (no biome) are black
+pub fn render_biome_map<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    rect: MapRect,
+    resolution: (u32, u32),
+    color_fn: impl Fn(&BiomeT) -> [u8; 3],
+) -> RgbImage
+where
+    BiomeT: BiomeVariants + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    RgbImage::from_fn(resolution.0, resolution.1, |ix, iy| {
+        let (x, z) = rect.sample_pos(resolution, ix, iy);
+        let mut rgb = [0.0; 3];
+        for (w, biome) in worley.get(x, z).iter() {
+            for (channel, c) in rgb.iter_mut().zip(color_fn(biome)) {
+                *channel += c as f64 * w;
+            }
+        }
+        Rgb(rgb.map(|c| c.round().clamp(0.0, 255.0) as u8))
+    })
+}
+
+///! the biome heights blended by weight, as 16 bit gray.
+///! black is the lowest, white the highest height of any variant, so maps of different
+///! areas share one scale
+pub fn render_height_map<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    rect: MapRect,
+    resolution: (u32, u32),
+    height_fn: impl Fn(&BiomeT) -> f32,
+) -> ImageBuffer<Luma<u16>, Vec<u16>>
+where
+    BiomeT: BiomeVariants + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let (min, max) = BiomeT::variants()
+        .iter()
+        .map(|v| height_fn(v) as f64)
+        .fold((f64::MAX, f64::MIN), |(lo, hi), h| (lo.min(h), hi.max(h)));
+    let range = (max - min).max(1e-9);
+    ImageBuffer::from_fn(resolution.0, resolution.1, |ix, iy| {
+        let (x, z) = rect.sample_pos(resolution, ix, iy);
+        let h: f64 = worley
+            .get(x, z)
+            .iter()
+            .map(|(w, biome)| w * height_fn(biome) as f64)
+            .sum();
+        Luma([(((h - min) / range).clamp(0.0, 1.0) * u16::MAX as f64).round() as u16])
+    })
+}
+
+///! render_biome_map, saved as png
+///! ```ignore
+///! let rect = MapRect::from_center((0.0, 0.0), 2048.0);
+///! export_png(&worley, rect, (1024, 1024), |biome| biome.rgb(), "reference.png")?;
+///! ```
+pub fn export_png<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    rect: MapRect,
+    resolution: (u32, u32),
+    color_fn: impl Fn(&BiomeT) -> [u8; 3],
+    path: impl AsRef<Path>,
+) -> Result<(), ImageError>
+where
+    BiomeT: BiomeVariants + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    render_biome_map(worley, rect, resolution, color_fn).save(path)
+}
+
+///! render_height_map, saved as 16 bit grayscale png
+pub fn export_height_png<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    rect: MapRect,
+    resolution: (u32, u32),
+    height_fn: impl Fn(&BiomeT) -> f32,
+    path: impl AsRef<Path>,
+) -> Result<(), ImageError>
+where
+    BiomeT: BiomeVariants + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    render_height_map(worley, rect, resolution, height_fn).save(path)
+}
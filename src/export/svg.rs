@@ -0,0 +1,190 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    biome_picker::{BiomePicker, BiomeVariants, variant_index},
+    export::MapRect,
+    utils::cell_point,
+    worley::Worley,
+};
+
+///! what write_svg draws, each is a `<g>` group named after the field
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvgLayers {
+    ///! the dominant biome per sample, rows merged into runs of rects
+    pub fills: bool,
+    ///! lines where the nearest (warped) cell changes between neighbouring samples
+    pub borders: bool,
+    ///! a circle per non-empty cell feature point. drawn at point * zoom, which is in warped
+    ///! space: exact without warp, shifted by the warp otherwise
+    pub points: bool,
+}
+
+impl Default for SvgLayers {
+    fn default() -> Self {
+        Self {
+            fills: true,
+            borders: true,
+            points: true,
+        }
+    }
+}
+
+///! the cell structure of rect as svg, in world units relative to rect.min (viewBox),
+///! resolution samples (also the size in px). borders follow the warp at sample precision
+pub fn write_svg<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    rect: MapRect,
+    resolution: (u32, u32),
+    layers: SvgLayers,
+    color_fn: impl Fn(&BiomeT) -> [u8; 3],
+    mut writer: impl Write,
+) -> std::io::Result<()>
+where
+    BiomeT: BiomeVariants + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let (w, h) = rect.size();
+    let (sw, sh) = (w / resolution.0 as f64, h / resolution.1 as f64);
+    writeln!(
+        writer,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        resolution.0, resolution.1, w, h
+    )?;
+
+    // (dominant biome, nearest cell) per sample, row major
+    let samples: Vec<(Option<BiomeT>, (i32, i32))> = (0..resolution.1)
+        .flat_map(|iy| (0..resolution.0).map(move |ix| (ix, iy)))
+        .map(|(ix, iy)| {
+            let (x, z) = rect.sample_pos(resolution, ix, iy);
+            (worley.dominant(x, z), worley.cell_sample(x, z).cell)
+        })
+        .collect();
+    let at = |ix: u32, iy: u32| &samples[(iy * resolution.0 + ix) as usize];
+
+    if layers.fills {
+        writeln!(writer, r#"<g id="fills" shape-rendering="crispEdges">"#)?;
+        for iy in 0..resolution.1 {
+            let mut ix = 0;
+            while ix < resolution.0 {
+                let biome = at(ix, iy).0;
+                let start = ix;
+                let variant = biome.as_ref().and_then(variant_index);
+                while ix < resolution.0 && at(ix, iy).0.as_ref().and_then(variant_index) == variant
+                {
+                    ix += 1;
+                }
+                let Some(biome) = biome else {
+                    continue;
+                };
+                let [r, g, b] = color_fn(&biome);
+                writeln!(
+                    writer,
+                    r#"<rect x="{:.3}" y="{:.3}" width="{:.3}" height="{:.3}" fill="rgb({},{},{})"/>"#,
+                    start as f64 * sw,
+                    iy as f64 * sh,
+                    (ix - start) as f64 * sw,
+                    sh,
+                    r,
+                    g,
+                    b
+                )?;
+            }
+        }
+        writeln!(writer, "</g>")?;
+    }
+
+    if layers.borders {
+        // marching squares over the sample centers: a border point on every square edge whose
+        // ends are in different cells, joined pairwise, or through the square center at junctions
+        let center = |ix: u32, iy: u32| ((ix as f64 + 0.5) * sw, (iy as f64 + 0.5) * sh);
+        let mut path = String::new();
+        for iy in 0..resolution.1.saturating_sub(1) {
+            for ix in 0..resolution.0.saturating_sub(1) {
+                let corners = [(ix, iy), (ix + 1, iy), (ix + 1, iy + 1), (ix, iy + 1)];
+                let mut crossings = Vec::with_capacity(4);
+                for i in 0..4 {
+                    let (a, b) = (corners[i], corners[(i + 1) % 4]);
+                    if at(a.0, a.1).1 != at(b.0, b.1).1 {
+                        let (ca, cb) = (center(a.0, a.1), center(b.0, b.1));
+                        crossings.push(((ca.0 + cb.0) / 2.0, (ca.1 + cb.1) / 2.0));
+                    }
+                }
+                let mid = center(ix, iy);
+                let mid = (mid.0 + sw / 2.0, mid.1 + sh / 2.0);
+                match crossings.as_slice() {
+                    [] => {}
+                    [a, b] => {
+                        path.push_str(&format!("M{:.3} {:.3}L{:.3} {:.3}", a.0, a.1, b.0, b.1))
+                    }
+                    points => {
+                        for p in points {
+                            path.push_str(&format!(
+                                "M{:.3} {:.3}L{:.3} {:.3}",
+                                p.0, p.1, mid.0, mid.1
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        writeln!(
+            writer,
+            r#"<g id="borders"><path d="{}" fill="none" stroke="black" stroke-width="{:.3}" stroke-linecap="round"/></g>"#,
+            path,
+            sw.min(sh)
+        )?;
+    }
+
+    if layers.points {
+        writeln!(writer, r#"<g id="points">"#)?;
+        let zoom = worley.zoom;
+        for cx in (rect.min.0 / zoom).floor() as i32..=(rect.max.0 / zoom).floor() as i32 {
+            for cz in (rect.min.1 / zoom).floor() as i32..=(rect.max.1 / zoom).floor() as i32 {
+                if worley
+                    .biome_picker
+                    .try_pick_biome(worley.seed, cx, cz)
+                    .is_none()
+                {
+                    continue;
+                }
+                let (px, pz) = cell_point(worley.seed, cx, cz);
+                let (px, pz) = (px * zoom - rect.min.0, pz * zoom - rect.min.1);
+                if !(0.0..=w).contains(&px) || !(0.0..=h).contains(&pz) {
+                    continue;
+                }
+                writeln!(
+                    writer,
+                    r#"<circle cx="{:.3}" cy="{:.3}" r="{:.3}"/>"#,
+                    px,
+                    pz,
+                    2.0 * sw.min(sh)
+                )?;
+            }
+        }
+        writeln!(writer, "</g>")?;
+    }
+
+    writeln!(writer, "</svg>")
+}
+
+///! write_svg into a file
+pub fn export_svg<BiomeT, Picker>(
+    worley: &Worley<BiomeT, Picker>,
+    rect: MapRect,
+    resolution: (u32, u32),
+    layers: SvgLayers,
+    color_fn: impl Fn(&BiomeT) -> [u8; 3],
+    path: impl AsRef<Path>,
+) -> std::io::Result<()>
+where
+    BiomeT: BiomeVariants + Default + 'static,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_svg(worley, rect, resolution, layers, color_fn, &mut writer)?;
+    writer.flush()
+}
@@ -1,14 +1,22 @@
 pub mod biome_picker;
+pub mod biome_tags;
+pub mod chunk;
 pub mod distance_fn;
+pub mod dyn_worley;
+pub mod export;
 pub mod utils;
 pub mod warp;
 pub mod worley;
 
-#[cfg(feature = "bevy")]
+#[cfg(feature = "bevy-core")]
 pub mod bevy;
 
 pub mod prelude {
     pub use crate::biome_picker::BiomeVariants;
+    pub use crate::biome_picker::DynPicker;
+    pub use crate::biome_picker::LandMaskPicker;
     pub use crate::biome_picker::SimpleBiomePicker;
+    pub use crate::biome_tags::BiomeTags;
+    pub use crate::dyn_worley::DynWorley;
     pub use crate::worley::Worley;
 }
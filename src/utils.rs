@@ -1,13 +1,108 @@
-use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand::{Rng, RngCore, SeedableRng};
 use std::hash::{Hash, Hasher};
 
+#[cfg(feature = "serde")]
+pub mod seed_serde;
+
+///! seed domains: every consumer of the world seed derives its own seed with `derive_seed(seed, domain)`.
+///! guarantee: point placement, biome picking and warp never hash the same (seed, x, z) input,
+///! so e.g. a cell's jitter tells you nothing about its biome.
+///! new consumers must add their own constant here instead of offsetting the seed ad-hoc
+pub mod seed_domain {
+    pub const POINT_X: u64 = 0x9E37_79B9_7F4A_7C15;
+    pub const POINT_Z: u64 = 0xC2B2_AE3D_27D4_EB4F;
+    pub const PICK: u64 = 0x1656_67B1_9E37_79F9;
+    pub const WARP: u64 = 0x27D4_EB2F_1656_67C5;
+    pub const MARKOV: u64 = 0x85EB_CA77_C2B2_AE63;
+    pub const COVERAGE: u64 = 0xFF51_AFD7_ED55_8CCD;
+    pub const MASK_BLEND: u64 = 0xC4CE_B9FE_1A85_EC53;
+    pub const ANTI_CLUSTER: u64 = 0x5851_F42D_4C95_7F2D;
+    pub const SIZE_CLASS: u64 = 0x2545_F491_4F6C_DD1D;
+    pub const SPARSE: u64 = 0x7FB5_D329_728E_A185;
+    pub const SUBSET: u64 = 0x4F1B_BCDC_BFA5_3E0B;
+    pub const MUTATION: u64 = 0xD6E8_FEB8_6659_FD93;
+    pub const DITHER: u64 = 0x9FB2_1C65_1E98_DF25;
+}
+
+///! mix the world seed with a domain constant (splitmix64 finalizer),
+///! so nearby seeds or domains produce unrelated derived seeds
+#[inline(always)]
+pub fn derive_seed(seed: u64, domain: u64) -> u64 {
+    let mut z = seed ^ domain;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
 pub fn hash_u64(seed: u64, x: i32, z: i32) -> u64 {
     let mut hasher = fxhash::FxHasher::default();
     (seed, x, z).hash(&mut hasher);
     hasher.finish()
 }
 
+///! a SplitMix64 seeded from the world seed + cell position
 pub fn seeded_rng(seed: u64, x: i32, z: i32) -> impl Rng {
+    seeded_rng_with::<SplitMix64>(seed, x, z)
+}
+
+///! like seeded_rng, but with any rng, e.g. a faster PCG/Xoshiro or a version pinned one
+pub fn seeded_rng_with<R: SeedableRng + RngCore>(seed: u64, x: i32, z: i32) -> R {
     let combined = seed ^ ((x as u64) << 32) ^ (z as u64);
-    StdRng::seed_from_u64(combined)
+    R::seed_from_u64(combined)
+}
+
+///! splitmix64: one u64 of state, an add and the derive_seed finalizer per draw.
+///! meant for the few rolls a cell needs, where seeding a StdRng (ChaCha12) costs more than the rolls.
+///! determinism: next_u64 is fully defined here (seed_from_u64 uses the state as is), so it doesn't change
+///! with the rand version. values drawn through Rng::random (e.g. f32) go through rand's conversions
+#[derive(Clone, Debug)]
+pub struct SplitMix64(pub u64);
+
+impl RngCore for SplitMix64 {
+    #[inline(always)]
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    #[inline(always)]
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        derive_seed(self.0, 0)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for chunk in dst.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+impl SeedableRng for SplitMix64 {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: [u8; 8]) -> Self {
+        Self(u64::from_le_bytes(seed))
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        Self(state)
+    }
+}
+
+///! the cell feature point, a random position inside the cell seeded from the world seed + cell position
+#[inline(always)]
+pub fn cell_point(seed: u64, cell_x: i32, cell_z: i32) -> (f64, f64) {
+    let h1 = hash_u64(derive_seed(seed, seed_domain::POINT_X), cell_x, cell_z);
+    let h2 = hash_u64(derive_seed(seed, seed_domain::POINT_Z), cell_x, cell_z);
+
+    let fx = cell_x as f64 + ((h1 & 0xFFFF) as f64 / 65535.0);
+    let fz = cell_z as f64 + ((h2 & 0xFFFF) as f64 / 65535.0);
+    (fx, fz)
+}
+
+///! uniform value in 0.0..1.0 from the cell hash, cheaper than building an rng for a single roll
+#[inline(always)]
+pub fn hash_f32(seed: u64, x: i32, z: i32) -> f32 {
+    (hash_u64(seed, x, z) >> 40) as f32 / (1u64 << 24) as f32
 }
@@ -0,0 +1,79 @@
+use serde::{
+    Deserializer, Serializer,
+    de::{Error, Visitor},
+};
+
+// `#[serde(with = "crate::utils::seed_serde")]` for u64 seeds: toml integers are i64, so human
+// readable formats get seeds above i64::MAX as a decimal string, `"18446744073709551615"`.
+// smaller seeds stay numbers, loading accepts either. binary formats keep the plain u64
+
+pub fn serialize<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() && *seed > i64::MAX as u64 {
+        serializer.serialize_str(&seed.to_string())
+    } else {
+        serializer.serialize_u64(*seed)
+    }
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_any(SeedVisitor)
+    } else {
+        deserializer.deserialize_u64(SeedVisitor)
+    }
+}
+
+struct SeedVisitor;
+
+impl<'de> Visitor<'de> for SeedVisitor {
+    type Value = u64;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a u64 seed, as a number or a decimal string")
+    }
+
+    fn visit_u64<E: Error>(self, v: u64) -> Result<u64, E> {
+        Ok(v)
+    }
+
+    fn visit_i64<E: Error>(self, v: i64) -> Result<u64, E> {
+        u64::try_from(v).map_err(|_| E::custom(format!("negative seed {}", v)))
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<u64, E> {
+        v.parse()
+            .map_err(|_| E::custom(format!("seed {:?} is not a u64", v)))
+    }
+}
+
+// the same for Option<u64>
+pub mod option {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(seed: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match seed {
+            Some(seed) => serializer.serialize_some(&Seed(*seed)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u64>, D::Error> {
+        Ok(Option::<Seed>::deserialize(deserializer)?.map(|seed| seed.0))
+    }
+
+    struct Seed(u64);
+
+    impl serde::Serialize for Seed {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            super::serialize(&self.0, serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Seed {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            super::deserialize(deserializer).map(Seed)
+        }
+    }
+}
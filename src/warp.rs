@@ -1,27 +1,521 @@
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+#[cfg(feature = "bevy-core")]
+use bevy::reflect::Reflect;
 use bracket_fast_noise::prelude::FastNoise;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::utils::{derive_seed, seed_domain};
+
+#[cfg(feature = "serde")]
+mod io;
+
+///! a 2d noise source for warping, returning roughly -1.0 -> 1.0.
+///! implemented for bracket_fast_noise, and behind features for noise-rs ("noise-rs")
+///! and fastnoise-lite ("fastnoise-lite"). implement it to supply your own
+pub trait WarpNoise {
+    fn get_noise(&self, x: f32, z: f32) -> f32;
+
+    ///! how much the source scales coordinates before sampling, warp_coords divides the z channel
+    ///! offset by it to keep it the same distance in noise space. 1.0: samples at x, z as given
+    fn frequency(&self) -> f32 {
+        1.0
+    }
+}
+
+impl WarpNoise for FastNoise {
+    #[inline(always)]
+    fn get_noise(&self, x: f32, z: f32) -> f32 {
+        FastNoise::get_noise(self, x, z)
+    }
+
+    fn frequency(&self) -> f32 {
+        self.frequency
+    }
+}
+
+///! adapter for any noise-rs 2d noise function, e.g. `NoiseRs(noise::Perlin::new(0))`
+#[cfg(feature = "noise-rs")]
+pub struct NoiseRs<N>(pub N);
+
+#[cfg(feature = "noise-rs")]
+impl<N: noise::NoiseFn<f64, 2>> WarpNoise for NoiseRs<N> {
+    #[inline(always)]
+    fn get_noise(&self, x: f32, z: f32) -> f32 {
+        self.0.get([x as f64, z as f64]) as f32
+    }
+}
+
+#[cfg(feature = "fastnoise-lite")]
+impl WarpNoise for fastnoise_lite::FastNoiseLite {
+    #[inline(always)]
+    fn get_noise(&self, x: f32, z: f32) -> f32 {
+        self.get_noise_2d(x, z)
+    }
+
+    fn frequency(&self) -> f32 {
+        self.frequency
+    }
+}
+
+///! replaces the whole noise based warp stage of a Worley (see Worley::custom_warp),
+///! e.g. to warp by a precomputed flow field. takes and returns cell space coordinates.
+///! implemented for closures `Fn(f64, f64) -> (f64, f64)`
+pub trait CoordWarp {
+    fn warp(&self, x: f64, z: f64) -> (f64, f64);
+}
+
+impl<F: Fn(f64, f64) -> (f64, f64)> CoordWarp for F {
+    #[inline(always)]
+    fn warp(&self, x: f64, z: f64) -> (f64, f64) {
+        self(x, z)
+    }
+}
+
+///! where the warp noise seeds come from
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy-core", derive(Reflect))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub enum WarpSeed {
+    ///! derived from Worley::seed (seed_domain::WARP), so changing the world seed changes the warp too
+    #[default]
+    Derived,
+    ///! whatever seed the noise was given, the old behaviour
+    Explicit,
+}
+
+///! which coordinates the warp runs in, see Worley::warp_space
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bevy-core", derive(Reflect))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub enum WarpSpace {
+    ///! after dividing by zoom: the warp scales with the cells, changing zoom changes the warp's look
+    #[default]
+    Cell,
+    ///! in world units before zoom: tune the warp once, then resize cells freely.
+    ///! frequency and strength are in world units then, so they need much smaller/larger values
+    World,
+}
+
+///! how noise turns into a displacement
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Default)]
+#[cfg_attr(feature = "bevy-core", derive(Reflect))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub enum WarpMode {
+    ///! independent x/z offsets sampled from the noise
+    #[default]
+    Offset,
+    ///! displace along the curl of the noise field (perpendicular to its gradient).
+    ///! flow like and divergence free, so borders swirl without pinching
+    Curl,
+}
+
+///! with feature="bevy-core" this is Reflect, except for `noise`, `strength_modulation` and `backend`:
+///! FastNoise is a foreign type with private fields, edit those through the DebugPlugin ui.
+///! deserializing also accepts the legacy flat `noise_seed`/`noise_frequency`/`noise_fractal_*` layout.
+///! text formats write the noise seeds apart from the noise (see warp::io), deserialized alone
+///! a WarpSeed::Derived layer has seed 0 until apply_world_seed
+#[cfg_attr(feature = "bevy-core", derive(Reflect), reflect(no_field_bounds))]
+#[derive(Clone)]
 pub struct WarpSettings {
     pub strength: f32,
+    ///! per axis (x, z) multipliers of strength, e.g. (1.0, 0.2) for wind swept, x striated shapes
+    pub strength_axes: (f32, f32),
+    ///! serialized with all its settings, including interpolation (Interp)
+    ///! and the cellular sub-settings used by NoiseType::Cellular
+    #[cfg_attr(feature = "bevy-core", reflect(ignore))]
     pub noise: FastNoise,
+    ///! if not empty, the warp sums its own octaves with these weights instead of using the fractal.
+    ///! octave i samples at frequency * lacunarity^i, e.g. [1.0, 0.3, 0.05] for strong continental
+    ///! warp with little high frequency jitter. use it with a non-fractal noise type (Perlin, Simplex, ..)
+    pub octave_weights: Vec<f32>,
+    ///! where the z displacement channel samples the noise, relative to the x channel.
+    ///! in noise space (divided by frequency), so the channels stay decorrelated at any frequency.
+    ///! avoid multiples of 256, FastNoise repeats with that period
+    pub z_channel_offset: (f32, f32),
+    pub mode: WarpMode,
+    ///! displace by |noise| (re-centered to -1.0 -> 1.0) instead of noise,
+    ///! folding the field at its zero crossings into sharp ridged borders
+    pub turbulence: bool,
+    pub seed_mode: WarpSeed,
+    ///! if set, sampled instead of `noise` (not serialized, set it again after loading)
+    #[cfg_attr(feature = "bevy-core", reflect(ignore))]
+    pub backend: Option<Arc<dyn WarpNoise + Send + Sync>>,
+    ///! if set, scales strength per position by this noise remapped to 0.0 -> 1.0,
+    ///! so some areas are heavily distorted and others nearly regular
+    #[cfg_attr(feature = "bevy-core", reflect(ignore))]
+    pub strength_modulation: Option<FastNoise>,
+    ///! warp-of-warp: 1 is a single pass, 2 is p + warp(p + warp(p)), ...
+    ///! higher values give more organic swirling borders, each iteration costs another noise sample
+    pub iterations: u8,
+    ///! extra layers, applied in sequence after this one.
+    ///! e.g. a low frequency continent scale warp, followed by a fine coastline wiggle
+    pub layers: Vec<WarpSettings>,
+}
+
+#[cfg(feature = "serde")]
+fn default_iterations() -> u8 {
+    1
+}
+
+const DEFAULT_Z_CHANNEL_OFFSET: (f32, f32) = (517.3, -371.9);
+
+///! the fractal octaves Worley::validate accepts. FastNoise takes any i32, but every octave is
+///! another noise sample per warp lookup, and values past this only add invisible detail
+pub const FRACTAL_OCTAVES: std::ops::RangeInclusive<i32> = 0..=16;
+
+#[cfg(feature = "serde")]
+fn default_z_channel_offset() -> (f32, f32) {
+    DEFAULT_Z_CHANNEL_OFFSET
+}
+
+#[cfg(feature = "serde")]
+fn default_strength_axes() -> (f32, f32) {
+    (1.0, 1.0)
+}
+
+impl Default for WarpSettings {
+    fn default() -> Self {
+        Self {
+            strength: 0.0,
+            strength_axes: (1.0, 1.0),
+            noise: FastNoise::default(),
+            octave_weights: Vec::new(),
+            z_channel_offset: DEFAULT_Z_CHANNEL_OFFSET,
+            mode: WarpMode::default(),
+            turbulence: false,
+            seed_mode: WarpSeed::default(),
+            backend: None,
+            strength_modulation: None,
+            iterations: 1,
+            layers: Vec::new(),
+        }
+    }
+}
+
+// FastNoise isn't Hash, hash what its pub fields and getters expose
+pub(crate) fn hash_noise(noise: &FastNoise, h: &mut impl Hasher) {
+    noise.get_seed().hash(h);
+    noise.frequency.to_bits().hash(h);
+    noise.fractal_lacunarity.to_bits().hash(h);
+    noise.fractal_octaves.hash(h);
+    noise.get_fractal_gain().to_bits().hash(h);
+    std::mem::discriminant(&noise.noise_type).hash(h);
+    std::mem::discriminant(&noise.fractal_type).hash(h);
+    std::mem::discriminant(&noise.get_interp()).hash(h);
+    std::mem::discriminant(&noise.get_cellular_distance_function()).hash(h);
+    std::mem::discriminant(&noise.get_cellular_return_type()).hash(h);
+    noise.get_cellular_jitter().to_bits().hash(h);
 }
 
 impl WarpSettings {
+    ///! feed every setting of this layer and the extra layers into a hasher, see Worley::config_hash.
+    ///! a backend can't be hashed, only whether one is set
+    pub fn hash_config(&self, h: &mut impl Hasher) {
+        self.strength.to_bits().hash(h);
+        self.strength_axes.0.to_bits().hash(h);
+        self.strength_axes.1.to_bits().hash(h);
+        hash_noise(&self.noise, h);
+        for weight in &self.octave_weights {
+            weight.to_bits().hash(h);
+        }
+        self.z_channel_offset.0.to_bits().hash(h);
+        self.z_channel_offset.1.to_bits().hash(h);
+        self.mode.hash(h);
+        self.turbulence.hash(h);
+        self.seed_mode.hash(h);
+        self.backend.is_some().hash(h);
+        self.strength_modulation.is_some().hash(h);
+        if let Some(modulation) = &self.strength_modulation {
+            hash_noise(modulation, h);
+        }
+        self.iterations.hash(h);
+        self.layers.len().hash(h);
+        for layer in &self.layers {
+            layer.hash_config(h);
+        }
+    }
+
+    ///! Err with the reason if this layer (or an extra layer) has values producing NaN or
+    ///! unbounded work, see Worley::validate
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.strength.is_finite() {
+            return Err(format!(
+                "warp strength must be finite, is {}",
+                self.strength
+            ));
+        }
+        if !(self.strength_axes.0.is_finite() && self.strength_axes.1.is_finite()) {
+            return Err(format!(
+                "warp strength_axes must be finite, are {:?}",
+                self.strength_axes
+            ));
+        }
+        for noise in std::iter::once(&self.noise).chain(&self.strength_modulation) {
+            if !noise.frequency.is_finite() {
+                return Err(format!(
+                    "warp frequency must be finite, is {}",
+                    noise.frequency
+                ));
+            }
+            if !FRACTAL_OCTAVES.contains(&noise.fractal_octaves) {
+                return Err(format!(
+                    "warp fractal_octaves must be in {:?}, is {}",
+                    FRACTAL_OCTAVES, noise.fractal_octaves
+                ));
+            }
+        }
+        if self.octave_weights.len() > *FRACTAL_OCTAVES.end() as usize {
+            return Err(format!(
+                "warp octave_weights can have at most {} entries, has {}",
+                FRACTAL_OCTAVES.end(),
+                self.octave_weights.len()
+            ));
+        }
+        if let Some(w) = self.octave_weights.iter().find(|w| !w.is_finite()) {
+            return Err(format!("warp octave_weights must be finite, found {}", w));
+        }
+        for (i, layer) in self.layers.iter().enumerate() {
+            layer
+                .validate()
+                .map_err(|err| format!("layers[{}]: {}", i, err))?;
+        }
+        Ok(())
+    }
+
+    ///! fix what validate complains about (non-finite values reset to the default,
+    ///! octaves clamped), returning a warning per change
+    pub fn sanitize(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if !self.strength.is_finite() {
+            warnings.push(format!("warp strength {} reset to 0", self.strength));
+            self.strength = 0.0;
+        }
+        if !(self.strength_axes.0.is_finite() && self.strength_axes.1.is_finite()) {
+            warnings.push(format!(
+                "warp strength_axes {:?} reset to (1, 1)",
+                self.strength_axes
+            ));
+            self.strength_axes = (1.0, 1.0);
+        }
+        for noise in std::iter::once(&mut self.noise).chain(&mut self.strength_modulation) {
+            if !noise.frequency.is_finite() {
+                let frequency = FastNoise::default().frequency;
+                warnings.push(format!(
+                    "warp frequency {} reset to {}",
+                    noise.frequency, frequency
+                ));
+                noise.frequency = frequency;
+            }
+            let octaves = noise
+                .fractal_octaves
+                .clamp(*FRACTAL_OCTAVES.start(), *FRACTAL_OCTAVES.end());
+            if octaves != noise.fractal_octaves {
+                warnings.push(format!(
+                    "warp fractal_octaves {} clamped to {}",
+                    noise.fractal_octaves, octaves
+                ));
+                noise.fractal_octaves = octaves;
+            }
+        }
+        let max_weights = *FRACTAL_OCTAVES.end() as usize;
+        if self.octave_weights.len() > max_weights {
+            warnings.push(format!(
+                "warp octave_weights truncated from {} to {} entries",
+                self.octave_weights.len(),
+                max_weights
+            ));
+            self.octave_weights.truncate(max_weights);
+        }
+        for w in self.octave_weights.iter_mut().filter(|w| !w.is_finite()) {
+            warnings.push(format!("warp octave weight {} reset to 0", w));
+            *w = 0.0;
+        }
+        for (i, layer) in self.layers.iter_mut().enumerate() {
+            warnings.extend(
+                layer
+                    .sanitize()
+                    .into_iter()
+                    .map(|warning| format!("layers[{}]: {}", i, warning)),
+            );
+        }
+        warnings
+    }
+
+    ///! in WarpSeed::Derived mode, reseed the noises of this layer (and extra layers) from the world seed.
+    ///! called by Worley::set_seed and Worley::rebuild
+    pub fn apply_world_seed(&mut self, world_seed: u64) {
+        let seed = derive_seed(world_seed, seed_domain::WARP);
+        if self.seed_mode == WarpSeed::Derived {
+            self.noise.set_seed(seed);
+            if let Some(modulation) = &mut self.strength_modulation {
+                modulation.set_seed(derive_seed(seed, 1));
+            }
+        }
+        for (i, layer) in self.layers.iter_mut().enumerate() {
+            // every layer gets its own seed, otherwise identical layers would stack up
+            layer.apply_world_seed(derive_seed(seed, i as u64 + 2));
+        }
+    }
+
+    ///! warp by this layer, then by every extra layer
     pub fn warp_coords(&self, x: f32, z: f32) -> (f64, f64) {
-        let nx = self.noise.get_noise(x, z);
-        let nz = self.noise.get_noise(x + 103f32, z);
+        let (mut x, mut z) = self.warp_layer(x, z);
+        for layer in &self.layers {
+            (x, z) = layer.warp_coords(x as f32, z as f32);
+        }
+        (x, z)
+    }
+
+    ///! warp by this layer only, ignoring extra layers
+    pub fn warp_layer(&self, x: f32, z: f32) -> (f64, f64) {
+        let (mut dx, mut dz) = (0.0, 0.0);
+        for _ in 0..self.iterations.max(1) {
+            (dx, dz) = self.displacement(x + dx, z + dz);
+        }
+        ((x + dx) as f64, (z + dz) as f64)
+    }
+
+    ///! single pass displacement of this layer at a point
+    pub fn displacement(&self, x: f32, z: f32) -> (f32, f32) {
+        let (nx, nz) = match self.mode {
+            WarpMode::Offset => {
+                let frequency = self.frequency().abs().max(1e-6);
+                let (ox, oz) = self.z_channel_offset;
+                (
+                    self.sample(x, z),
+                    self.sample(x + ox / frequency, z + oz / frequency),
+                )
+            }
+            WarpMode::Curl => self.curl(x, z),
+        };
+        let strength = self.strength_at(x, z);
         (
-            (x + nx * self.strength) as f64,
-            (z + nz * self.strength) as f64,
+            nx * strength * self.strength_axes.0,
+            nz * strength * self.strength_axes.1,
         )
     }
+
+    // curl of the noise as a 2d stream function: (dn/dz, -dn/dx), via central differences.
+    // divided by frequency, so strength means roughly the same as in Offset mode
+    fn curl(&self, x: f32, z: f32) -> (f32, f32) {
+        let frequency = self.frequency().abs().max(1e-6);
+        let eps = 0.05 / frequency;
+        let dn_dx = (self.sample(x + eps, z) - self.sample(x - eps, z)) / (2.0 * eps);
+        let dn_dz = (self.sample(x, z + eps) - self.sample(x, z - eps)) / (2.0 * eps);
+        (dn_dz / frequency, -dn_dx / frequency)
+    }
+
+    ///! strength at a point, after modulation
+    pub fn strength_at(&self, x: f32, z: f32) -> f32 {
+        match &self.strength_modulation {
+            Some(modulation) => {
+                self.strength * (modulation.get_noise(x, z) * 0.5 + 0.5).clamp(0.0, 1.0)
+            }
+            None => self.strength,
+        }
+    }
+
+    // frequency of what sample_raw samples, the backend's if one is set
+    fn frequency(&self) -> f32 {
+        match &self.backend {
+            Some(backend) => backend.frequency(),
+            None => self.noise.frequency,
+        }
+    }
+
+    #[inline(always)]
+    fn sample_raw(&self, x: f32, z: f32) -> f32 {
+        match &self.backend {
+            Some(backend) => backend.get_noise(x, z),
+            None => self.noise.get_noise(x, z),
+        }
+    }
+
+    fn sample(&self, x: f32, z: f32) -> f32 {
+        let n = match self.octave_weights.is_empty() {
+            true => self.sample_raw(x, z),
+            false => {
+                // offset octaves in noise space, so they don't all share a feature at the origin
+                let octave_offset = 59.3 / self.frequency().abs().max(1e-6);
+                let mut sum = 0.0;
+                let mut norm = 0.0;
+                let mut scale = 1.0;
+                for (i, weight) in self.octave_weights.iter().enumerate() {
+                    let offset = i as f32 * octave_offset;
+                    sum += weight * self.sample_raw(x * scale + offset, z * scale - offset);
+                    norm += weight.abs();
+                    scale *= self.noise.fractal_lacunarity;
+                }
+                sum / norm.max(1e-6)
+            }
+        };
+        match self.turbulence {
+            true => n.abs() * 2.0 - 1.0,
+            false => n,
+        }
+    }
 }
 
-pub fn warp_coords(noise: &FastNoise, strength: f32, x: f32, z: f32) -> (f64, f64) {
+///! single noise warp of a point, the z channel sampled the default z_channel_offset away
+///! in noise space (see WarpSettings::z_channel_offset), so it doesn't follow the x channel
+pub fn warp_coords<N: WarpNoise + ?Sized>(noise: &N, strength: f32, x: f32, z: f32) -> (f64, f64) {
+    let frequency = noise.frequency().abs().max(1e-6);
+    let (ox, oz) = DEFAULT_Z_CHANNEL_OFFSET;
     let nx = noise.get_noise(x, z);
-    let nz = noise.get_noise(x + 103f32, z);
+    let nz = noise.get_noise(x + ox / frequency, z + oz / frequency);
     ((x + nx * strength) as f64, (z + nz * strength) as f64)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // noise value = x coordinate, shows where each channel was sampled
+    struct SampledX {
+        frequency: f32,
+    }
+
+    impl WarpNoise for SampledX {
+        fn get_noise(&self, x: f32, _z: f32) -> f32 {
+            x
+        }
+
+        fn frequency(&self) -> f32 {
+            self.frequency
+        }
+    }
+
+    #[test]
+    fn free_warp_coords_offsets_z_in_noise_space() {
+        for frequency in [0.01, 0.5, 4.0] {
+            let (x, z) = warp_coords(&SampledX { frequency }, 1.0, 2.0, 0.0);
+            assert_eq!(x, 4.0);
+            let expected = 2.0 + DEFAULT_Z_CHANNEL_OFFSET.0 / frequency;
+            assert!((z - expected as f64).abs() < 1e-3, "{} vs {}", z, expected);
+        }
+    }
+
+    #[test]
+    fn backend_frequency_scales_z_offset_and_curl() {
+        let frequency = 0.25;
+        let mut settings = WarpSettings {
+            strength: 1.0,
+            backend: Some(Arc::new(SampledX { frequency })),
+            ..Default::default()
+        };
+        settings.noise.frequency = 8.0;
+
+        let (_, nz) = settings.displacement(2.0, 0.0);
+        let expected = 2.0 + DEFAULT_Z_CHANNEL_OFFSET.0 / frequency;
+        assert!((nz - expected).abs() < 1e-2, "{} vs {}", nz, expected);
+
+        // noise = x: dn/dx = 1, so the curl's z component is -1 / frequency
+        settings.mode = WarpMode::Curl;
+        let (dx, dz) = settings.displacement(2.0, 0.0);
+        assert!(dx.abs() < 1e-3, "{}", dx);
+        assert!((dz + 1.0 / frequency).abs() < 1e-2, "{}", dz);
+    }
+}
@@ -0,0 +1,230 @@
+use bracket_fast_noise::prelude::{FastNoise, FractalType, NoiseType};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{
+    WarpMode, WarpSeed, WarpSettings, default_iterations, default_strength_axes,
+    default_z_channel_offset,
+};
+
+// text formats write the noise seeds next to the noises instead of inside them: seeds are full
+// u64 (derived ones usually exceed i64::MAX), which toml can't store as integers, so they go
+// through seed_serde. a Derived layer's seeds are rederived on load and not written at all.
+// binary formats write every field as is, in WarpSettingsFields' order
+#[derive(Serialize)]
+#[serde(rename = "WarpSettings")]
+struct WarpSettingsRef<'a> {
+    strength: f32,
+    strength_axes: (f32, f32),
+    noise: FastNoise,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::utils::seed_serde::option::serialize"
+    )]
+    noise_seed: Option<u64>,
+    octave_weights: &'a Vec<f32>,
+    z_channel_offset: (f32, f32),
+    mode: WarpMode,
+    turbulence: bool,
+    seed_mode: WarpSeed,
+    strength_modulation: Option<FastNoise>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::utils::seed_serde::option::serialize"
+    )]
+    strength_modulation_seed: Option<u64>,
+    iterations: u8,
+    layers: &'a Vec<WarpSettings>,
+}
+
+impl Serialize for WarpSettings {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut noise = self.noise.clone();
+        let mut strength_modulation = self.strength_modulation.clone();
+        let mut noise_seed = None;
+        let mut strength_modulation_seed = None;
+        if serializer.is_human_readable() {
+            let explicit = self.seed_mode == WarpSeed::Explicit;
+            noise_seed = explicit.then(|| noise.get_seed());
+            noise.set_seed(0);
+            if let Some(modulation) = &mut strength_modulation {
+                strength_modulation_seed = explicit.then(|| modulation.get_seed());
+                modulation.set_seed(0);
+            }
+        }
+        WarpSettingsRef {
+            strength: self.strength,
+            strength_axes: self.strength_axes,
+            noise,
+            noise_seed,
+            octave_weights: &self.octave_weights,
+            z_channel_offset: self.z_channel_offset,
+            mode: self.mode,
+            turbulence: self.turbulence,
+            seed_mode: self.seed_mode,
+            strength_modulation,
+            strength_modulation_seed,
+            iterations: self.iterations,
+            layers: &self.layers,
+        }
+        .serialize(serializer)
+    }
+}
+
+// WarpSettings is deserialized through these shims, every field has a default so files
+// written before a field existed keep loading (see Worley's WorleyData)
+
+// self describing formats (ron, json, toml): the current fields, plus the legacy flat noise
+// fields older saves (e.g. the 3d example's) have instead of `noise`
+#[derive(Deserialize)]
+struct WarpSettingsFile {
+    #[serde(default)]
+    strength: f32,
+    #[serde(default = "default_strength_axes")]
+    strength_axes: (f32, f32),
+    #[serde(default)]
+    noise: Option<FastNoise>,
+    #[serde(default)]
+    octave_weights: Vec<f32>,
+    #[serde(default = "default_z_channel_offset")]
+    z_channel_offset: (f32, f32),
+    #[serde(default)]
+    mode: WarpMode,
+    #[serde(default)]
+    turbulence: bool,
+    // None: Explicit if the legacy noise_seed is there (those files predate Derived), else Derived
+    #[serde(default)]
+    seed_mode: Option<WarpSeed>,
+    #[serde(default)]
+    strength_modulation: Option<FastNoise>,
+    #[serde(default, with = "crate::utils::seed_serde::option")]
+    strength_modulation_seed: Option<u64>,
+    #[serde(default = "default_iterations")]
+    iterations: u8,
+    #[serde(default)]
+    layers: Vec<WarpSettings>,
+
+    // also written by WarpSettingsRef, on top of `noise`
+    #[serde(default, with = "crate::utils::seed_serde::option")]
+    noise_seed: Option<u64>,
+    #[serde(default)]
+    noise_frequency: Option<f32>,
+    #[serde(default)]
+    noise_fractal_lacunarity: Option<f32>,
+    #[serde(default)]
+    noise_fractal_gain: Option<f32>,
+    #[serde(default)]
+    noise_fractal_octaves: Option<i32>,
+    #[serde(default)]
+    noise_noise_type: Option<NoiseType>,
+    #[serde(default)]
+    noise_fractal_type: Option<FractalType>,
+}
+
+impl WarpSettingsFile {
+    // `noise`, or else the legacy fields on top of FastNoise's defaults. noise_seed applies to both
+    fn noise(&mut self) -> FastNoise {
+        let seed = self.noise_seed;
+        let mut noise = self.noise.take().unwrap_or_else(|| self.legacy_noise());
+        if let Some(seed) = seed {
+            noise.set_seed(seed);
+        }
+        noise
+    }
+
+    fn legacy_noise(&self) -> FastNoise {
+        let mut noise = FastNoise::default();
+        if let Some(frequency) = self.noise_frequency {
+            noise.frequency = frequency;
+        }
+        if let Some(lacunarity) = self.noise_fractal_lacunarity {
+            noise.fractal_lacunarity = lacunarity;
+        }
+        if let Some(gain) = self.noise_fractal_gain {
+            noise.set_fractal_gain(gain);
+        }
+        if let Some(octaves) = self.noise_fractal_octaves {
+            noise.fractal_octaves = octaves;
+        }
+        if let Some(noise_type) = self.noise_noise_type {
+            noise.noise_type = noise_type;
+        }
+        if let Some(fractal_type) = self.noise_fractal_type {
+            noise.fractal_type = fractal_type;
+        }
+        noise
+    }
+}
+
+impl From<WarpSettingsFile> for WarpSettings {
+    fn from(mut file: WarpSettingsFile) -> Self {
+        // otherwise apply_world_seed would replace the migrated seed and the file would warp differently
+        let seed_mode = file.seed_mode.unwrap_or(match file.noise_seed {
+            Some(_) => WarpSeed::Explicit,
+            None => WarpSeed::Derived,
+        });
+        Self {
+            noise: file.noise(),
+            strength: file.strength,
+            strength_axes: file.strength_axes,
+            octave_weights: file.octave_weights,
+            z_channel_offset: file.z_channel_offset,
+            mode: file.mode,
+            turbulence: file.turbulence,
+            seed_mode,
+            backend: None,
+            strength_modulation: file.strength_modulation.map(|mut modulation| {
+                if let Some(seed) = file.strength_modulation_seed {
+                    modulation.set_seed(seed);
+                }
+                modulation
+            }),
+            iterations: file.iterations,
+            layers: file.layers,
+        }
+    }
+}
+
+// binary formats (postcard) have no field names, so no legacy layout: exactly the serialized fields
+#[derive(Deserialize)]
+struct WarpSettingsFields {
+    strength: f32,
+    strength_axes: (f32, f32),
+    noise: FastNoise,
+    octave_weights: Vec<f32>,
+    z_channel_offset: (f32, f32),
+    mode: WarpMode,
+    turbulence: bool,
+    seed_mode: WarpSeed,
+    strength_modulation: Option<FastNoise>,
+    iterations: u8,
+    layers: Vec<WarpSettings>,
+}
+
+impl From<WarpSettingsFields> for WarpSettings {
+    fn from(fields: WarpSettingsFields) -> Self {
+        Self {
+            strength: fields.strength,
+            strength_axes: fields.strength_axes,
+            noise: fields.noise,
+            octave_weights: fields.octave_weights,
+            z_channel_offset: fields.z_channel_offset,
+            mode: fields.mode,
+            turbulence: fields.turbulence,
+            seed_mode: fields.seed_mode,
+            backend: None,
+            strength_modulation: fields.strength_modulation,
+            iterations: fields.iterations,
+            layers: fields.layers,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for WarpSettings {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            WarpSettingsFile::deserialize(deserializer).map(Into::into)
+        } else {
+            WarpSettingsFields::deserialize(deserializer).map(Into::into)
+        }
+    }
+}
@@ -1,54 +1,180 @@
 use std::default::Default;
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
+use std::sync::Arc;
 
+#[cfg(feature = "bevy-core")]
+use bevy::reflect::Reflect;
 #[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 
 use tinyvec::TinyVec;
 
 use crate::biome_picker::{BiomePicker, BiomeVariants};
 use crate::distance_fn::DistanceFn;
-use crate::utils::hash_u64;
-use crate::warp::{WarpSettings, warp_coords};
+use crate::utils::cell_point;
+use crate::warp::{CoordWarp, WarpSettings, WarpSpace};
 
-///! a biome picker based on (worley) which is offset by (noise)
+#[cfg(feature = "serde")]
+mod io;
+mod metadata;
+#[cfg(feature = "serde")]
+mod palette_serde;
+mod validate;
+mod warp_grid;
+#[cfg(feature = "binary")]
+pub use io::BINARY_VERSION;
+#[cfg(feature = "serde")]
+pub use io::{WORLEY_EXTENSION, WorleyFormat, WorleyIoError, with_worley_extension};
+pub use metadata::{WORLEY_FORMAT_VERSION, WorleyMetadata};
+pub use validate::MAX_SHARPNESS;
+pub use warp_grid::WarpGrid;
+
+///! a biome picker based on (worley) which is offset by (noise).
+///! with feature="bevy-core" this is Reflect when BiomeT and Picker are TypePath, the picker itself is not reflected.
+///! generic types are not auto registered: `app.register_type::<Worley<MyBiome, MyPicker>>()`.
+///! with feature="serde" it (de)serializes through shims: derived state, custom_warp and
+///! warp backends are not part of the saved config
 #[cfg_attr(
     feature = "serde",
-    derive(Serialize, Deserialize),
-    serde(bound(
-        serialize = "BiomeT: Serialize, Picker: Serialize",
-        deserialize = "BiomeT: Deserialize<'de>, Picker: Deserialize<'de>"
-    ))
+    derive(Deserialize),
+    serde(bound(deserialize = "BiomeT: Deserialize<'de>, Picker: Deserialize<'de>")),
+    serde(from = "WorleyData<BiomeT, Picker>")
 )]
+#[cfg_attr(feature = "bevy-core", derive(Reflect))]
+#[derive(Clone)]
 pub struct Worley<BiomeT, Picker>
 where
     BiomeT: BiomeVariants,
     Picker: BiomePicker<BiomeT> + Default,
 {
     ///! biome picking
+    #[cfg_attr(feature = "bevy-core", reflect(ignore))]
     pub biome_picker: Picker,
     pub zoom: f64,
-    #[cfg_attr(feature = "serde", serde(skip, default = "default_distance_fn"))]
+    ///! derived from distance_fn_config, rebuilt on deserialize
+    ///! (not reflected: after reflection edits call rebuild(), the DebugPlugin does it for you)
+    #[cfg_attr(
+        feature = "bevy-core",
+        reflect(ignore, default = "default_distance_fn")
+    )]
     pub distance_fn: fn(f64, f64) -> f64,
     pub distance_fn_config: DistanceFn,
-    ///! high value: sharper borders, recommended: 0.0 -> 20.0
+    ///! high value: sharper borders, recommended: 0.0 -> 20.0, validate accepts 0.0 -> 64.0
     pub sharpness: f64,
-    ///! how many k biomes to fetch closest
+    ///! how many k biomes to fetch closest, at most 9
     pub k: usize,
+    ///! world seed, prefer set_seed() (or call rebuild() after writing it) so derived warp seeds follow
     pub seed: u64,
     ///! warps coordinate for interesting shapes
     pub warp_settings: WarpSettings,
+    ///! whether warp_settings runs before (World) or after (Cell) dividing by zoom
+    pub warp_space: WarpSpace,
+    ///! if set, replaces the warp_settings based warp entirely (not serialized, set it again after loading)
+    #[cfg_attr(feature = "bevy-core", reflect(ignore))]
+    pub custom_warp: Option<Arc<dyn CoordWarp + Send + Sync>>,
     ///! if set, biomes below this threshold, will not return from Worley::get()
     ///! recommended to be set, defaults to 0.01 = 1%
     pub kill_percent_threshold: Option<f64>,
-    #[cfg_attr(feature = "serde", serde(skip))]
+    ///! srgb overrides of the debug views' biome colors, per BiomeT::variants() index.
+    ///! None (or missing): the biome's own color. doesn't affect get()
+    pub debug_palette: Vec<Option<[f32; 3]>>,
+    ///! provenance written alongside the config, None: no metadata block.
+    ///! doesn't affect get() or config_hash
+    #[cfg_attr(feature = "bevy-core", reflect(ignore))]
+    pub metadata: Option<WorleyMetadata>,
+    #[cfg_attr(feature = "bevy-core", reflect(ignore))]
     pub _phantom: PhantomData<BiomeT>,
 }
 
-// euclidian squared
-#[cfg(feature = "serde")]
+#[cfg(feature = "bevy-core")]
 fn default_distance_fn() -> fn(f64, f64) -> f64 {
-    |dx, dz| dx * dx + dz * dz
+    DistanceFn::EuclideanSquared.to_func()
+}
+
+// deserialization shim: Worley is built from this and then rebuilt,
+// so derived state (distance_fn, derived warp seeds) is never stale after loading.
+// every field falls back to Worley::default's value, so files written
+// before a field existed keep loading
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "Picker: Deserialize<'de> + Default"))]
+struct WorleyData<BiomeT, Picker> {
+    // older saves (e.g. assets/test.worley.ron) named it biome_generator
+    #[serde(default, alias = "biome_generator")]
+    biome_picker: Picker,
+    #[serde(default = "default_zoom")]
+    zoom: f64,
+    // older saves named it distance_fn
+    #[serde(default, alias = "distance_fn")]
+    distance_fn_config: DistanceFn,
+    #[serde(default = "default_sharpness")]
+    sharpness: f64,
+    #[serde(default = "default_k")]
+    k: usize,
+    #[serde(default, with = "crate::utils::seed_serde")]
+    seed: u64,
+    #[serde(default)]
+    warp_settings: WarpSettings,
+    #[serde(default)]
+    warp_space: WarpSpace,
+    // a missing Option is None without this, an explicit `None` still is
+    #[serde(default = "default_kill_percent_threshold")]
+    kill_percent_threshold: Option<f64>,
+    #[serde(default, with = "palette_serde")]
+    debug_palette: Vec<Option<[f32; 3]>>,
+    #[serde(default)]
+    metadata: Option<WorleyMetadata>,
+    #[serde(skip)]
+    _phantom: PhantomData<BiomeT>,
+}
+
+#[cfg(feature = "serde")]
+fn default_zoom() -> f64 {
+    100.0
+}
+
+#[cfg(feature = "serde")]
+fn default_sharpness() -> f64 {
+    20.0
+}
+
+#[cfg(feature = "serde")]
+fn default_k() -> usize {
+    3
+}
+
+#[cfg(feature = "serde")]
+fn default_kill_percent_threshold() -> Option<f64> {
+    Some(0.01)
+}
+
+#[cfg(feature = "serde")]
+impl<BiomeT, Picker> From<WorleyData<BiomeT, Picker>> for Worley<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    fn from(data: WorleyData<BiomeT, Picker>) -> Self {
+        let mut worley = Self {
+            biome_picker: data.biome_picker,
+            zoom: data.zoom,
+            distance_fn: data.distance_fn_config.to_func(),
+            distance_fn_config: data.distance_fn_config,
+            sharpness: data.sharpness,
+            k: data.k,
+            seed: data.seed,
+            warp_settings: data.warp_settings,
+            warp_space: data.warp_space,
+            custom_warp: None,
+            kill_percent_threshold: data.kill_percent_threshold,
+            debug_palette: data.debug_palette,
+            metadata: data.metadata,
+            _phantom: PhantomData,
+        };
+        worley.rebuild();
+        worley
+    }
 }
 
 impl<BiomeT, Picker> Default for Worley<BiomeT, Picker>
@@ -57,9 +183,9 @@ where
     Picker: BiomePicker<BiomeT> + Default,
 {
     fn default() -> Self {
-        let distance_fn_config = DistanceFn::EuclideanSquared;
+        let distance_fn_config = DistanceFn::default();
         let distance_fn = distance_fn_config.to_func();
-        Self {
+        let mut worley = Self {
             distance_fn,
             distance_fn_config,
             biome_picker: Picker::default(),
@@ -67,28 +193,22 @@ where
             sharpness: 20.0,
             k: 3,
             warp_settings: WarpSettings::default(),
+            warp_space: WarpSpace::default(),
+            custom_warp: None,
             _phantom: PhantomData::default(),
             kill_percent_threshold: Some(0.01),
+            debug_palette: Vec::new(),
+            metadata: None,
             seed: 0,
-        }
+        };
+        worley.rebuild();
+        worley
     }
 }
 
-const NEIGHBOR_OFFSETS: [(i32, i32); 9] = [
-    (-1, -1),
-    (-1, 0),
-    (-1, 1),
-    (0, -1),
-    (0, 0),
-    (0, 1),
-    (1, -1),
-    (1, 0),
-    (1, 1),
-];
-
 impl<BiomeT, Picker> Worley<BiomeT, Picker>
 where
-    BiomeT: BiomeVariants + 'static + Default,
+    BiomeT: BiomeVariants,
     Picker: BiomePicker<BiomeT> + Default,
 {
     pub fn set_distance_fn(&mut self, distance_fn: DistanceFn) {
@@ -99,42 +219,236 @@ where
         self.distance_fn_config
     }
 
-    ///! returns a vec of (0: percentage) we use for (1: biome type)
-    pub fn get(&self, x: f64, z: f64) -> TinyVec<[(f64, BiomeT); 3]> {
-        let (x, z) = (x / self.zoom, z / self.zoom);
-        let (x, z) = warp_coords(
-            &self.warp_settings.noise,
-            self.warp_settings.strength,
-            x as f32,
-            z as f32,
-        );
+    ///! set the world seed, and reseed derived warp noise (see WarpSeed)
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.warp_settings.apply_world_seed(seed);
+    }
+
+    ///! hash of everything that changes what get() returns, e.g. to key caches of generated chunks.
+    ///! the picker contributes BiomePicker::config_hash, custom_warp only whether it is set
+    pub fn config_hash(&self) -> u64 {
+        let mut h = fxhash::FxHasher64::default();
+        self.zoom.to_bits().hash(&mut h);
+        self.distance_fn_config.hash(&mut h);
+        self.sharpness.to_bits().hash(&mut h);
+        self.k.hash(&mut h);
+        self.seed.hash(&mut h);
+        self.warp_settings.hash_config(&mut h);
+        self.warp_space.hash(&mut h);
+        self.custom_warp.is_some().hash(&mut h);
+        self.kill_percent_threshold.map(f64::to_bits).hash(&mut h);
+        self.biome_picker.config_hash().hash(&mut h);
+        h.finish()
+    }
+
+    ///! rebuild state derived from other fields (distance_fn from distance_fn_config, derived warp seeds).
+    ///! call after editing pub fields directly
+    pub fn rebuild(&mut self) {
+        self.distance_fn = self.distance_fn_config.to_func();
+        self.warp_settings.apply_world_seed(self.seed);
+    }
+}
+
+///! the cells around a position, see Worley::cell_sample
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CellSample {
+    ///! the position after warp, in cell space
+    pub warped: (f64, f64),
+    ///! nearest non-empty cell
+    pub cell: (i32, i32),
+    ///! feature point of that cell, in cell space
+    pub point: (f64, f64),
+    ///! distance_fn to the nearest feature point (F1)
+    pub f1: f64,
+    ///! distance_fn to the second nearest feature point (F2), F2 - F1 is ~0 on cell borders
+    pub f2: f64,
+}
+
+// d^sharpness for blend, picked once per call: powf is a large part of get(),
+// integer and half integer sharpness (the default 20 included) get by with multiplications
+#[derive(Clone, Copy)]
+enum SharpnessPow {
+    Int(i32),
+    ///! n + 0.5
+    HalfInt(i32),
+    Float(f64),
+}
+
+impl SharpnessPow {
+    #[inline(always)]
+    fn new(sharpness: f64) -> Self {
+        let twice = sharpness * 2.0;
+        if !(0.0..=128.0).contains(&twice) || (twice - twice.round()).abs() > 1e-9 {
+            return SharpnessPow::Float(sharpness);
+        }
+        let twice = twice.round() as i32;
+        match twice % 2 {
+            0 => SharpnessPow::Int(twice / 2),
+            _ => SharpnessPow::HalfInt(twice / 2),
+        }
+    }
+
+    #[inline(always)]
+    fn pow(self, d: f64) -> f64 {
+        match self {
+            SharpnessPow::Int(n) => d.powi(n),
+            SharpnessPow::HalfInt(n) => d.powi(n) * d.sqrt(),
+            SharpnessPow::Float(sharpness) => d.powf(sharpness),
+        }
+    }
+}
+
+///! how many rings of cells around the 3x3 blending searches for non-empty cells
+///! when a sparse picker leaves too many of them empty, see try_pick_biome
+pub const MAX_SEARCH_RADIUS: i32 = 8;
+
+// the most biomes one position blends, what the 3x3 cell neighbourhood holds
+const MAX_K: usize = 9;
+
+impl<BiomeT, Picker> Worley<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + 'static + Default,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    ///! world position -> warped position in cell space (cell n spans n..n+1)
+    pub fn warped_cell_position(&self, x: f64, z: f64) -> (f64, f64) {
+        if let Some(custom_warp) = &self.custom_warp {
+            return custom_warp.warp(x / self.zoom, z / self.zoom);
+        }
+        match self.warp_space {
+            WarpSpace::Cell => {
+                let (x, z) = (x / self.zoom, z / self.zoom);
+                self.warp_settings.warp_coords(x as f32, z as f32)
+            }
+            WarpSpace::World => {
+                let (x, z) = self.warp_settings.warp_coords(x as f32, z as f32);
+                (x / self.zoom, z / self.zoom)
+            }
+        }
+    }
+
+    ///! world position -> warped world position.
+    ///! apply this to companion data (rivers, roads) to warp it exactly like the biomes
+    pub fn warp_point(&self, x: f64, z: f64) -> (f64, f64) {
+        let (wx, wz) = self.warped_cell_position(x, z);
+        (wx * self.zoom, wz * self.zoom)
+    }
+
+    ///! how far (in world units) the warp stage moved this position
+    pub fn warp_displacement(&self, x: f64, z: f64) -> (f64, f64) {
+        let (wx, wz) = self.warp_point(x, z);
+        (wx - x, wz - z)
+    }
 
+    ///! nearest cells of a world position, for debugging and overlays.
+    ///! skips empty cells like get() does, f1/f2 are f64::MAX if there are fewer non-empty cells
+    pub fn cell_sample(&self, x: f64, z: f64) -> CellSample {
+        let warped = self.warped_cell_position(x, z);
+        let (x, z) = warped;
         let cell_x = x.floor() as i32;
         let cell_z = z.floor() as i32;
 
-        let mut candidates: [(f64, BiomeT); 9] = [(0.0, BiomeT::default()); 9];
-        for (i, (dx, dz)) in NEIGHBOR_OFFSETS.iter().enumerate() {
-            let cx = cell_x + dx;
-            let cz = cell_z + dz;
+        let mut sample = CellSample {
+            warped,
+            cell: (cell_x, cell_z),
+            point: (x, z),
+            f1: f64::MAX,
+            f2: f64::MAX,
+        };
+        self.for_each_filled_cell(cell_x, cell_z, 2, |cx, cz, _biome| {
             let (fx, fz) = cell_point(self.seed, cx, cz);
             let dist = (self.distance_fn)(x - fx, z - fz);
-            let biome = self.biome_picker.pick_biome(self.seed, cx, cz);
-            candidates[i] = (dist, biome);
-        }
+            if dist < sample.f1 {
+                sample.f2 = sample.f1;
+                sample.f1 = dist;
+                sample.cell = (cx, cz);
+                sample.point = (fx, fz);
+            } else if dist < sample.f2 {
+                sample.f2 = dist;
+            }
+        });
+        sample
+    }
+
+    ///! the highest weighted biome at a position, None if every cell around it is empty
+    pub fn dominant(&self, x: f64, z: f64) -> Option<BiomeT> {
+        self.get(x, z)
+            .iter()
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, biome)| *biome)
+    }
+
+    ///! returns a vec of (0: percentage) we use for (1: biome type).
+    ///! room for 9 inline, the most biomes one position blends, so no k heap allocates.
+    ///! empty cells are skipped, the search widens past the 3x3 until k non-empty cells are found
+    pub fn get(&self, x: f64, z: f64) -> TinyVec<[(f64, BiomeT); 9]> {
+        self.blend(x, z, self.kill_percent_threshold)
+    }
+
+    ///! get() at an already warped cell space position (see warped_cell_position, WarpGrid)
+    pub fn get_warped(&self, warped: (f64, f64)) -> TinyVec<[(f64, BiomeT); 9]> {
+        self.blend_warped(warped, self.kill_percent_threshold)
+    }
 
-        let k = self.k.min(candidates.len());
-        // select the 3 lowest
-        candidates.select_nth_unstable_by(k, |a, b| a.0.total_cmp(&b.0));
+    ///! how many of the k nearest biomes kill_percent_threshold removes at a position
+    pub fn killed_count(&self, x: f64, z: f64) -> usize {
+        let Some(kill_percent_threshold) = self.kill_percent_threshold else {
+            return 0;
+        };
+        self.blend(x, z, None)
+            .iter()
+            .filter(|(percent, _biome)| *percent <= kill_percent_threshold)
+            .count()
+    }
 
+    #[inline(always)]
+    fn blend(
+        &self,
+        x: f64,
+        z: f64,
+        kill_percent_threshold: Option<f64>,
+    ) -> TinyVec<[(f64, BiomeT); 9]> {
+        self.blend_warped(self.warped_cell_position(x, z), kill_percent_threshold)
+    }
+
+    #[inline(always)]
+    fn blend_warped(
+        &self,
+        (x, z): (f64, f64),
+        kill_percent_threshold: Option<f64>,
+    ) -> TinyVec<[(f64, BiomeT); 9]> {
+        let cell_x = x.floor() as i32;
+        let cell_z = z.floor() as i32;
+
+        let k = self.k.min(MAX_K);
+        // empty cells (picker returned None) are skipped, so the next-nearest cells blend instead.
+        // only spills to the heap when the search had to widen past the 3x3
+        let mut candidates: TinyVec<[(f64, BiomeT); 9]> = TinyVec::new();
+        self.for_each_filled_cell(cell_x, cell_z, k, |cx, cz, biome| {
+            let (fx, fz) = cell_point(self.seed, cx, cz);
+            let dist = (self.distance_fn)(x - fx, z - fz);
+            candidates.push((dist, biome));
+        });
+        let candidates = &mut candidates[..];
+
+        let k = k.min(candidates.len());
+        // select the k lowest
+        if k < candidates.len() {
+            candidates.select_nth_unstable_by(k, |a, b| a.0.total_cmp(&b.0));
+        }
+
+        let sharpness = SharpnessPow::new(self.sharpness);
         let mut sum = 0.0;
-        let mut out = TinyVec::with_capacity(self.k);
-        for (d, biome) in candidates.iter().take(self.k) {
+        // k is at most MAX_K, always fits inline
+        let mut out = TinyVec::new();
+        for (d, biome) in candidates.iter().take(k) {
             // very close, high value
             let w = if *d < 1e-9 {
                 100.0
             } else {
                 // closer to 0, higher weight value
-                1.0 / d.powf(self.sharpness)
+                1.0 / sharpness.pow(*d)
             };
             sum += w;
             out.push((w, *biome));
@@ -145,7 +459,7 @@ where
         }
 
         // remove low percentage biomes
-        if let Some(kill_percent_threshold) = self.kill_percent_threshold {
+        if let Some(kill_percent_threshold) = kill_percent_threshold {
             let len_before = out.len();
             out.retain(|(percent, _biome)| *percent > kill_percent_threshold);
             if out.len() != len_before {
@@ -159,15 +473,150 @@ where
 
         out
     }
+
+    ///! visits the non-empty cells around (cell_x, cell_z): the whole 3x3, then ring by ring
+    ///! outwards until `want` were found or MAX_SEARCH_RADIUS is reached
+    #[inline(always)]
+    fn for_each_filled_cell(
+        &self,
+        cell_x: i32,
+        cell_z: i32,
+        want: usize,
+        mut visit: impl FnMut(i32, i32, BiomeT),
+    ) {
+        let mut found = 0;
+        for radius in 1..=MAX_SEARCH_RADIUS {
+            for dx in -radius..=radius {
+                // radius 1 is the whole 3x3, later rings only their border
+                let step = match radius == 1 || dx.abs() == radius {
+                    true => 1,
+                    false => 2 * radius,
+                };
+                let mut dz = -radius;
+                while dz <= radius {
+                    let (cx, cz) = (cell_x + dx, cell_z + dz);
+                    dz += step;
+                    let Some(biome) = self.biome_picker.try_pick_biome(self.seed, cx, cz) else {
+                        continue;
+                    };
+                    visit(cx, cz, biome);
+                    found += 1;
+                }
+            }
+            if found >= want {
+                return;
+            }
+        }
+    }
 }
 
-// generate a random position seeded from cell position
-#[inline(always)]
-fn cell_point(seed: u64, cell_x: i32, cell_z: i32) -> (f64, f64) {
-    let h1 = hash_u64(seed.wrapping_add(1337), cell_x, cell_z);
-    let h2 = hash_u64(seed.wrapping_add(7331), cell_x, cell_z);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biome_picker::SimpleBiomePicker;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    enum TestBiome {
+        #[default]
+        Plains,
+        Forest,
+    }
+
+    impl BiomeVariants for TestBiome {
+        fn variants() -> &'static [Self] {
+            &[TestBiome::Plains, TestBiome::Forest]
+        }
+    }
+
+    // only every `every`th cell on both axes has a biome, every = 0: none
+    #[derive(Default)]
+    struct GridPicker {
+        every: i32,
+    }
+
+    impl BiomePicker<TestBiome> for GridPicker {
+        fn pick_biome(&self, _seed: u64, cell_x: i32, _cell_z: i32) -> TestBiome {
+            match cell_x.rem_euclid(2) {
+                0 => TestBiome::Plains,
+                _ => TestBiome::Forest,
+            }
+        }
+
+        fn try_pick_biome(&self, seed: u64, cell_x: i32, cell_z: i32) -> Option<TestBiome> {
+            let every = self.every;
+            (every > 0 && cell_x.rem_euclid(every) == 0 && cell_z.rem_euclid(every) == 0)
+                .then(|| self.pick_biome(seed, cell_x, cell_z))
+        }
+    }
+
+    fn sparse(every: i32) -> Worley<TestBiome, GridPicker> {
+        Worley {
+            biome_picker: GridPicker { every },
+            k: 3,
+            kill_percent_threshold: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn empty_3x3_widens_to_k_cells() {
+        // cells 1..=3 are all empty, the ring around them holds (0|4, 0|4)
+        let worley = sparse(4);
+        let blended = worley.get_warped((2.5, 2.5));
+        assert_eq!(blended.len(), 3);
+        let sum: f64 = blended.iter().map(|(w, _biome)| w).sum();
+        assert!((sum - 1.0).abs() < 1e-9);
+
+        let sample = worley.cell_sample(2.5 * worley.zoom, 2.5 * worley.zoom);
+        assert!(sample.f2 < f64::MAX);
+        assert_eq!(sample.cell.0.rem_euclid(4), 0);
+        assert_eq!(sample.cell.1.rem_euclid(4), 0);
+    }
+
+    #[test]
+    fn dense_picker_keeps_the_3x3() {
+        let worley = sparse(1);
+        assert_eq!(worley.get_warped((0.5, 0.5)).len(), 3);
+    }
 
-    let fx = cell_x as f64 + ((h1 & 0xFFFF) as f64 / 65535.0);
-    let fz = cell_z as f64 + ((h2 & 0xFFFF) as f64 / 65535.0);
-    (fx, fz)
+    #[test]
+    fn sharpness_outside_0_to_64_is_clamped() {
+        let mut worley = sparse(1);
+        worley.sharpness = 200.0;
+        assert!(worley.validate().is_err());
+        assert_eq!(worley.sanitize().len(), 1);
+        assert_eq!(worley.sharpness, MAX_SHARPNESS);
+
+        worley.sharpness = -1.0;
+        worley.sanitize();
+        assert_eq!(worley.sharpness, 0.0);
+        assert!(worley.validate().is_ok());
+    }
+
+    #[test]
+    fn single_picker_is_that_biome_everywhere() {
+        let worley = Worley::<TestBiome, SimpleBiomePicker<TestBiome>> {
+            biome_picker: SimpleBiomePicker::Single(TestBiome::Forest),
+            ..Default::default()
+        };
+        for (x, z) in [(0.0, 0.0), (13.7, -250.2), (-9001.5, 42.0), (1e6, 1e6)] {
+            let blended = worley.get(x, z);
+            assert!(!blended.is_empty());
+            assert!(
+                blended
+                    .iter()
+                    .all(|(_w, biome)| *biome == TestBiome::Forest)
+            );
+            let total: f64 = blended.iter().map(|(w, _biome)| w).sum();
+            assert!((total - 1.0).abs() < 1e-9);
+            assert_eq!(worley.dominant(x, z), Some(TestBiome::Forest));
+        }
+    }
+
+    #[test]
+    fn all_empty_stops_at_max_radius() {
+        let worley = sparse(0);
+        assert!(worley.get_warped((0.5, 0.5)).is_empty());
+        assert!(worley.dominant(0.0, 0.0).is_none());
+    }
 }
@@ -0,0 +1,504 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    biome_picker::{BiomePicker, BiomeVariants},
+    worley::{Worley, WorleyMetadata},
+};
+
+///! the file extension Worley files use (the debug plugin and WorleyAssetPlugin read/write it)
+pub const WORLEY_EXTENSION: &str = "worley.ron";
+
+#[derive(Debug)]
+pub enum WorleyIoError {
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Deserialize(ron::de::SpannedError),
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    #[cfg(feature = "binary")]
+    Binary(postcard::Error),
+    ///! the bytes start with a format version this build can't read
+    #[cfg(feature = "binary")]
+    BinaryVersion(u8),
+    #[cfg(feature = "toml")]
+    TomlSerialize(toml::ser::Error),
+    #[cfg(feature = "toml")]
+    TomlDeserialize(toml::de::Error),
+    ///! parsed, but the values make no sense (e.g. `k: 0`, `zoom: 0.0`)
+    Invalid(String),
+}
+
+impl std::fmt::Display for WorleyIoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorleyIoError::Io(err) => write!(f, "could not access worley file: {}", err),
+            WorleyIoError::Serialize(err) => write!(f, "could not serialize worley: {}", err),
+            WorleyIoError::Deserialize(err) => write!(f, "could not parse worley: {}", err),
+            #[cfg(feature = "json")]
+            WorleyIoError::Json(err) => write!(f, "could not (de)serialize worley json: {}", err),
+            #[cfg(feature = "binary")]
+            WorleyIoError::Binary(err) => {
+                write!(f, "could not (de)serialize worley bytes: {}", err)
+            }
+            #[cfg(feature = "binary")]
+            WorleyIoError::BinaryVersion(version) => {
+                write!(f, "unknown worley binary version {}", version)
+            }
+            #[cfg(feature = "toml")]
+            WorleyIoError::TomlSerialize(err) => {
+                write!(f, "could not serialize worley toml: {}", err)
+            }
+            #[cfg(feature = "toml")]
+            WorleyIoError::TomlDeserialize(err) => {
+                write!(f, "could not parse worley toml: {}", err)
+            }
+            WorleyIoError::Invalid(reason) => write!(f, "invalid worley: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for WorleyIoError {}
+
+impl From<std::io::Error> for WorleyIoError {
+    fn from(err: std::io::Error) -> Self {
+        WorleyIoError::Io(err)
+    }
+}
+
+impl From<ron::Error> for WorleyIoError {
+    fn from(err: ron::Error) -> Self {
+        WorleyIoError::Serialize(err)
+    }
+}
+
+impl From<ron::de::SpannedError> for WorleyIoError {
+    fn from(err: ron::de::SpannedError) -> Self {
+        WorleyIoError::Deserialize(err)
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for WorleyIoError {
+    fn from(err: serde_json::Error) -> Self {
+        WorleyIoError::Json(err)
+    }
+}
+
+#[cfg(feature = "binary")]
+impl From<postcard::Error> for WorleyIoError {
+    fn from(err: postcard::Error) -> Self {
+        WorleyIoError::Binary(err)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::ser::Error> for WorleyIoError {
+    fn from(err: toml::ser::Error) -> Self {
+        WorleyIoError::TomlSerialize(err)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for WorleyIoError {
+    fn from(err: toml::de::Error) -> Self {
+        WorleyIoError::TomlDeserialize(err)
+    }
+}
+
+// serialization shim, the fields of WorleyData borrowed from a Worley, with the metadata stamped
+#[derive(Serialize)]
+#[serde(rename = "Worley", bound(serialize = "Picker: Serialize"))]
+struct WorleyRef<'a, Picker> {
+    biome_picker: &'a Picker,
+    zoom: f64,
+    distance_fn_config: crate::distance_fn::DistanceFn,
+    sharpness: f64,
+    k: usize,
+    #[serde(with = "crate::utils::seed_serde")]
+    seed: u64,
+    warp_settings: &'a crate::warp::WarpSettings,
+    warp_space: crate::warp::WarpSpace,
+    kill_percent_threshold: Option<f64>,
+    #[serde(
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "super::palette_serde::serialize"
+    )]
+    debug_palette: &'a Vec<Option<[f32; 3]>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<WorleyMetadata>,
+}
+
+impl<BiomeT, Picker> Serialize for Worley<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants,
+    Picker: BiomePicker<BiomeT> + Default + Serialize,
+{
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        WorleyRef {
+            biome_picker: &self.biome_picker,
+            zoom: self.zoom,
+            distance_fn_config: self.distance_fn_config,
+            sharpness: self.sharpness,
+            k: self.k,
+            seed: self.seed,
+            warp_settings: &self.warp_settings,
+            warp_space: self.warp_space,
+            kill_percent_threshold: self.kill_percent_threshold,
+            debug_palette: &self.debug_palette,
+            metadata: self.metadata.as_ref().map(|m| m.stamped(self.seed)),
+        }
+        .serialize(serializer)
+    }
+}
+
+///! first byte of Worley::to_bytes, bumped when the layout changes
+#[cfg(feature = "binary")]
+pub const BINARY_VERSION: u8 = 1;
+
+// postcard isn't self describing: every field is written, in WorleyData's order
+// (WorleyRef skips an empty debug_palette and missing metadata, which postcard can't read back)
+#[cfg(feature = "binary")]
+#[derive(Serialize)]
+#[serde(bound(serialize = "Picker: Serialize"))]
+struct WorleyBytes<'a, Picker> {
+    biome_picker: &'a Picker,
+    zoom: f64,
+    distance_fn_config: crate::distance_fn::DistanceFn,
+    sharpness: f64,
+    k: usize,
+    seed: u64,
+    warp_settings: &'a crate::warp::WarpSettings,
+    warp_space: crate::warp::WarpSpace,
+    kill_percent_threshold: Option<f64>,
+    debug_palette: &'a Vec<Option<[f32; 3]>>,
+    metadata: Option<WorleyMetadata>,
+}
+
+///! the text formats a Worley file can be in, picked by file extension
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorleyFormat {
+    Ron,
+    #[cfg(feature = "json")]
+    Json,
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+impl WorleyFormat {
+    ///! `.json` and `.toml` (with their features), ron for everything else
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            #[cfg(feature = "json")]
+            Some("json") => WorleyFormat::Json,
+            #[cfg(feature = "toml")]
+            Some("toml") => WorleyFormat::Toml,
+            _ => WorleyFormat::Ron,
+        }
+    }
+}
+
+///! path with the WORLEY_EXTENSION added, if it has no extension
+pub fn with_worley_extension(path: impl AsRef<Path>) -> PathBuf {
+    let path = path.as_ref();
+    match path.extension() {
+        Some(_) => path.to_path_buf(),
+        None => path.with_extension(WORLEY_EXTENSION),
+    }
+}
+
+impl<BiomeT, Picker> Worley<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants + Serialize + for<'de> Deserialize<'de>,
+    Picker: BiomePicker<BiomeT> + Default + Serialize + for<'de> Deserialize<'de>,
+{
+    ///! pretty printed RON, the format the debug plugin saves
+    pub fn to_ron_string(&self) -> Result<String, WorleyIoError> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    ///! parse RON, rebuild the derived state (see serde(from)) and validate
+    pub fn from_ron_str(ron: &str) -> Result<Self, WorleyIoError> {
+        let worley = ron::from_str::<Self>(ron)?;
+        worley.validate().map_err(WorleyIoError::Invalid)?;
+        Ok(worley)
+    }
+
+    ///! pretty printed json
+    #[cfg(feature = "json")]
+    pub fn to_json_string(&self) -> Result<String, WorleyIoError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    ///! parse json, rebuild the derived state and validate
+    #[cfg(feature = "json")]
+    pub fn from_json_str(json: &str) -> Result<Self, WorleyIoError> {
+        let worley = serde_json::from_str::<Self>(json)?;
+        worley.validate().map_err(WorleyIoError::Invalid)?;
+        Ok(worley)
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn to_toml_string(&self) -> Result<String, WorleyIoError> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    ///! parse toml, rebuild the derived state and validate
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(toml: &str) -> Result<Self, WorleyIoError> {
+        let worley = toml::from_str::<Self>(toml)?;
+        worley.validate().map_err(WorleyIoError::Invalid)?;
+        Ok(worley)
+    }
+
+    ///! compact binary encoding (postcard, behind a version byte), for save files and packets.
+    ///! like every format, custom_warp and warp backends are not included
+    #[cfg(feature = "binary")]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WorleyIoError> {
+        let bytes = WorleyBytes {
+            biome_picker: &self.biome_picker,
+            zoom: self.zoom,
+            distance_fn_config: self.distance_fn_config,
+            sharpness: self.sharpness,
+            k: self.k,
+            seed: self.seed,
+            warp_settings: &self.warp_settings,
+            warp_space: self.warp_space,
+            kill_percent_threshold: self.kill_percent_threshold,
+            debug_palette: &self.debug_palette,
+            metadata: self.metadata.as_ref().map(|m| m.stamped(self.seed)),
+        };
+        let mut out = vec![BINARY_VERSION];
+        out.extend(postcard::to_allocvec(&bytes)?);
+        Ok(out)
+    }
+
+    ///! read to_bytes, rebuild the derived state and validate
+    #[cfg(feature = "binary")]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WorleyIoError> {
+        let Some((&version, bytes)) = bytes.split_first() else {
+            return Err(WorleyIoError::Binary(
+                postcard::Error::DeserializeUnexpectedEnd,
+            ));
+        };
+        if version != BINARY_VERSION {
+            return Err(WorleyIoError::BinaryVersion(version));
+        }
+        let data = postcard::from_bytes::<super::WorleyData<BiomeT, Picker>>(bytes)?;
+        let worley = Self::from(data);
+        worley.validate().map_err(WorleyIoError::Invalid)?;
+        Ok(worley)
+    }
+
+    ///! serialize in format
+    pub fn to_format_string(&self, format: WorleyFormat) -> Result<String, WorleyIoError> {
+        match format {
+            WorleyFormat::Ron => self.to_ron_string(),
+            #[cfg(feature = "json")]
+            WorleyFormat::Json => self.to_json_string(),
+            #[cfg(feature = "toml")]
+            WorleyFormat::Toml => self.to_toml_string(),
+        }
+    }
+
+    ///! deserialize and validate from format
+    pub fn from_format_str(text: &str, format: WorleyFormat) -> Result<Self, WorleyIoError> {
+        match format {
+            WorleyFormat::Ron => Self::from_ron_str(text),
+            #[cfg(feature = "json")]
+            WorleyFormat::Json => Self::from_json_str(text),
+            #[cfg(feature = "toml")]
+            WorleyFormat::Toml => Self::from_toml_str(text),
+        }
+    }
+
+    ///! like from_format_str, but whatever validate would reject is clamped or reset instead
+    ///! (see Worley::sanitize), returned next to a warning per change.
+    ///! still Err if sanitizing couldn't fix it (e.g. a WeightedPicker without weights)
+    pub fn from_format_str_sanitized(
+        text: &str,
+        format: WorleyFormat,
+    ) -> Result<(Self, Vec<String>), WorleyIoError> {
+        let mut worley = match format {
+            WorleyFormat::Ron => ron::from_str::<Self>(text)?,
+            #[cfg(feature = "json")]
+            WorleyFormat::Json => serde_json::from_str::<Self>(text)?,
+            #[cfg(feature = "toml")]
+            WorleyFormat::Toml => toml::from_str::<Self>(text)?,
+        };
+        let warnings = worley.sanitize();
+        worley.validate().map_err(WorleyIoError::Invalid)?;
+        Ok((worley, warnings))
+    }
+
+    ///! write to path in the format of its extension (see WorleyFormat::from_path),
+    ///! `.worley.ron` is added if path has no extension. returns the path written
+    pub fn save_to_path(&self, path: impl AsRef<Path>) -> Result<PathBuf, WorleyIoError> {
+        let path = with_worley_extension(path);
+        let text = self.to_format_string(WorleyFormat::from_path(&path))?;
+        std::fs::write(&path, text)?;
+        Ok(path)
+    }
+
+    ///! read path in the format of its extension, `.worley.ron` is added if path has no extension
+    pub fn load_from_path(path: impl AsRef<Path>) -> Result<Self, WorleyIoError> {
+        let path = with_worley_extension(path);
+        Self::from_format_str(
+            &std::fs::read_to_string(&path)?,
+            WorleyFormat::from_path(&path),
+        )
+    }
+
+    ///! load_from_path with from_format_str_sanitized, for hand edited files
+    pub fn load_from_path_sanitized(
+        path: impl AsRef<Path>,
+    ) -> Result<(Self, Vec<String>), WorleyIoError> {
+        let path = with_worley_extension(path);
+        Self::from_format_str_sanitized(
+            &std::fs::read_to_string(&path)?,
+            WorleyFormat::from_path(&path),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::{
+        biome_picker::FixedPicker,
+        warp::{WarpSeed, WarpSettings},
+    };
+
+    #[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+    enum TestBiome {
+        #[default]
+        Plains,
+        Forest,
+        Desert,
+    }
+
+    impl BiomeVariants for TestBiome {
+        fn variants() -> &'static [Self] {
+            &[TestBiome::Plains, TestBiome::Forest, TestBiome::Desert]
+        }
+    }
+
+    type TestWorley = Worley<TestBiome, FixedPicker<TestBiome>>;
+
+    // a seed toml can't store as an integer, a palette with holes and a tuple keyed picker
+    fn sample() -> TestWorley {
+        let mut worley = TestWorley {
+            biome_picker: FixedPicker::new(TestBiome::Plains)
+                .with_cell(0, 0, TestBiome::Forest)
+                .with_cell(-3, 7, TestBiome::Desert),
+            debug_palette: vec![Some([0.1, 0.2, 0.3]), None, Some([1.0, 0.5, 0.0])],
+            metadata: Some(WorleyMetadata::new("tests", "round trip")),
+            ..Default::default()
+        };
+        worley.warp_settings.strength = 0.4;
+        let mut layer = WarpSettings::default();
+        layer.strength = 0.1;
+        layer.seed_mode = WarpSeed::Explicit;
+        layer.noise.set_seed(u64::MAX - 3);
+        worley.warp_settings.layers.push(layer);
+        worley.set_seed(u64::MAX - 12345);
+        worley
+    }
+
+    fn assert_round_trip(original: &TestWorley, loaded: &TestWorley) {
+        assert_eq!(loaded.seed, original.seed);
+        assert_eq!(loaded.config_hash(), original.config_hash());
+        assert_eq!(loaded.debug_palette, original.debug_palette);
+        assert_eq!(loaded.biome_picker.cells, original.biome_picker.cells);
+        assert_eq!(loaded.biome_picker.default, original.biome_picker.default);
+        assert_eq!(
+            loaded.warp_settings.layers[0].noise.get_seed(),
+            original.warp_settings.layers[0].noise.get_seed()
+        );
+        assert_eq!(
+            loaded.metadata,
+            original.metadata.as_ref().map(|m| m.stamped(original.seed))
+        );
+        for (x, z) in [(0.0, 0.0), (123.4, -56.7), (-310.0, 705.5)] {
+            assert_eq!(loaded.get(x, z), original.get(x, z));
+        }
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        let worley = sample();
+        let text = worley.to_ron_string().unwrap();
+        assert_round_trip(&worley, &TestWorley::from_ron_str(&text).unwrap());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_round_trip() {
+        let worley = sample();
+        let text = worley.to_json_string().unwrap();
+        assert_round_trip(&worley, &TestWorley::from_json_str(&text).unwrap());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_round_trip() {
+        let worley = sample();
+        let text = worley.to_toml_string().unwrap();
+        assert_round_trip(&worley, &TestWorley::from_toml_str(&text).unwrap());
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn bytes_round_trip() {
+        let worley = sample();
+        let bytes = worley.to_bytes().unwrap();
+        assert_round_trip(&worley, &TestWorley::from_bytes(&bytes).unwrap());
+    }
+
+    #[test]
+    fn legacy_asset_loads() {
+        let worley =
+            Worley::<TestBiome, crate::biome_picker::SimpleBiomePicker<TestBiome>>::load_from_path(
+                concat!(env!("CARGO_MANIFEST_DIR"), "/assets/test.worley.ron"),
+            )
+            .unwrap();
+        assert!(matches!(
+            worley.biome_picker,
+            crate::biome_picker::SimpleBiomePicker::Any
+        ));
+        assert_eq!(worley.zoom, 40.0);
+        assert_eq!(
+            worley.distance_fn_config,
+            crate::distance_fn::DistanceFn::Chebyshev
+        );
+        // the flat noise_seed is kept, not replaced by a seed derived from the world seed
+        assert_eq!(worley.warp_settings.seed_mode, WarpSeed::Explicit);
+        assert_eq!(worley.warp_settings.noise.get_seed(), 0);
+        assert_eq!(worley.warp_settings.noise.frequency, 0.7);
+        assert_eq!(worley.warp_settings.noise.fractal_octaves, 5);
+    }
+
+    #[test]
+    fn older_ron_layouts_load() {
+        let worley = TestWorley::from_ron_str(
+            "(
+                biome_picker: (cells: {(0, 0): Forest}, default: Desert),
+                seed: 42,
+                debug_palette: [None, Some((0.5, 0.5, 0.5))],
+            )",
+        )
+        .unwrap();
+        assert_eq!(worley.seed, 42);
+        assert_eq!(worley.debug_palette, vec![None, Some([0.5, 0.5, 0.5])]);
+        assert_eq!(
+            worley.biome_picker.cells.get(&(0, 0)),
+            Some(&TestBiome::Forest)
+        );
+    }
+}
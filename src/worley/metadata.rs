@@ -0,0 +1,50 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+///! bumped when the saved layout of a Worley changes, see WorleyMetadata::format_version
+pub const WORLEY_FORMAT_VERSION: u32 = 1;
+
+///! provenance saved alongside a Worley's config (see Worley::metadata).
+///! crate_version, format_version and seed are refreshed every time the Worley is serialized,
+///! created, author and description are kept as they are
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct WorleyMetadata {
+    ///! version of worley_biomes that wrote the file
+    pub crate_version: String,
+    ///! WORLEY_FORMAT_VERSION of the writer
+    pub format_version: u32,
+    ///! unix seconds, when the metadata was created
+    pub created: u64,
+    pub author: String,
+    pub description: String,
+    ///! the Worley's seed when it was written
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::seed_serde"))]
+    pub seed: u64,
+}
+
+impl WorleyMetadata {
+    ///! created now, by this crate version
+    pub fn new(author: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            format_version: WORLEY_FORMAT_VERSION,
+            created: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |t| t.as_secs()),
+            author: author.into(),
+            description: description.into(),
+            seed: 0,
+        }
+    }
+
+    ///! a copy with the writer's crate and format version, and seed
+    pub fn stamped(&self, seed: u64) -> Self {
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            format_version: WORLEY_FORMAT_VERSION,
+            seed,
+            ..self.clone()
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{Error, SeqAccess, Visitor},
+    ser::SerializeSeq,
+};
+
+// `#[serde(with = "palette_serde")]` for Worley::debug_palette: toml has no null, so human
+// readable formats write each slot as a list, `[]` for None and `[r, g, b]` for an override.
+// loading also accepts the older `None`/`Some((r, g, b))` (and json null).
+// binary formats keep the plain Vec<Option<[f32; 3]>>
+
+pub fn serialize<S: Serializer>(
+    palette: &[Option<[f32; 3]>],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if !serializer.is_human_readable() {
+        return palette.serialize(serializer);
+    }
+    let mut seq = serializer.serialize_seq(Some(palette.len()))?;
+    for slot in palette {
+        seq.serialize_element(slot.as_ref().map_or(&[][..], |rgb| &rgb[..]))?;
+    }
+    seq.end()
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Option<[f32; 3]>>, D::Error> {
+    if !deserializer.is_human_readable() {
+        return Vec::deserialize(deserializer);
+    }
+    Ok(Vec::<Slot>::deserialize(deserializer)?
+        .into_iter()
+        .map(|slot| slot.0)
+        .collect())
+}
+
+struct Slot(Option<[f32; 3]>);
+
+impl<'de> Deserialize<'de> for Slot {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(SlotVisitor).map(Slot)
+    }
+}
+
+struct SlotVisitor;
+
+impl<'de> Visitor<'de> for SlotVisitor {
+    type Value = Option<[f32; 3]>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("[] or [r, g, b]")
+    }
+
+    fn visit_none<E: Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        <[f32; 3]>::deserialize(deserializer).map(Some)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut rgb = [0.0; 3];
+        let mut len = 0;
+        while let Some(channel) = seq.next_element::<f32>()? {
+            if len == 3 {
+                return Err(A::Error::invalid_length(4, &self));
+            }
+            rgb[len] = channel;
+            len += 1;
+        }
+        match len {
+            0 => Ok(None),
+            3 => Ok(Some(rgb)),
+            _ => Err(A::Error::invalid_length(len, &self)),
+        }
+    }
+}
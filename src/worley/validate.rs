@@ -0,0 +1,91 @@
+use crate::{
+    biome_picker::{BiomePicker, BiomeVariants},
+    worley::Worley,
+};
+
+///! highest sharpness validate accepts, past it d^sharpness under/overflows and borders are hard anyway
+pub const MAX_SHARPNESS: f64 = 64.0;
+
+impl<BiomeT, Picker> Worley<BiomeT, Picker>
+where
+    BiomeT: BiomeVariants,
+    Picker: BiomePicker<BiomeT> + Default,
+{
+    ///! Err with the reason if the values produce garbage (NaN weights, no cells, panics),
+    ///! checks the warp settings and the picker too. the loaders run this on every file
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.zoom.is_finite() && self.zoom > 0.0) {
+            return Err(format!("zoom must be > 0, is {}", self.zoom));
+        }
+        if self.k < 1 {
+            return Err("k must be >= 1, is 0".to_string());
+        }
+        if !(0.0..=MAX_SHARPNESS).contains(&self.sharpness) {
+            return Err(format!(
+                "sharpness must be in 0..={}, is {}",
+                MAX_SHARPNESS, self.sharpness
+            ));
+        }
+        if let Some(threshold) = self.kill_percent_threshold
+            && !(0.0..1.0).contains(&threshold)
+        {
+            return Err(format!(
+                "kill_percent_threshold must be in 0..1, is {}",
+                threshold
+            ));
+        }
+        self.warp_settings.validate()?;
+        self.biome_picker
+            .validate()
+            .map_err(|err| format!("biome_picker: {}", err))
+    }
+
+    ///! clamp or reset (to Worley::default's) whatever validate would complain about,
+    ///! returning a warning per change. for hand edited files that should load anyway
+    pub fn sanitize(&mut self) -> Vec<String> {
+        let defaults = Self::default();
+        let mut warnings = Vec::new();
+        if !(self.zoom.is_finite() && self.zoom > 0.0) {
+            warnings.push(format!("zoom {} reset to {}", self.zoom, defaults.zoom));
+            self.zoom = defaults.zoom;
+        }
+        if self.k < 1 {
+            warnings.push("k 0 clamped to 1".to_string());
+            self.k = 1;
+        }
+        if self.sharpness.is_nan() {
+            warnings.push(format!(
+                "sharpness {} reset to {}",
+                self.sharpness, defaults.sharpness
+            ));
+            self.sharpness = defaults.sharpness;
+        } else if !(0.0..=MAX_SHARPNESS).contains(&self.sharpness) {
+            let clamped = self.sharpness.clamp(0.0, MAX_SHARPNESS);
+            warnings.push(format!(
+                "sharpness {} clamped to {}",
+                self.sharpness, clamped
+            ));
+            self.sharpness = clamped;
+        }
+        if let Some(threshold) = self.kill_percent_threshold
+            && !(0.0..1.0).contains(&threshold)
+        {
+            warnings.push(format!(
+                "kill_percent_threshold {} reset to {:?}",
+                threshold, defaults.kill_percent_threshold
+            ));
+            self.kill_percent_threshold = defaults.kill_percent_threshold;
+        }
+        warnings.extend(self.warp_settings.sanitize());
+        warnings.extend(
+            self.biome_picker
+                .sanitize()
+                .into_iter()
+                .map(|warning| format!("biome_picker: {}", warning)),
+        );
+        if !warnings.is_empty() {
+            self.rebuild();
+        }
+        warnings
+    }
+}
@@ -0,0 +1,85 @@
+use tinyvec::TinyVec;
+
+use crate::{
+    biome_picker::{BiomePicker, BiomeVariants},
+    worley::Worley,
+};
+
+///! warped_cell_position evaluated every `step` samples of a regular sample grid and bilinearly
+///! interpolated in between. trades warp detail below step * spacing for roughly step² fewer
+///! warp noise evaluations, which are most of the cost of filling a region.
+///! exact where the warp is (close to) linear over a step, e.g. without warp
+pub struct WarpGrid {
+    step: u32,
+    ///! lattice points per row (z direction)
+    lattice_z: u32,
+    ///! warped cell space positions, row major (x * lattice_z + z)
+    warped: Vec<(f64, f64)>,
+}
+
+impl WarpGrid {
+    ///! a grid of size.0 x size.1 samples, sample (ix, iz) at world origin + (ix, iz) * spacing
+    pub fn new<BiomeT, Picker>(
+        worley: &Worley<BiomeT, Picker>,
+        origin: (f64, f64),
+        spacing: f64,
+        size: (u32, u32),
+        step: u32,
+    ) -> Self
+    where
+        BiomeT: BiomeVariants + Default + 'static,
+        Picker: BiomePicker<BiomeT> + Default,
+    {
+        let step = step.max(1);
+        let lattice = |samples: u32| samples.saturating_sub(1).div_ceil(step) + 1;
+        let (lattice_x, lattice_z) = (lattice(size.0), lattice(size.1));
+        let mut warped = Vec::with_capacity((lattice_x * lattice_z) as usize);
+        for gx in 0..lattice_x {
+            for gz in 0..lattice_z {
+                warped.push(worley.warped_cell_position(
+                    origin.0 + (gx * step) as f64 * spacing,
+                    origin.1 + (gz * step) as f64 * spacing,
+                ));
+            }
+        }
+        Self {
+            step,
+            lattice_z,
+            warped,
+        }
+    }
+
+    ///! interpolated warped cell space position of sample (ix, iz)
+    pub fn warped(&self, ix: u32, iz: u32) -> (f64, f64) {
+        let (gx, gz) = (ix / self.step, iz / self.step);
+        let (tx, tz) = (
+            (ix % self.step) as f64 / self.step as f64,
+            (iz % self.step) as f64 / self.step as f64,
+        );
+        let at = |gx: u32, gz: u32| self.warped[(gx * self.lattice_z + gz) as usize];
+        // t is 0 on the last lattice point, the next one isn't needed (and may not exist)
+        let gx1 = if tx > 0.0 { gx + 1 } else { gx };
+        let gz1 = if tz > 0.0 { gz + 1 } else { gz };
+        let lerp =
+            |a: (f64, f64), b: (f64, f64), t: f64| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+        lerp(
+            lerp(at(gx, gz), at(gx, gz1), tz),
+            lerp(at(gx1, gz), at(gx1, gz1), tz),
+            tx,
+        )
+    }
+
+    ///! Worley::get of sample (ix, iz), with the interpolated warp
+    pub fn get<BiomeT, Picker>(
+        &self,
+        worley: &Worley<BiomeT, Picker>,
+        ix: u32,
+        iz: u32,
+    ) -> TinyVec<[(f64, BiomeT); 9]>
+    where
+        BiomeT: BiomeVariants + Default + 'static,
+        Picker: BiomePicker<BiomeT> + Default,
+    {
+        worley.get_warped(self.warped(ix, iz))
+    }
+}
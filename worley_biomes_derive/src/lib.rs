@@ -0,0 +1,61 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, parse_macro_input};
+
+///! generates `BiomeVariants::variants()` for a fieldless enum, listing every variant in declaration order
+#[proc_macro_derive(BiomeVariants)]
+pub fn derive_biome_variants(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Enum(data) = &input.data else {
+        return syn::Error::new_spanned(
+            &input.ident,
+            "BiomeVariants can only be derived for enums",
+        )
+        .to_compile_error()
+        .into();
+    };
+
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return syn::Error::new_spanned(
+                variant,
+                "BiomeVariants can only be derived for fieldless enums",
+            )
+            .to_compile_error()
+            .into();
+        }
+        let ident = &variant.ident;
+        variants.push(quote! { Self::#ident });
+    }
+
+    quote! {
+        impl #impl_generics ::worley_biomes::biome_picker::BiomeVariants for #name #ty_generics #where_clause {
+            fn variants() -> &'static [Self] {
+                &[#(#variants),*]
+            }
+        }
+    }
+    .into()
+}
+
+///! generates `DebugColor::get_color()` with `bevy::traits::auto_debug_color`, a hue per variant
+///! evenly spaced by its position in `BiomeVariants::variants()`. needs BiomeVariants (derived or not)
+#[proc_macro_derive(DebugColor)]
+pub fn derive_debug_color(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::worley_biomes::bevy::traits::DebugColor<#name #ty_generics> for #name #ty_generics #where_clause {
+            fn get_color(&self) -> ::bevy::color::Srgba {
+                ::worley_biomes::bevy::traits::auto_debug_color(self)
+            }
+        }
+    }
+    .into()
+}